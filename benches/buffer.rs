@@ -0,0 +1,86 @@
+#![feature(test)]
+
+extern crate test;
+extern crate king;
+
+use test::Bencher;
+
+use king::buffer::Buffer;
+
+const LINES: usize = 200_000;
+
+fn text() -> String {
+    let mut text = String::new();
+
+    for n in 0..LINES {
+        if n > 0 {
+            text.push('\n');
+        }
+        text.push_str(&format!("line number {} of a generated file", n));
+    }
+
+    text
+}
+
+// Baseline: the `Vec<String>` line storage the buffer used before the rope.
+
+#[bench]
+fn vec_break_line_near_top(b: &mut Bencher) {
+    let mut contents = text().lines().map(|line| line.to_owned()).collect::<Vec<_>>();
+
+    b.iter(|| {
+        let rest = contents[1].split_off(5);
+        contents.insert(2, rest);
+        let line = contents.remove(2);
+        contents[1].push_str(&line);
+    });
+}
+
+#[bench]
+fn vec_insert_line_near_top(b: &mut Bencher) {
+    let mut contents = text().lines().map(|line| line.to_owned()).collect::<Vec<_>>();
+
+    b.iter(|| {
+        contents.insert(1, String::new());
+        contents.remove(1);
+    });
+}
+
+#[bench]
+fn rope_break_line_near_top(b: &mut Bencher) {
+    let mut buffer = Buffer::from(&text()[..]);
+
+    b.iter(|| {
+        buffer.break_line(1, 5).unwrap();
+        buffer.join_lines(1).unwrap();
+    });
+}
+
+#[bench]
+fn rope_insert_line_near_top(b: &mut Bencher) {
+    let mut buffer = Buffer::from(&text()[..]);
+
+    b.iter(|| {
+        buffer.add_line(1).unwrap();
+        buffer.delete_range((1, 0), (2, 0)).unwrap();
+    });
+}
+
+#[bench]
+fn rope_line_lookup(b: &mut Bencher) {
+    let buffer = Buffer::from(&text()[..]);
+
+    b.iter(|| {
+        test::black_box(buffer.line(LINES / 2).len())
+    });
+}
+
+#[bench]
+fn rope_delete_char_near_top(b: &mut Bencher) {
+    let mut buffer = Buffer::from(&text()[..]);
+
+    b.iter(|| {
+        buffer.delete_char_at(1, 0).unwrap();
+        buffer.insert_char(1, 0, 'l').unwrap();
+    });
+}
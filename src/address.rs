@@ -0,0 +1,161 @@
+use error::{Error, Result};
+use search::Search;
+
+/// What an address counts from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Base {
+    /// A line number, counting from 1.
+    Number(usize),
+    /// `.`, the cursor line.
+    Current,
+    /// `$`, the last line.
+    Last,
+    /// `'x`, the line of mark `x`, like `'<` for the start of the last
+    /// visual selection.
+    Mark(char),
+    /// `/pattern/` or `?pattern?`, the next line with a match after the
+    /// cursor line, or the previous one before it.
+    Search(Search),
+}
+
+/// A line given in an ex command, like `.+2` or `/end/-1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Address {
+    pub base: Base,
+    pub offset: isize,
+}
+
+impl Address {
+    pub fn new(base: Base) -> Address {
+        Address { base: base, offset: 0 }
+    }
+
+    /// Parses the address at the start of `text`, if there's one, giving
+    /// back the rest. An offset on its own counts from the cursor line.
+    fn parse(text: &str) -> Result<(Option<Address>, &str)> {
+        let (base, mut rest) = match text.chars().next() {
+            Some(c) if c.is_ascii_digit() => {
+                let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+                (Some(Base::Number(parse_number(&text[..end])?)), &text[end..])
+            },
+            Some('.') => (Some(Base::Current), &text[1..]),
+            Some('$') => (Some(Base::Last), &text[1..]),
+            Some('\'') => match text[1..].chars().next() {
+                Some(name) => (Some(Base::Mark(name)), &text[1 + name.len_utf8()..]),
+                None => return Err(Error::InvalidRange),
+            },
+            Some(delimiter @ '/') | Some(delimiter @ '?') => {
+                let (pattern, rest) = delimited(&text[1..], delimiter);
+                (Some(Base::Search(Search::new(&pattern, delimiter == '?'))), rest)
+            },
+            _ => (None, text),
+        };
+
+        let mut offset = 0;
+        let mut has_offset = false;
+
+        while rest.starts_with('+') || rest.starts_with('-') {
+            let sign = if rest.starts_with('-') { -1 } else { 1 };
+            rest = &rest[1..];
+
+            // `+` and `-` without a number stand for 1
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let number = if end == 0 { 1 } else { parse_number(&rest[..end])? };
+            rest = &rest[end..];
+
+            offset += sign * number as isize;
+            has_offset = true;
+        }
+
+        let address = match base {
+            Some(base) => Some(Address { base: base, offset: offset }),
+            None if has_offset => Some(Address { base: Base::Current, offset: offset }),
+            None => None,
+        };
+
+        Ok((address, rest))
+    }
+}
+
+/// Lines an ex command works on, from `start` to `end`. Without one,
+/// commands work on the cursor line, or on the whole buffer for some.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineRange {
+    pub start: Address,
+    pub end: Address,
+    /// Whether `end` counts from `start` rather than the cursor line, as
+    /// after `;`.
+    pub from_start: bool,
+}
+
+impl LineRange {
+    /// `%`, every line of the buffer.
+    pub fn all() -> LineRange {
+        LineRange {
+            start: Address::new(Base::Number(1)),
+            end: Address::new(Base::Last),
+            from_start: false,
+        }
+    }
+
+    /// Parses the range at the start of the text of a command line, after
+    /// its `:`, giving back the command that follows. A range is one
+    /// address, or two separated by a comma where a missing one stands for
+    /// the cursor line. With a semicolon instead, the second one counts from
+    /// the first.
+    pub fn parse(text: &str) -> Result<(Option<LineRange>, &str)> {
+        if let Some(rest) = text.strip_prefix('%') {
+            return Ok((Some(LineRange::all()), rest.trim_start()));
+        }
+
+        let (start, rest) = Address::parse(text)?;
+
+        if !rest.starts_with(',') && !rest.starts_with(';') {
+            let range = start.map(|start| LineRange { start: start.clone(), end: start, from_start: false });
+            return Ok((range, rest.trim_start()));
+        }
+
+        let from_start = rest.starts_with(';');
+        let (end, rest) = Address::parse(&rest[1..])?;
+        let current = || Address::new(Base::Current);
+        let range = LineRange {
+            start: start.unwrap_or_else(current),
+            end: end.unwrap_or_else(current),
+            from_start: from_start,
+        };
+
+        Ok((Some(range), rest.trim_start()))
+    }
+}
+
+fn parse_number(text: &str) -> Result<usize> {
+    text.parse().map_err(|_| Error::InvalidRange)
+}
+
+/// Splits `text` after the first `delimiter` that isn't escaped, giving
+/// what comes before it without the backslashes escaping the delimiter.
+/// The closing delimiter can be left out at the end of the command.
+fn delimited(text: &str, delimiter: char) -> (String, &str) {
+    let mut pattern = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c == delimiter {
+            return (pattern, &text[i + c.len_utf8()..]);
+        }
+
+        pattern.push(c);
+        if c == '\\' {
+            match chars.next() {
+                Some((_, next)) if next == delimiter => {
+                    pattern.pop();
+                    pattern.push(next);
+                },
+                Some((_, next)) => pattern.push(next),
+                None => (),
+            }
+        }
+    }
+
+    (pattern, "")
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use input::Key;
+
+/// How a cell is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Attributes {
+    pub reverse: bool,
+    pub bold: bool,
+}
+
+impl Attributes {
+    pub fn normal() -> Attributes {
+        Attributes::default()
+    }
+
+    pub fn reverse() -> Attributes {
+        Attributes { reverse: true, ..Attributes::default() }
+    }
+}
+
+/// A screen the ui draws on and reads keys from. Coordinates are zero based
+/// rows and columns, and a grapheme takes as many columns as its display
+/// width.
+pub trait Backend {
+    /// Height and width of the screen.
+    fn size(&self) -> (usize, usize);
+
+    fn clear(&mut self);
+
+    /// Draws `grapheme` with its first column at `(y, x)`.
+    fn put(&mut self, y: usize, x: usize, grapheme: &str, attributes: Attributes);
+
+    fn move_cursor(&mut self, y: usize, x: usize);
+
+    /// Passes an escape sequence like OSC 52 straight to the terminal.
+    fn write_escape_sequence(&mut self, sequence: &str);
+
+    /// Makes everything drawn since the last call visible.
+    fn flush(&mut self);
+
+    /// Waits for a key, giving up after `timeout` if there is one.
+    fn read_key(&mut self, timeout: Option<Duration>) -> Option<Key>;
+}
@@ -1,13 +1,63 @@
-use std::io;
+extern crate ropey;
+
+use self::ropey::{Rope, RopeBuilder};
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::min;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write, BufWriter, ErrorKind};
+use std::io::{self, Read, Write, BufWriter, ErrorKind};
+use std::rc::Rc;
 
+use encoding::{self, Decoder, Encoding};
 use error::{Error, Result};
+use file::{self, Written};
+use history::{Edit, History};
+use search::Haystack;
 use unicode;
 
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FileFormat {
+    Unix,
+    Dos,
+}
+
+impl FileFormat {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            FileFormat::Unix => "unix",
+            FileFormat::Dos => "dos",
+        }
+    }
+
+    fn line_ending(&self) -> &'static str {
+        match *self {
+            FileFormat::Unix => "\n",
+            FileFormat::Dos => "\r\n",
+        }
+    }
+}
+
 pub struct Buffer {
     filename: Option<String>,
-    contents: Vec<String>,
+    contents: Rope,
+    history: History,
+    modified: bool,
+    /// Whether the file format or encoding changed since the buffer was
+    /// written. Undo doesn't revert them, so this is kept apart.
+    options_modified: bool,
+    changes: usize,
+    saved_seq: usize,
+    format: FileFormat,
+    eol: bool,
+    bom: bool,
+    encoding: Encoding,
+    invalid_bytes: usize,
+    /// Lines of the marks set with `m{a-z}`.
+    marks: HashMap<char, usize>,
+    /// The text searches go through, kept until the contents change.
+    haystack: RefCell<Option<Rc<Haystack>>>,
 }
 
 impl Default for Buffer {
@@ -16,45 +66,180 @@ impl Default for Buffer {
     }
 }
 
+impl<'a> From<&'a str> for Buffer {
+    fn from(text: &'a str) -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.contents = Rope::from_str(text);
+        buffer
+    }
+}
+
 impl Buffer {
     pub fn new() -> Buffer {
         Buffer {
             filename: None,
-            contents: vec![String::new()],
+            contents: Rope::new(),
+            history: History::new(),
+            modified: false,
+            options_modified: false,
+            changes: 0,
+            saved_seq: 0,
+            format: FileFormat::Unix,
+            eol: true,
+            bom: false,
+            encoding: Encoding::Utf8,
+            invalid_bytes: 0,
+            marks: HashMap::new(),
+            haystack: RefCell::new(None),
         }
     }
 
     pub fn for_file(filename: &str) -> Result<Buffer> {
-        Ok(Buffer {
-            filename: Some(filename.to_owned()),
-            contents: load_file(filename)?,
-        })
+        let mut buffer = Buffer::new();
+        buffer.filename = Some(filename.to_owned());
+
+        if let Some(bytes) = load_file(filename)? {
+            buffer.load(bytes);
+        }
+
+        Ok(buffer)
+    }
+
+    /// Fills the buffer from the raw contents of a file, remembering its
+    /// encoding, BOM, line endings and whether it ends with a newline so
+    /// `write` can reproduce them. The text is decoded a chunk at a time,
+    /// so it's only held whole in the rope.
+    fn load(&mut self, bytes: Vec<u8>) {
+        let (encoding, bom) = encoding::detect(&bytes);
+        let body = if bom { &bytes[encoding.bom().len()..] } else { &bytes[..] };
+
+        // whether the lines end in CRLF is only known at the end, so the
+        // body is decoded once to find out and again to fill the rope
+        let mut endings = LineEndings::default();
+        decode_chunks(encoding, body, |piece| endings.count(piece));
+
+        let mut builder = ContentsBuilder::new(endings.format());
+        self.invalid_bytes = decode_chunks(encoding, body, |piece| builder.append(piece));
+
+        self.encoding = encoding;
+        self.bom = bom;
+        self.format = endings.format();
+
+        let (contents, eol) = builder.finish();
+        self.contents = contents;
+        self.eol = eol;
+        *self.haystack.get_mut() = None;
     }
 
     pub fn filename(&self) -> Option<&str> {
         self.filename.as_ref().map(|s| &s[..])
     }
 
+    /// Whether the buffer has changed since it was loaded or last written.
+    pub fn is_modified(&self) -> bool {
+        self.modified || self.options_modified
+    }
+
+    /// Number of changes made since the buffer was loaded or last written.
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+
+    /// Where the buffer is in its undo history, which moves with every
+    /// committed change, undo and redo.
+    pub fn seq(&self) -> usize {
+        self.history.seq()
+    }
+
+    pub fn file_format(&self) -> FileFormat {
+        self.format
+    }
+
+    pub fn set_file_format(&mut self, format: FileFormat) {
+        if self.format != format {
+            self.format = format;
+            self.options_modified = true;
+            self.changes += 1;
+        }
+    }
+
+    pub fn set_mark(&mut self, name: char, line: usize) {
+        self.marks.insert(name, line);
+    }
+
+    pub fn mark(&self, name: char) -> Option<usize> {
+        self.marks.get(&name).cloned()
+    }
+
+    /// The lines joined for searching, made again only after a change.
+    pub fn haystack(&self) -> Rc<Haystack> {
+        self.haystack.borrow_mut().get_or_insert_with(|| Rc::new(Haystack::new(self))).clone()
+    }
+
+    /// Whether the last line ends with a newline.
+    pub fn has_eol(&self) -> bool {
+        self.eol
+    }
+
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Changes the encoding used when writing the buffer. UTF-16 is always
+    /// written with a BOM so it can be detected when reading it back.
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        if self.encoding == encoding {
+            return;
+        }
+
+        if encoding.is_utf16() {
+            self.bom = true;
+        } else if encoding == Encoding::Latin1 || self.encoding.is_utf16() {
+            self.bom = false;
+        }
+
+        self.encoding = encoding;
+        self.options_modified = true;
+        self.changes += 1;
+    }
+
+    /// Number of bytes in the file that couldn't be decoded when loading it.
+    pub fn invalid_bytes(&self) -> usize {
+        self.invalid_bytes
+    }
+
     pub fn add_line(&mut self, pos: usize) -> Result<()> {
-        self.contents.insert(pos, "".to_owned());
+        let index = if pos < self.len() {
+            self.contents.line_to_char(pos)
+        } else {
+            self.contents.len_chars()
+        };
+
+        self.insert(index, "\n");
         Ok(())
     }
 
     pub fn break_line(&mut self, y: usize, x: usize) -> Result<()> {
-        let rest = self.contents[y].split_off(x);
-        self.contents.insert((y + 1), rest);
+        let offset = self.offset_at(y, x);
+        let index = self.char_index(y, offset);
+
+        self.insert(index, "\n");
         Ok(())
     }
 
     pub fn grapheme_at(&self, y: usize, x: usize) -> Option<(usize, String)> {
-        let line = &self.contents[y];
+        let line = self.line(y);
 
-        if x >= unicode::width(line) {
+        if x >= unicode::width(&line) {
             return None;
         }
 
         let mut current_column = 0;
-        for (offset, grapheme) in unicode::graphemes(line) {
+        for (offset, grapheme) in unicode::graphemes(&line) {
             let size = unicode::width(grapheme);
 
             if current_column + size > x {
@@ -68,78 +253,436 @@ impl Buffer {
     }
 
     pub fn join_lines(&mut self, n: usize) -> Result<()> {
-        let line = self.contents.remove(n + 1);
-        self.contents[n].push_str(&line);
+        let newline = self.contents.line_to_char(n + 1) - 1;
+
+        self.remove(newline, newline + 1);
         Ok(())
     }
 
     pub fn delete_char_at(&mut self, y: usize, x: usize) -> Result<Option<String>> {
         match self.grapheme_at(y, x) {
             Some((offset, grapheme)) => {
-                self.contents[y].splice(offset..(offset+grapheme.len()), "");
+                let start = self.char_index(y, offset);
+                let end = self.char_index(y, offset + grapheme.len());
+
+                self.remove(start, end);
                 Ok(Some(grapheme))
             },
             None => Ok(None),
         }
     }
 
-    pub fn line(&self, n: usize) -> &str {
-        &self.contents[n]
+    /// Text from `start` up to `end`, both given as a line and a byte offset
+    /// in it. An offset at the end of a line stands for its line break.
+    pub fn text_range(&self, start: (usize, usize), end: (usize, usize)) -> String {
+        let (start, end) = (self.point_index(start), self.point_index(end));
+
+        self.contents.slice(start..end).to_string()
+    }
+
+    /// Removes the text from `start` up to `end`, returning it.
+    pub fn delete_range(&mut self, start: (usize, usize), end: (usize, usize)) -> Result<String> {
+        self.replace_range(start, end, "")
+    }
+
+    /// Replaces the text from `start` up to `end` with `text`, returning the
+    /// text that was there. An empty range inserts `text` at `start`.
+    pub fn replace_range(&mut self, start: (usize, usize), end: (usize, usize), text: &str) -> Result<String> {
+        let (start, end) = (self.point_index(start), self.point_index(end));
+        if start > end {
+            return Err(Error::InvalidArgument(format!("{}..{}", start, end)));
+        }
+
+        let old = self.contents.slice(start..end).to_string();
+
+        if start < end {
+            self.remove(start, end);
+        }
+        if !text.is_empty() {
+            self.insert(start, text);
+        }
+
+        Ok(old)
+    }
+
+    pub fn insert_char(&mut self, y: usize, x: usize, c: char) -> Result<()> {
+        let offset = self.offset_at(y, x);
+        let index = self.char_index(y, offset);
+
+        self.insert(index, c.encode_utf8(&mut [0; 4]));
+        Ok(())
     }
 
-    pub fn line_mut(&mut self, n: usize) -> &mut String {
-        &mut self.contents[n]
+    pub fn line(&self, n: usize) -> Cow<'_, str> {
+        let line = self.contents.line(n);
+        let len = line.len_chars();
+
+        let line = if len > 0 && line.char(len - 1) == '\n' {
+            line.slice(..(len - 1))
+        } else {
+            line
+        };
+
+        match line.as_str() {
+            Some(text) => Cow::Borrowed(text),
+            None => Cow::Owned(line.to_string()),
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.contents.len()
+        self.contents.len_lines()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.len_chars() == 0
+    }
+
+    pub fn write(&mut self) -> Result<Written> {
+        let written = self.write_contents()?;
+
+        self.history.commit();
+        self.saved_seq = self.history.seq();
+        self.modified = false;
+        self.options_modified = false;
+        self.changes = 0;
+
+        Ok(written)
     }
 
-    pub fn write(&self) -> Result<()> {
+    fn write_contents(&self) -> Result<Written> {
         match self.filename {
             Some(ref filename) => {
-                match File::create(filename) {
-                    Ok(file) => {
-                        let mut writer = BufWriter::new(file);
-                        for line in &self.contents {
-                            match writer.write(line.as_bytes()) {
-                                Ok(_) => {},
-                                Err(err) => return Err(Error::IoError(err)),
-                            };
-                            match writer.write(b"\n") {
-                                Ok(_) => {},
-                                Err(err) => return Err(Error::IoError(err)),
-                            };
-                        }
-
-                        Ok(())
-                    },
-                    Err(err) => Err(Error::IoError(err)),
+                for chunk in self.contents.chunks() {
+                    self.encoding.check(chunk)?;
                 }
+
+                file::write_atomic(filename, |writer| self.write_to(writer)).map_err(Error::IoError)
             },
             None => Err(Error::NoFileName),
         }
     }
+
+    /// Writes the lines from `first` to `last` to `filename`, in the
+    /// encoding and with the line endings of the buffer, which stays as it
+    /// is.
+    pub fn write_lines(&self, filename: &str, first: usize, last: usize) -> Result<Written> {
+        let line_ending = self.format.line_ending();
+        let mut bytes = Vec::new();
+
+        if self.bom {
+            bytes.extend_from_slice(self.encoding.bom());
+        }
+
+        for line in first..(last + 1) {
+            let text = self.line(line);
+            self.encoding.check(&text)?;
+
+            self.encoding.encode(&text, &mut bytes);
+            self.encoding.encode(line_ending, &mut bytes);
+        }
+
+        file::write_atomic(filename, |writer| writer.write_all(&bytes)).map_err(Error::IoError)
+    }
+
+    fn write_to(&self, writer: &mut BufWriter<File>) -> io::Result<()> {
+        let line_ending = self.format.line_ending();
+        let mut bytes = Vec::new();
+
+        if self.bom {
+            bytes.extend_from_slice(self.encoding.bom());
+        }
+
+        for chunk in self.contents.chunks() {
+            let mut lines = chunk.split('\n');
+
+            if let Some(line) = lines.next() {
+                self.encoding.encode(line, &mut bytes);
+            }
+
+            for line in lines {
+                self.encoding.encode(line_ending, &mut bytes);
+                self.encoding.encode(line, &mut bytes);
+            }
+
+            writer.write_all(&bytes)?;
+            bytes.clear();
+        }
+
+        if self.eol {
+            self.encoding.encode(line_ending, &mut bytes);
+        }
+
+        writer.write_all(&bytes)
+    }
+
+    /// Whether the buffer changed since `commit_changes` was last called.
+    pub fn has_uncommitted_changes(&self) -> bool {
+        self.history.has_pending()
+    }
+
+    /// Closes the edits made since the last call into a single undo step.
+    pub fn commit_changes(&mut self) {
+        self.history.commit();
+    }
+
+    /// Reverts the last undo step. Returns the position (line, column) where
+    /// the change happened, or `None` when already at the oldest change.
+    pub fn undo(&mut self) -> Option<(usize, usize)> {
+        self.history.commit();
+
+        self.history.undo().map(|edits| self.apply(&edits))
+    }
+
+    /// Reapplies the last undone step. Returns the position (line, column)
+    /// where the change happened, or `None` when already at the newest change.
+    pub fn redo(&mut self) -> Option<(usize, usize)> {
+        self.history.commit();
+
+        self.history.redo().map(|edits| self.apply(&edits))
+    }
+
+    /// Moves `count` steps back in time through the undo tree, across
+    /// branches.
+    pub fn earlier(&mut self, count: usize) -> Option<(usize, usize)> {
+        self.history.commit();
+
+        let seq = self.history.seq().saturating_sub(count);
+        self.travel(seq)
+    }
+
+    /// Moves `count` steps forward in time through the undo tree, across
+    /// branches.
+    pub fn later(&mut self, count: usize) -> Option<(usize, usize)> {
+        self.history.commit();
+
+        let seq = self.history.seq().saturating_add(count);
+        self.travel(seq)
+    }
+
+    fn travel(&mut self, seq: usize) -> Option<(usize, usize)> {
+        let edits = self.history.goto(seq);
+
+        if edits.is_empty() {
+            None
+        } else {
+            Some(self.apply(&edits))
+        }
+    }
+
+    /// Applies edits coming from the history without recording them again.
+    fn apply(&mut self, edits: &[Edit]) -> (usize, usize) {
+        for edit in edits {
+            match *edit {
+                Edit::Insert(at, ref text) => self.contents.insert(at, text),
+                Edit::Remove(at, ref text) => self.contents.remove(at..(at + text.chars().count())),
+            }
+        }
+
+        self.modified = self.history.seq() != self.saved_seq;
+        self.changes += 1;
+        *self.haystack.get_mut() = None;
+
+        let at = edits.iter().map(Edit::position).min().unwrap_or(0);
+        self.position(at)
+    }
+
+    fn insert(&mut self, at: usize, text: &str) {
+        self.contents.insert(at, text);
+        self.history.record(Edit::Insert(at, text.to_owned()));
+        self.modified = true;
+        self.changes += 1;
+        *self.haystack.get_mut() = None;
+    }
+
+    fn remove(&mut self, start: usize, end: usize) {
+        let text = self.contents.slice(start..end).to_string();
+
+        self.contents.remove(start..end);
+        self.history.record(Edit::Remove(start, text));
+        self.modified = true;
+        self.changes += 1;
+        *self.haystack.get_mut() = None;
+    }
+
+    /// Line and column of char index `at`.
+    fn position(&self, at: usize) -> (usize, usize) {
+        let at = if at > self.contents.len_chars() { self.contents.len_chars() } else { at };
+        let y = self.contents.char_to_line(at);
+        let prefix = self.contents.slice(self.contents.line_to_char(y)..at).to_string();
+
+        (y, unicode::width(&prefix))
+    }
+
+    /// Byte offset inside line `y` of the grapheme under column `x`, or the
+    /// end of the line when `x` is past it.
+    fn offset_at(&self, y: usize, x: usize) -> usize {
+        match self.grapheme_at(y, x) {
+            Some((offset, _)) => offset,
+            None => self.line(y).len(),
+        }
+    }
+
+    /// Char index into the rope for byte `offset` of line `y`.
+    fn char_index(&self, y: usize, offset: usize) -> usize {
+        let line_start = self.contents.line_to_byte(y);
+
+        self.contents.byte_to_char(line_start + offset)
+    }
+
+    /// Char index into the rope for a (line, byte offset) point, clamped to
+    /// the buffer.
+    fn point_index(&self, (y, offset): (usize, usize)) -> usize {
+        if y >= self.len() {
+            return self.contents.len_chars();
+        }
+
+        let offset = min(offset, self.line(y).len());
+        self.char_index(y, offset)
+    }
+}
+
+/// Bytes decoded at a time when loading a file.
+const LOAD_CHUNK: usize = 64 * 1024;
+
+/// Decodes `bytes` a chunk at a time, giving each piece of text to `f`.
+/// Returns the number of invalid bytes.
+fn decode_chunks<F>(encoding: Encoding, bytes: &[u8], mut f: F) -> usize
+    where F: FnMut(&str)
+{
+    let mut decoder = Decoder::new(encoding);
+    let mut text = String::new();
+
+    for chunk in bytes.chunks(LOAD_CHUNK) {
+        decoder.decode(chunk, &mut text);
+        f(&text);
+        text.clear();
+    }
+
+    decoder.finish(&mut text);
+    f(&text);
+
+    decoder.invalid_bytes()
+}
+
+/// Counts the line endings of a text given in pieces, which may split a
+/// CRLF.
+#[derive(Default)]
+struct LineEndings {
+    lf: usize,
+    crlf: usize,
+    after_cr: bool,
+}
+
+impl LineEndings {
+    fn count(&mut self, piece: &str) {
+        if piece.is_empty() {
+            return;
+        }
+
+        self.lf += piece.matches('\n').count();
+        self.crlf += piece.matches("\r\n").count();
+        if self.after_cr && piece.starts_with('\n') {
+            self.crlf += 1;
+        }
+        self.after_cr = piece.ends_with('\r');
+    }
+
+    /// A text is in dos format when every line ends in CRLF.
+    fn format(&self) -> FileFormat {
+        if self.lf > 0 && self.lf == self.crlf {
+            FileFormat::Dos
+        } else {
+            FileFormat::Unix
+        }
+    }
+}
+
+/// Builds the contents of a buffer from a text given in pieces, turning
+/// CRLF into LF for dos files and leaving out the newline ending the last
+/// line. A CR or LF at the end of a piece is held back until the next one
+/// shows whether it's part of a CRLF or the last char.
+struct ContentsBuilder {
+    builder: RopeBuilder,
+    format: FileFormat,
+    cr: bool,
+    lf: bool,
+    empty: bool,
 }
 
-fn load_file(filename: &str) -> Result<Vec<String>> {
+impl ContentsBuilder {
+    fn new(format: FileFormat) -> ContentsBuilder {
+        ContentsBuilder {
+            builder: RopeBuilder::new(),
+            format: format,
+            cr: false,
+            lf: false,
+            empty: true,
+        }
+    }
+
+    fn append(&mut self, piece: &str) {
+        if piece.is_empty() {
+            return;
+        }
+
+        let mut piece = Cow::Borrowed(piece);
+        if self.format == FileFormat::Dos {
+            let mut text = String::with_capacity(piece.len() + 1);
+            if self.cr {
+                text.push('\r');
+            }
+            text.push_str(&piece);
+
+            self.cr = text.ends_with('\r');
+            if self.cr {
+                text.pop();
+            }
+            piece = Cow::Owned(text.replace("\r\n", "\n"));
+        }
+
+        self.append_lines(&piece);
+    }
+
+    fn append_lines(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        if self.lf {
+            self.builder.append("\n");
+        }
+
+        self.lf = text.ends_with('\n');
+        self.builder.append(if self.lf { &text[..text.len() - 1] } else { text });
+        self.empty = false;
+    }
+
+    /// The contents, and whether the text ended with a newline.
+    fn finish(mut self) -> (Rope, bool) {
+        if self.cr {
+            self.append_lines("\r");
+        }
+
+        // an empty file has no last line to end, but lines typed into it
+        // should still get one
+        let eol = self.empty || self.lf;
+        (self.builder.finish(), eol)
+    }
+}
+
+fn load_file(filename: &str) -> Result<Option<Vec<u8>>> {
     match File::open(filename) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            match reader.lines().collect::<io::Result<Vec<_>>>() {
-                Ok(lines) => {
-                    if lines.is_empty() {
-                        Ok(vec![String::new()])
-                    } else {
-                        Ok(lines)
-                    }
-                },
+        Ok(mut file) => {
+            let mut bytes = Vec::new();
+
+            match file.read_to_end(&mut bytes) {
+                Ok(_) => Ok(Some(bytes)),
                 Err(err) => Err(Error::IoError(err)),
             }
         },
         Err(err) => {
             match err.kind() {
-                ErrorKind::NotFound => Ok(vec![String::new()]),
+                ErrorKind::NotFound => Ok(None),
                 _ => Err(Error::IoError(err)),
             }
         },
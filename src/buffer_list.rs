@@ -0,0 +1,132 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use buffer::Buffer;
+use window::View;
+
+pub struct Entry {
+    number: usize,
+    buffer: Rc<RefCell<Buffer>>,
+    view: View,
+}
+
+impl Entry {
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn buffer(&self) -> &Rc<RefCell<Buffer>> {
+        &self.buffer
+    }
+
+    pub fn view(&self) -> View {
+        self.view
+    }
+}
+
+/// The buffers open in the editor. Buffer numbers start at 1 and are never
+/// reused, so they stay valid while other buffers come and go.
+pub struct BufferList {
+    entries: Vec<Entry>,
+    next_number: usize,
+    current: usize,
+    alternate: Option<usize>,
+}
+
+impl BufferList {
+    pub fn new(buffer: Buffer) -> BufferList {
+        let mut list = BufferList {
+            entries: Vec::new(),
+            next_number: 1,
+            current: 1,
+            alternate: None,
+        };
+
+        list.add(buffer);
+        list
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn alternate(&self) -> Option<usize> {
+        self.alternate
+    }
+
+    pub fn get(&self, number: usize) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.number == number)
+    }
+
+    pub fn find(&self, filename: &str) -> Option<usize> {
+        self.entries.iter()
+            .find(|entry| entry.buffer.borrow().filename() == Some(filename))
+            .map(|entry| entry.number)
+    }
+
+    pub fn number_of(&self, buffer: &Rc<RefCell<Buffer>>) -> Option<usize> {
+        self.entries.iter()
+            .find(|entry| Rc::ptr_eq(&entry.buffer, buffer))
+            .map(|entry| entry.number)
+    }
+
+    pub fn add(&mut self, buffer: Buffer) -> usize {
+        let number = self.next_number;
+        self.next_number += 1;
+
+        self.entries.push(Entry {
+            number: number,
+            buffer: Rc::new(RefCell::new(buffer)),
+            view: View::default(),
+        });
+
+        number
+    }
+
+    /// Makes `number` the current buffer, remembering `view` as the position
+    /// in the buffer being left.
+    pub fn switch(&mut self, number: usize, view: View) {
+        if number == self.current {
+            return;
+        }
+
+        let current = self.current;
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.number == current) {
+            entry.view = view;
+            self.alternate = Some(current);
+        }
+
+        self.current = number;
+    }
+
+    /// Buffer after `number`, wrapping around at the end of the list.
+    pub fn next(&self, number: usize) -> usize {
+        let index = self.index(number);
+
+        self.entries[(index + 1) % self.entries.len()].number
+    }
+
+    /// Buffer before `number`, wrapping around at the start of the list.
+    pub fn previous(&self, number: usize) -> usize {
+        let index = self.index(number);
+
+        self.entries[(index + self.entries.len() - 1) % self.entries.len()].number
+    }
+
+    pub fn remove(&mut self, number: usize) {
+        let index = self.index(number);
+        self.entries.remove(index);
+
+        if self.alternate == Some(number) {
+            self.alternate = None;
+        }
+    }
+
+    fn index(&self, number: usize) -> usize {
+        self.entries.iter().position(|entry| entry.number == number).unwrap_or(0)
+    }
+}
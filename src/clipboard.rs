@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::process::{Command, Stdio};
+
+use error::{Error, Result};
+
+/// The two system selections: `"+` is the clipboard and `"*` the primary
+/// selection, which are the same thing where there's only one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClipboardKind {
+    Clipboard,
+    Primary,
+}
+
+impl ClipboardKind {
+    /// The selection register `name` stands for.
+    pub fn from_register(name: char) -> Option<ClipboardKind> {
+        match name {
+            '+' => Some(ClipboardKind::Clipboard),
+            '*' => Some(ClipboardKind::Primary),
+            _ => None,
+        }
+    }
+}
+
+/// Where the `"+` and `"*` registers get their text from and send it to.
+pub trait Clipboard {
+    fn copy(&mut self, kind: ClipboardKind, text: &str) -> Result<()>;
+
+    fn paste(&self, kind: ClipboardKind) -> Result<Option<String>>;
+
+    /// Escape sequences waiting to be written to the terminal, for
+    /// providers that go through it.
+    fn take_escape_sequences(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Keeps the selections in memory, for when there's no system clipboard.
+#[derive(Default)]
+pub struct Memory {
+    selections: HashMap<ClipboardKind, String>,
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::default()
+    }
+}
+
+impl Clipboard for Memory {
+    fn copy(&mut self, kind: ClipboardKind, text: &str) -> Result<()> {
+        self.selections.insert(kind, text.to_owned());
+        Ok(())
+    }
+
+    fn paste(&self, kind: ClipboardKind) -> Result<Option<String>> {
+        Ok(self.selections.get(&kind).cloned())
+    }
+}
+
+/// Sets the clipboard of the terminal with OSC 52 escape sequences, which
+/// works over ssh. Terminals seldom allow reading it back, so pasting
+/// gives what was last copied here.
+#[derive(Default)]
+pub struct Osc52 {
+    copied: Memory,
+    sequences: Vec<String>,
+}
+
+impl Osc52 {
+    pub fn new() -> Osc52 {
+        Osc52::default()
+    }
+}
+
+impl Clipboard for Osc52 {
+    fn copy(&mut self, kind: ClipboardKind, text: &str) -> Result<()> {
+        let target = match kind {
+            ClipboardKind::Clipboard => 'c',
+            ClipboardKind::Primary => 'p',
+        };
+
+        self.sequences.push(format!("\x1b]52;{};{}\x07", target, base64(text.as_bytes())));
+        self.copied.copy(kind, text)
+    }
+
+    fn paste(&self, kind: ClipboardKind) -> Result<Option<String>> {
+        self.copied.paste(kind)
+    }
+
+    fn take_escape_sequences(&mut self) -> Vec<String> {
+        self.sequences.drain(..).collect()
+    }
+}
+
+/// Runs external commands like `xclip` to copy and paste, giving them the
+/// text on stdin and reading it from their stdout.
+pub struct External {
+    copy: HashMap<ClipboardKind, Vec<String>>,
+    paste: HashMap<ClipboardKind, Vec<String>>,
+}
+
+impl External {
+    /// Uses `copy` and `paste`, each a program and its arguments, for both
+    /// selections.
+    pub fn new(copy: &[&str], paste: &[&str]) -> External {
+        let mut clipboard = External { copy: HashMap::new(), paste: HashMap::new() };
+
+        for &kind in &[ClipboardKind::Clipboard, ClipboardKind::Primary] {
+            clipboard.set_commands(kind, copy, paste);
+        }
+
+        clipboard
+    }
+
+    /// Uses other commands for the `kind` selection.
+    pub fn set_commands(&mut self, kind: ClipboardKind, copy: &[&str], paste: &[&str]) {
+        self.copy.insert(kind, copy.iter().map(|s| (*s).to_owned()).collect());
+        self.paste.insert(kind, paste.iter().map(|s| (*s).to_owned()).collect());
+    }
+
+    /// The usual commands of one of the known tools: `xclip`, `xsel`,
+    /// `wl-copy` or `pbcopy`.
+    pub fn from_name(name: &str) -> Result<External> {
+        let (clipboard, primary) = match name {
+            "xclip" => (
+                (&["xclip", "-i", "-selection", "clipboard"][..], &["xclip", "-o", "-selection", "clipboard"][..]),
+                (&["xclip", "-i", "-selection", "primary"][..], &["xclip", "-o", "-selection", "primary"][..]),
+            ),
+            "xsel" => (
+                (&["xsel", "-i", "-b"][..], &["xsel", "-o", "-b"][..]),
+                (&["xsel", "-i", "-p"][..], &["xsel", "-o", "-p"][..]),
+            ),
+            "wl-copy" => (
+                (&["wl-copy"][..], &["wl-paste", "-n"][..]),
+                (&["wl-copy", "-p"][..], &["wl-paste", "-n", "-p"][..]),
+            ),
+            "pbcopy" => (
+                (&["pbcopy"][..], &["pbpaste"][..]),
+                (&["pbcopy"][..], &["pbpaste"][..]),
+            ),
+            _ => return Err(Error::InvalidArgument(name.to_owned())),
+        };
+
+        let mut external = External::new(clipboard.0, clipboard.1);
+        external.set_commands(ClipboardKind::Primary, primary.0, primary.1);
+
+        Ok(external)
+    }
+}
+
+impl Clipboard for External {
+    fn copy(&mut self, kind: ClipboardKind, text: &str) -> Result<()> {
+        let mut child = command(&self.copy[&kind])?
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(Error::IoError)?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).map_err(Error::IoError)?;
+        }
+
+        check_status(&self.copy[&kind], child.wait().map_err(Error::IoError)?.success())
+    }
+
+    fn paste(&self, kind: ClipboardKind) -> Result<Option<String>> {
+        let output = command(&self.paste[&kind])?.stderr(Stdio::null()).output().map_err(Error::IoError)?;
+        check_status(&self.paste[&kind], output.status.success())?;
+
+        let text = String::from_utf8_lossy(&output.stdout).into_owned();
+        Ok(if text.is_empty() { None } else { Some(text) })
+    }
+}
+
+fn command(words: &[String]) -> Result<Command> {
+    match words.split_first() {
+        Some((program, args)) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            Ok(command)
+        },
+        None => Err(Error::ClipboardError("no clipboard command".to_owned())),
+    }
+}
+
+fn check_status(words: &[String], success: bool) -> Result<()> {
+    if success {
+        Ok(())
+    } else {
+        Err(Error::ClipboardError(format!("{} failed", words.join(" "))))
+    }
+}
+
+/// The clipboard a named provider stands for, as `:set clipboard` takes
+/// them, or one running the shell commands given as `{copy}|{paste}`, where
+/// `\|` is a `|` in a command.
+pub fn from_name(name: &str) -> Result<Box<dyn Clipboard>> {
+    if let Some((copy, paste)) = split_commands(name) {
+        if copy.trim().is_empty() || paste.trim().is_empty() {
+            return Err(Error::InvalidArgument(name.to_owned()));
+        }
+
+        return Ok(Box::new(External::new(&["sh", "-c", &copy], &["sh", "-c", &paste])));
+    }
+
+    match name {
+        "none" => Ok(Box::new(Memory::new())),
+        "osc52" => Ok(Box::new(Osc52::new())),
+        name => Ok(Box::new(External::from_name(name)?)),
+    }
+}
+
+fn split_commands(value: &str) -> Option<(String, String)> {
+    let mut copy = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.as_str().starts_with('|') => {
+                copy.push('|');
+                chars.next();
+            },
+            '|' => return Some((copy, chars.as_str().replace("\\|", "|"))),
+            c => copy.push(c),
+        }
+    }
+
+    None
+}
+
+/// Picks a clipboard from the environment: the Wayland or X11 tools when
+/// there's a display and they're installed, `pbcopy` on macOS, and OSC 52
+/// otherwise, like over ssh.
+pub fn detect() -> Box<dyn Clipboard> {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    let x11 = env::var_os("DISPLAY").is_some();
+
+    let tools = [(wayland, "wl-copy"), (x11, "xclip"), (x11, "xsel"), (cfg!(target_os = "macos"), "pbcopy")];
+    let name = tools.iter()
+        .find(|&&(usable, name)| usable && is_installed(name))
+        .map_or("osc52", |&(_, name)| name);
+
+    from_name(name).unwrap()
+}
+
+/// Whether `program` is an executable in one of the directories of `PATH`.
+fn is_installed(program: &str) -> bool {
+    let path = match env::var_os("PATH") {
+        Some(path) => path,
+        None => return false,
+    };
+
+    env::split_paths(&path).any(|dir| {
+        match dir.join(program).metadata() {
+            Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+            Err(_) => false,
+        }
+    })
+}
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
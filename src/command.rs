@@ -1,12 +1,41 @@
 use error::{Error, Result};
 
 use movement::Movement;
+use operator::Operator;
+use text_object::TextObject;
+use selection::SelectionKind;
+use layout::{Orientation, Side, Size};
+use substitute::Substitution;
+use address::LineRange;
+
+/// Commands that take the next typed char as their argument, like
+/// `f{char}`.
+#[derive(Clone, Copy)]
+pub enum CharCommand {
+    Find { backward: bool, till: bool },
+    /// `"{register}`, the register the next command uses.
+    SelectRegister,
+    /// `<C-r>{register}` in insert mode.
+    InsertRegister,
+    /// `q{register}`, which starts recording keys into the register.
+    RecordMacro,
+    /// `@{register}`, which types the keys in the register.
+    PlayMacro,
+    /// `m{a-z}`, which marks the cursor line.
+    SetMark,
+}
 
 #[derive(Clone)]
 pub enum Command {
     Quit,
-    Edit(String),
-    Write,
+    ForceQuit,
+    WriteQuit,
+    UpdateQuit,
+    Edit(Option<String>),
+    ForceEdit(Option<String>),
+    /// `:write`, or `:write file` to write the lines of the range, all of
+    /// them without one, to another file.
+    Write(Option<LineRange>, Option<String>),
     EnterPrompt(char),
     CancelPrompt,
     RunPrompt,
@@ -18,17 +47,206 @@ pub enum Command {
     DeleteCharBeforeCursor,
     DeleteCharBeforeCursorInPrompt,
     Movement(Movement),
+    Operator(Operator),
+    TextObject(TextObject),
+    Visual(SelectionKind),
+    LeaveVisual,
+    SwapSelectionEnds,
+    LineOperator(Option<LineRange>, Operator),
+    /// `:{range}`, which moves to the last line of the range.
+    Goto(LineRange),
+    /// `:normal {keys}`, typing the keys on each line of the range.
+    Normal(Option<LineRange>, String),
+    /// `:sort`, of the whole buffer without a range. `:sort!` reverses the
+    /// order and the `i` flag ignores case.
+    Sort { range: Option<LineRange>, reverse: bool, ignore_case: bool },
+    /// `:!command`, which runs a shell command, or puts the lines of the
+    /// range through it.
+    Shell(Option<LineRange>, String),
+    WaitForChar(CharCommand),
+    RepeatChange,
+    /// `q`, which stops recording, or asks for the register to record into.
+    Record,
+    PutAfter,
+    PutBefore,
+    ListRegisters,
+    /// `:nohlsearch`, which stops highlighting matches until the next
+    /// search.
+    NoHighlightSearch,
+    /// `:s/pattern/replacement/flags`, or `:s` alone to do the last one
+    /// again.
+    Substitute(Option<LineRange>, Option<Substitution>),
+    Undo,
+    Redo,
+    Earlier(usize),
+    Later(usize),
+    Set(String, Option<String>),
+    Buffer(usize),
+    AlternateBuffer,
+    NextBuffer,
+    PreviousBuffer,
+    DeleteBuffer(Option<usize>),
+    ForceDeleteBuffer(Option<usize>),
+    ListBuffers,
+    Split(Option<String>),
+    VerticalSplit(Option<String>),
+    CloseWindow,
+    OnlyWindow,
+    FocusWindow(Side),
+    NextWindow,
+    ResizeWindow(Orientation, Size),
+    EqualizeWindows,
 }
 
 impl Command {
   pub fn parse(text: &str) -> Result<Command> {
+    if let Some(text) = text.strip_prefix(':') {
+      if let (Some(range), rest) = LineRange::parse(text)? {
+        if rest.is_empty() {
+          return Ok(Command::Goto(range));
+        }
+
+        let command = Command::parse(&format!(":{}", rest))?;
+        return command.with_range(range);
+      }
+    }
+
+    if let Some(command) = text.strip_prefix(":!") {
+      return Ok(Command::Shell(None, command.to_owned()));
+    }
+
+    if let Some(rest) = substitute_arguments(text) {
+      return if rest.is_empty() {
+        Ok(Command::Substitute(None, None))
+      } else {
+        Ok(Command::Substitute(None, Some(Substitution::parse(rest)?)))
+      };
+    }
+
     let words = text.split(' ').collect::<Vec<_>>();
+    // the rest of the command line after the command name
+    let argument = text.find(' ').map_or("", |i| &text[(i + 1)..]);
 
     match words[0] {
       ":quit" => Ok(Command::Quit),
-      ":write" => Ok(Command::Write),
-      ":edit" => Ok(Command::Edit(words[1].to_owned())),
+      ":quit!" => Ok(Command::ForceQuit),
+      ":wq" => Ok(Command::WriteQuit),
+      ":x" => Ok(Command::UpdateQuit),
+      ":write" | ":w" => Ok(Command::Write(None, words.get(1).map(|s| (*s).to_owned()))),
+      ":edit" => Ok(Command::Edit(words.get(1).map(|s| (*s).to_owned()))),
+      ":edit!" => Ok(Command::ForceEdit(words.get(1).map(|s| (*s).to_owned()))),
+      ":undo" => Ok(Command::Undo),
+      ":redo" => Ok(Command::Redo),
+      ":earlier" => Ok(Command::Earlier(parse_count(words.get(1))?)),
+      ":later" => Ok(Command::Later(parse_count(words.get(1))?)),
+      ":set" => parse_set(argument),
+      ":ls" | ":buffers" => Ok(Command::ListBuffers),
+      ":b#" => Ok(Command::AlternateBuffer),
+      ":buffer" | ":b" => {
+        match words.get(1) {
+          Some(&"#") => Ok(Command::AlternateBuffer),
+          Some(word) => Ok(Command::Buffer(parse_number(word)?)),
+          None => Err(Error::InvalidArgument("".to_owned())),
+        }
+      },
+      ":bnext" | ":bn" => Ok(Command::NextBuffer),
+      ":bprevious" | ":bprev" | ":bp" => Ok(Command::PreviousBuffer),
+      ":bdelete" | ":bd" => Ok(Command::DeleteBuffer(parse_optional_number(words.get(1))?)),
+      ":split" | ":sp" => Ok(Command::Split(words.get(1).map(|s| (*s).to_owned()))),
+      ":vsplit" | ":vs" => Ok(Command::VerticalSplit(words.get(1).map(|s| (*s).to_owned()))),
+      ":close" | ":clo" => Ok(Command::CloseWindow),
+      ":only" | ":on" => Ok(Command::OnlyWindow),
+      ":resize" | ":res" => Ok(Command::ResizeWindow(Orientation::Horizontal, parse_size(words.get(1))?)),
+      ":vertical" | ":vert" => {
+        match words.get(1) {
+          Some(&"resize") | Some(&"res") => Ok(Command::ResizeWindow(Orientation::Vertical, parse_size(words.get(2))?)),
+          _ => Err(Error::CommandNotFound(text.to_owned())),
+        }
+      },
+      ":delete" | ":d" => Ok(Command::LineOperator(None, Operator::Delete)),
+      ":yank" | ":y" => Ok(Command::LineOperator(None, Operator::Yank)),
+      ":>" => Ok(Command::LineOperator(None, Operator::ShiftRight)),
+      ":<" => Ok(Command::LineOperator(None, Operator::ShiftLeft)),
+      ":registers" | ":reg" | ":display" | ":di" => Ok(Command::ListRegisters),
+      ":nohlsearch" | ":noh" => Ok(Command::NoHighlightSearch),
+      ":normal" | ":norm" | ":normal!" | ":norm!" => {
+        match argument {
+          "" => Err(Error::InvalidArgument("".to_owned())),
+          keys => Ok(Command::Normal(None, keys.to_owned())),
+        }
+      },
+      ":sort" | ":sor" | ":sort!" | ":sor!" => {
+        let ignore_case = match argument {
+          "" => false,
+          "i" => true,
+          _ => return Err(Error::InvalidArgument(argument.to_owned())),
+        };
+
+        Ok(Command::Sort { range: None, reverse: words[0].ends_with('!'), ignore_case: ignore_case })
+      },
+      ":bdelete!" | ":bd!" => Ok(Command::ForceDeleteBuffer(parse_optional_number(words.get(1))?)),
       _ => Err(Error::CommandNotFound(text.to_owned())),
     }
   }
+
+  fn with_range(self, range: LineRange) -> Result<Command> {
+    match self {
+      Command::LineOperator(_, operator) => Ok(Command::LineOperator(Some(range), operator)),
+      Command::Substitute(_, substitution) => Ok(Command::Substitute(Some(range), substitution)),
+      Command::Write(_, filename) => Ok(Command::Write(Some(range), filename)),
+      Command::Normal(_, keys) => Ok(Command::Normal(Some(range), keys)),
+      Command::Sort { reverse, ignore_case, .. } => Ok(Command::Sort { range: Some(range), reverse: reverse, ignore_case: ignore_case }),
+      Command::Shell(_, command) => Ok(Command::Shell(Some(range), command)),
+      _ => Err(Error::NoRangeAllowed),
+    }
+  }
+}
+
+/// What follows `:s` or `:substitute`, which can be any char that isn't a
+/// letter, so `:s#a#b#` works and `:set` doesn't count.
+fn substitute_arguments(text: &str) -> Option<&str> {
+    [":substitute", ":s"].iter()
+        .filter(|name| text.starts_with(*name))
+        .map(|name| &text[name.len()..])
+        .find(|rest| rest.chars().next().is_none_or(|c| !c.is_alphanumeric() && c != ' '))
+}
+
+fn parse_number(word: &str) -> Result<usize> {
+    word.parse().map_err(|_| Error::InvalidArgument(word.to_owned()))
+}
+
+fn parse_optional_number(word: Option<&&str>) -> Result<Option<usize>> {
+    match word {
+        Some(word) => parse_number(word).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// `N` sets the size, `+N` and `-N` change it, and no size at all means as
+/// large as possible.
+fn parse_size(word: Option<&&str>) -> Result<Size> {
+    match word {
+        Some(word) if word.starts_with('+') => Ok(Size::Relative(parse_number(&word[1..])? as isize)),
+        Some(word) if word.starts_with('-') => Ok(Size::Relative(-(parse_number(&word[1..])? as isize))),
+        Some(word) => Ok(Size::Absolute(parse_number(word)?)),
+        None => Ok(Size::Max),
+    }
+}
+
+fn parse_count(word: Option<&&str>) -> Result<usize> {
+    parse_optional_number(word).map(|count| count.unwrap_or(1))
+}
+
+/// `name` or `name=value`, where the value is the rest of the line, so it
+/// can have spaces, like the commands of `:set clipboard`.
+fn parse_set(argument: &str) -> Result<Command> {
+    if argument.is_empty() {
+        return Err(Error::InvalidArgument("".to_owned()));
+    }
+
+    let mut parts = argument.splitn(2, '=');
+    let name = parts.next().unwrap_or("").to_owned();
+    let value = parts.next().map(|value| value.to_owned());
+
+    Ok(Command::Set(name, value))
 }
@@ -1,58 +1,348 @@
-use error::Result;
-use input::Key;
+use error::{Error, Result};
+use input::{self, Key, KeyCode};
 use prompt::Prompt;
-use command::Command;
-use movement::Movement;
-use buffer::Buffer;
+use command::{CharCommand, Command};
+use address::{Address, Base, LineRange};
+use movement::{Find, Movement, Position};
+use operator::{Operator, Range};
+use text_object::TextObject;
+use selection::{Selection, SelectionKind};
+use registers::{Register, Registers};
+use search::{self, Search};
+use pattern::Pattern;
+use substitute::{Replacement, Substitution};
+use clipboard::{self, Clipboard, ClipboardKind};
+use buffer::{Buffer, FileFormat};
+use file::Written;
+use encoding::Encoding;
 use mode::Mode;
-use window::Window;
-use mappings::Mappings;
+use window::{Window, View};
+use mappings::{Mappings, Match};
+use buffer_list::BufferList;
+use shell;
+use layout::{Layout, Orientation, Rect, Side, Size};
+
+use std::cell::RefCell;
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Milliseconds to wait for the rest of a mapping, like Vim's `timeoutlen`.
+const DEFAULT_TIMEOUT: usize = 1000;
+
+const DEFAULT_SHIFTWIDTH: usize = 8;
+
+/// How deep macros may play other macros, or themselves.
+const MAX_MACRO_DEPTH: usize = 100;
+
+/// A key typed as part of a change, and whether it went into a count.
+type ChangeKey = (Key, bool);
+
+/// An operator waiting for the motion that tells what it works on.
+struct PendingOperator {
+    operator: Operator,
+    count: Option<usize>,
+    keys: Vec<Key>,
+}
+
+/// A search being typed, with the view it started from and the match the
+/// cursor was moved to.
+struct IncSearch {
+    view: View,
+    matched: Option<search::Match>,
+}
+
+/// A substitution asking about each match before replacing it, with the
+/// matches it was told to replace so far.
+struct Confirm {
+    view: View,
+    replacements: Vec<Replacement>,
+    accepted: Vec<Replacement>,
+    current: usize,
+}
 
 pub struct Editor {
     mode: Mode,
     running: bool,
     prompt: Prompt,
-    window: Window,
+    windows: HashMap<usize, Window>,
+    current_window: usize,
+    next_window: usize,
+    layout: Layout,
+    buffers: BufferList,
     height: usize,
     width: usize,
+    pending_keys: Vec<Key>,
+    char_command: Option<(CharCommand, Vec<Key>)>,
+    count: Option<usize>,
+    operator: Option<PendingOperator>,
+    registers: Registers,
+    clipboard: Box<dyn Clipboard>,
+    /// The register picked with `"` for the next command.
+    register_name: Option<char>,
+    /// Text typed in the current insert session, for `".`.
+    inserted: String,
+    /// How many times the text of the insert session goes in, and whether
+    /// each copy goes on lines of its own, as with `3o`.
+    insert_count: usize,
+    insert_lines: bool,
+    /// Keys typed since the editor was last waiting for a command, unless
+    /// they can't be repeated, and whether they changed the buffer.
+    change_keys: Option<Vec<ChangeKey>>,
+    changed: bool,
+    key_counted: bool,
+    /// Keys of the last change, for `.`.
+    last_change: Vec<ChangeKey>,
+    /// The register keys are being recorded into with `q`, and the keys.
+    recording: Option<(char, Vec<Key>)>,
+    /// The register last played with `@`, for `@@`.
+    last_macro: Option<char>,
+    /// How many macros are playing, one inside the other.
+    macro_depth: usize,
+    visual_anchor: (usize, usize),
+    last_selection: Option<Selection>,
+    last_find: Option<Find>,
+    last_search: Option<Search>,
+    /// Whether the matches of the last search are highlighted.
+    hlsearch: bool,
+    incsearch: Option<IncSearch>,
+    /// The count typed before `/` or `?`, for the search once it's typed.
+    search_count: Option<usize>,
+    /// The last `:substitute`, which `:s` alone does again.
+    last_substitution: Option<Substitution>,
+    confirm: Option<Confirm>,
+    timeout: usize,
+    shiftwidth: usize,
     normal_mappings: Mappings,
+    operator_pending_mappings: Mappings,
+    visual_mappings: Mappings,
     insert_mappings: Mappings,
     prompt_mappings: Mappings,
 }
 
 impl Editor {
     pub fn new(height: usize, width: usize) -> Editor {
-        let window = Window::new(height - 1, width, Buffer::new());
+        let buffers = BufferList::new(Buffer::new());
+        let window = Window::new(height - 1, width, buffers.get(1).unwrap().buffer().clone());
         let prompt = Prompt::new(height - 1);
 
+        let mut windows = HashMap::new();
+        windows.insert(0, window);
+
         let mut ed = Editor {
             mode: Mode::Normal,
             prompt: prompt,
-            window: window,
+            windows: windows,
+            current_window: 0,
+            next_window: 1,
+            layout: Layout::new(0),
+            buffers: buffers,
             running: true,
             height: height,
             width: width,
+            pending_keys: Vec::new(),
+            char_command: None,
+            count: None,
+            operator: None,
+            registers: Registers::new(),
+            clipboard: Box::new(clipboard::Memory::new()),
+            register_name: None,
+            inserted: String::new(),
+            insert_count: 1,
+            insert_lines: false,
+            change_keys: Some(Vec::new()),
+            changed: false,
+            key_counted: false,
+            last_change: Vec::new(),
+            recording: None,
+            last_macro: None,
+            macro_depth: 0,
+            visual_anchor: (0, 0),
+            last_selection: None,
+            last_find: None,
+            last_search: None,
+            hlsearch: false,
+            incsearch: None,
+            search_count: None,
+            last_substitution: None,
+            confirm: None,
+            timeout: DEFAULT_TIMEOUT,
+            shiftwidth: DEFAULT_SHIFTWIDTH,
             normal_mappings: Mappings::new(),
+            operator_pending_mappings: Mappings::new(),
+            visual_mappings: Mappings::new(),
             insert_mappings: Mappings::new(),
             prompt_mappings: Mappings::new(),
         };
 
-        ed.add_mapping(Mode::Normal, Key::Char(':'), Command::EnterPrompt(':'));
-        ed.add_mapping(Mode::Normal, Key::Char('i'), Command::EnterInsert);
-        ed.add_mapping(Mode::Normal, Key::Char('a'), Command::EnterInsertAfterCursor);
-        ed.add_mapping(Mode::Normal, Key::Char('o'), Command::OpenLineAfter);
-        ed.add_mapping(Mode::Normal, Key::Char('O'), Command::OpenLineBefore);
-        ed.add_mapping(Mode::Normal, Key::Char('h'), Command::Movement(Movement::Left));
-        ed.add_mapping(Mode::Normal, Key::Char('j'), Command::Movement(Movement::Down));
-        ed.add_mapping(Mode::Normal, Key::Char('k'), Command::Movement(Movement::Up));
-        ed.add_mapping(Mode::Normal, Key::Char('l'), Command::Movement(Movement::Right));
+        ed.map(Mode::Normal, ":", Command::EnterPrompt(':'));
+        ed.map(Mode::Normal, "/", Command::EnterPrompt('/'));
+        ed.map(Mode::Normal, "?", Command::EnterPrompt('?'));
+        ed.map(Mode::OperatorPending, "/", Command::EnterPrompt('/'));
+        ed.map(Mode::OperatorPending, "?", Command::EnterPrompt('?'));
+        ed.map(Mode::Normal, "i", Command::EnterInsert);
+        ed.map(Mode::Normal, "a", Command::EnterInsertAfterCursor);
+        ed.map(Mode::Normal, "o", Command::OpenLineAfter);
+        ed.map(Mode::Normal, "O", Command::OpenLineBefore);
+
+        // motions also tell an operator what to work on
+        let motions = vec![
+            ("h", Command::Movement(Movement::Left)),
+            ("j", Command::Movement(Movement::Down)),
+            ("k", Command::Movement(Movement::Up)),
+            ("l", Command::Movement(Movement::Right)),
+            ("w", Command::Movement(Movement::WordForward)),
+            ("b", Command::Movement(Movement::WordBackward)),
+            ("e", Command::Movement(Movement::WordEnd)),
+            ("W", Command::Movement(Movement::BigWordForward)),
+            ("B", Command::Movement(Movement::BigWordBackward)),
+            ("E", Command::Movement(Movement::BigWordEnd)),
+            ("0", Command::Movement(Movement::LineStart)),
+            ("^", Command::Movement(Movement::FirstNonBlank)),
+            ("$", Command::Movement(Movement::LineEnd)),
+            ("gg", Command::Movement(Movement::FirstLine)),
+            ("G", Command::Movement(Movement::LastLine)),
+            ("}", Command::Movement(Movement::ParagraphForward)),
+            ("{", Command::Movement(Movement::ParagraphBackward)),
+            ("%", Command::Movement(Movement::MatchingBracket)),
+            ("f", Command::WaitForChar(CharCommand::Find { backward: false, till: false })),
+            ("t", Command::WaitForChar(CharCommand::Find { backward: false, till: true })),
+            ("F", Command::WaitForChar(CharCommand::Find { backward: true, till: false })),
+            ("T", Command::WaitForChar(CharCommand::Find { backward: true, till: true })),
+            (";", Command::Movement(Movement::RepeatFind)),
+            (",", Command::Movement(Movement::RepeatFindReverse)),
+            ("n", Command::Movement(Movement::SearchNext)),
+            ("N", Command::Movement(Movement::SearchPrevious)),
+            ("<Home>", Command::Movement(Movement::LineStart)),
+            ("<End>", Command::Movement(Movement::LineEnd)),
+            ("<Left>", Command::Movement(Movement::Left)),
+            ("<Down>", Command::Movement(Movement::Down)),
+            ("<Up>", Command::Movement(Movement::Up)),
+            ("<Right>", Command::Movement(Movement::Right)),
+        ];
+
+        for (keys, command) in motions {
+            ed.map(Mode::Normal, keys, command.clone());
+            ed.map(Mode::OperatorPending, keys, command.clone());
+            ed.map(Mode::Visual, keys, command);
+        }
+
+        // typing an operator again, or just its last key, makes it work on
+        // lines, like `dd` and `guu`
+        let operators = vec![
+            ("d", Operator::Delete),
+            ("c", Operator::Change),
+            ("y", Operator::Yank),
+            (">", Operator::ShiftRight),
+            ("<lt>", Operator::ShiftLeft),
+            ("gu", Operator::Lowercase),
+            ("gU", Operator::Uppercase),
+            ("g~", Operator::ToggleCase),
+        ];
 
-        ed.add_mapping(Mode::Insert, Key::Esc, Command::LeaveInsert);
-        ed.add_mapping(Mode::Insert, Key::Backspace, Command::DeleteCharBeforeCursor);
+        for (keys, operator) in operators {
+            ed.map(Mode::Normal, keys, Command::Operator(operator));
+            ed.map(Mode::OperatorPending, keys, Command::Operator(operator));
+            ed.map(Mode::Visual, keys, Command::Operator(operator));
+        }
+
+        ed.map(Mode::OperatorPending, "u", Command::Operator(Operator::Lowercase));
+        ed.map(Mode::OperatorPending, "U", Command::Operator(Operator::Uppercase));
+        ed.map(Mode::OperatorPending, "~", Command::Operator(Operator::ToggleCase));
+
+        for &(prefix, inner) in &[("i", true), ("a", false)] {
+            let objects = vec![
+                ("w", TextObject::Word { big: false, inner: inner }),
+                ("W", TextObject::Word { big: true, inner: inner }),
+                ("s", TextObject::Sentence { inner: inner }),
+                ("p", TextObject::Paragraph { inner: inner }),
+                ("\"", TextObject::Quote { quote: '"', inner: inner }),
+                ("'", TextObject::Quote { quote: '\'', inner: inner }),
+                ("`", TextObject::Quote { quote: '`', inner: inner }),
+                ("(", TextObject::Block { open: '(', close: ')', inner: inner }),
+                (")", TextObject::Block { open: '(', close: ')', inner: inner }),
+                ("b", TextObject::Block { open: '(', close: ')', inner: inner }),
+                ("{", TextObject::Block { open: '{', close: '}', inner: inner }),
+                ("}", TextObject::Block { open: '{', close: '}', inner: inner }),
+                ("B", TextObject::Block { open: '{', close: '}', inner: inner }),
+                ("[", TextObject::Block { open: '[', close: ']', inner: inner }),
+                ("]", TextObject::Block { open: '[', close: ']', inner: inner }),
+                ("<lt>", TextObject::Block { open: '<', close: '>', inner: inner }),
+                (">", TextObject::Block { open: '<', close: '>', inner: inner }),
+                ("t", TextObject::Tag { inner: inner }),
+            ];
+
+            for (keys, object) in objects {
+                let keys = format!("{}{}", prefix, keys);
+                ed.map(Mode::OperatorPending, &keys, Command::TextObject(object));
+                ed.map(Mode::Visual, &keys, Command::TextObject(object));
+            }
+        }
+
+        let visual_modes = vec![
+            ("v", SelectionKind::Charwise),
+            ("V", SelectionKind::Linewise),
+            ("<C-v>", SelectionKind::Blockwise),
+        ];
+
+        for (keys, kind) in visual_modes {
+            ed.map(Mode::Normal, keys, Command::Visual(kind));
+            ed.map(Mode::Visual, keys, Command::Visual(kind));
+        }
 
-        ed.add_mapping(Mode::Prompt, Key::Esc, Command::CancelPrompt);
-        ed.add_mapping(Mode::Prompt, Key::Enter, Command::RunPrompt);
-        ed.add_mapping(Mode::Prompt, Key::Backspace, Command::DeleteCharBeforeCursorInPrompt);
+        ed.map(Mode::Visual, "<Esc>", Command::LeaveVisual);
+        ed.map(Mode::Visual, "o", Command::SwapSelectionEnds);
+        ed.map(Mode::Visual, ":", Command::EnterPrompt(':'));
+        ed.map(Mode::Visual, "x", Command::Operator(Operator::Delete));
+        ed.map(Mode::Visual, "s", Command::Operator(Operator::Change));
+        ed.map(Mode::Visual, "u", Command::Operator(Operator::Lowercase));
+        ed.map(Mode::Visual, "U", Command::Operator(Operator::Uppercase));
+        ed.map(Mode::Visual, "~", Command::Operator(Operator::ToggleCase));
+
+        ed.map(Mode::Normal, "\"", Command::WaitForChar(CharCommand::SelectRegister));
+        ed.map(Mode::Visual, "\"", Command::WaitForChar(CharCommand::SelectRegister));
+        ed.map(Mode::Normal, ".", Command::RepeatChange);
+        ed.map(Mode::Normal, "q", Command::Record);
+        ed.map(Mode::Normal, "@", Command::WaitForChar(CharCommand::PlayMacro));
+        ed.map(Mode::Normal, "m", Command::WaitForChar(CharCommand::SetMark));
+        ed.map(Mode::Normal, "p", Command::PutAfter);
+        ed.map(Mode::Normal, "P", Command::PutBefore);
+
+        ed.map(Mode::Normal, "u", Command::Undo);
+        ed.map(Mode::Normal, "<C-r>", Command::Redo);
+        ed.map(Mode::Normal, "ZZ", Command::UpdateQuit);
+        ed.map(Mode::Normal, "ZQ", Command::ForceQuit);
+
+        ed.map(Mode::Insert, "<Esc>", Command::LeaveInsert);
+        ed.map(Mode::Insert, "<BS>", Command::DeleteCharBeforeCursor);
+        ed.map(Mode::Insert, "<C-r>", Command::WaitForChar(CharCommand::InsertRegister));
+        ed.map(Mode::Insert, "<Left>", Command::Movement(Movement::Left));
+        ed.map(Mode::Insert, "<Down>", Command::Movement(Movement::Down));
+        ed.map(Mode::Insert, "<Up>", Command::Movement(Movement::Up));
+        ed.map(Mode::Insert, "<Right>", Command::Movement(Movement::Right));
+
+        ed.map(Mode::Prompt, "<Esc>", Command::CancelPrompt);
+        ed.map(Mode::Prompt, "<CR>", Command::RunPrompt);
+        ed.map(Mode::Prompt, "<BS>", Command::DeleteCharBeforeCursorInPrompt);
+
+        ed.map(Mode::Normal, "<C-w>h", Command::FocusWindow(Side::Left));
+        ed.map(Mode::Normal, "<C-w>j", Command::FocusWindow(Side::Below));
+        ed.map(Mode::Normal, "<C-w>k", Command::FocusWindow(Side::Above));
+        ed.map(Mode::Normal, "<C-w>l", Command::FocusWindow(Side::Right));
+        ed.map(Mode::Normal, "<C-w>w", Command::NextWindow);
+        ed.map(Mode::Normal, "<C-w><C-w>", Command::NextWindow);
+        ed.map(Mode::Normal, "<C-w>s", Command::Split(None));
+        ed.map(Mode::Normal, "<C-w>v", Command::VerticalSplit(None));
+        ed.map(Mode::Normal, "<C-w>c", Command::CloseWindow);
+        ed.map(Mode::Normal, "<C-w>o", Command::OnlyWindow);
+        ed.map(Mode::Normal, "<C-w>q", Command::Quit);
+        ed.map(Mode::Normal, "<C-w>+", Command::ResizeWindow(Orientation::Horizontal, Size::Relative(1)));
+        ed.map(Mode::Normal, "<C-w>-", Command::ResizeWindow(Orientation::Horizontal, Size::Relative(-1)));
+        ed.map(Mode::Normal, "<C-w>>", Command::ResizeWindow(Orientation::Vertical, Size::Relative(1)));
+        ed.map(Mode::Normal, "<C-w><lt>", Command::ResizeWindow(Orientation::Vertical, Size::Relative(-1)));
+        ed.map(Mode::Normal, "<C-w>=", Command::EqualizeWindows);
 
         ed
     }
@@ -61,8 +351,18 @@ impl Editor {
         self.running
     }
 
+    /// The window with the cursor.
     pub fn window(&self) -> &Window {
-        &self.window
+        &self.windows[&self.current_window]
+    }
+
+    fn window_mut(&mut self) -> &mut Window {
+        self.windows.get_mut(&self.current_window).unwrap()
+    }
+
+    /// All windows, from top left to bottom right.
+    pub fn windows(&self) -> Vec<&Window> {
+        self.layout.windows().iter().map(|id| &self.windows[id]).collect()
     }
 
     pub fn prompt(&self) -> &Prompt {
@@ -77,11 +377,159 @@ impl Editor {
         (self.height, self.width)
     }
 
-    pub fn add_mapping(&mut self, mode: Mode, key: Key, command: Command) {
+    pub fn add_mapping(&mut self, mode: Mode, keys: &[Key], command: Command) {
+        self.mappings_mut(mode).insert(keys, command);
+    }
+
+    /// Maps keys written in the notation of `input::parse_keys`.
+    fn map(&mut self, mode: Mode, keys: &str, command: Command) {
+        let keys = input::parse_keys(keys).unwrap();
+        self.add_mapping(mode, &keys, command);
+    }
+
+    fn mappings(&self, mode: Mode) -> &Mappings {
+        match mode {
+            Mode::Normal => &self.normal_mappings,
+            Mode::OperatorPending => &self.operator_pending_mappings,
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => &self.visual_mappings,
+            Mode::Insert => &self.insert_mappings,
+            Mode::Prompt => &self.prompt_mappings,
+        }
+    }
+
+    fn mappings_mut(&mut self, mode: Mode) -> &mut Mappings {
         match mode {
-            Mode::Normal => self.normal_mappings.insert(key, command),
-            Mode::Insert => self.insert_mappings.insert(key, command),
-            Mode::Prompt => self.prompt_mappings.insert(key, command),
+            Mode::Normal => &mut self.normal_mappings,
+            Mode::OperatorPending => &mut self.operator_pending_mappings,
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => &mut self.visual_mappings,
+            Mode::Insert => &mut self.insert_mappings,
+            Mode::Prompt => &mut self.prompt_mappings,
+        }
+    }
+
+    /// Keys typed so far of a mapping that isn't complete yet, or of a
+    /// command waiting for its argument or motion.
+    pub fn pending_keys(&self) -> String {
+        let mut text = String::new();
+
+        if let Some(name) = self.register_name {
+            text.push('"');
+            text.push(name);
+        }
+
+        if let Some(ref pending) = self.operator {
+            text.extend(pending.count.map(|count| count.to_string()));
+            text.extend(pending.keys.iter().map(|key| key.to_string()));
+        }
+
+        text.extend(self.count.map(|count| count.to_string()));
+
+        let argument_keys = self.char_command.iter().flat_map(|(_, keys)| keys.iter());
+        text.extend(argument_keys.chain(self.pending_keys.iter()).map(|key| key.to_string()));
+
+        text
+    }
+
+    /// The text selected in visual mode.
+    pub fn selection(&self) -> Option<Selection> {
+        visual_kind(self.mode).map(|kind| self.window().selection(kind, self.visual_anchor))
+    }
+
+    /// The match the cursor moved to for the search being typed, or the
+    /// one a substitution asks about.
+    pub fn current_match(&self) -> Option<search::Match> {
+        match self.confirm {
+            Some(ref confirm) => Some(confirm.replacements[confirm.current].matched),
+            None => self.incsearch.as_ref().and_then(|incsearch| incsearch.matched),
+        }
+    }
+
+    /// Matches of the last search shown in `window`, which stay highlighted
+    /// until `:nohlsearch`.
+    pub fn highlighted_matches(&self, window: &Window) -> Vec<search::Match> {
+        let pattern = match self.last_search {
+            Some(ref search) if self.hlsearch => Pattern::new(&search.pattern),
+            _ => return Vec::new(),
+        };
+
+        pattern.map(|pattern| window.matches(&pattern)).unwrap_or_default()
+    }
+
+    /// The register keys are being recorded into, if any.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|&(name, _)| name)
+    }
+
+    /// Text in register `name`, if there's any.
+    pub fn register(&self, name: char) -> Option<String> {
+        self.read_register(name).ok().map(|register| register.text)
+    }
+
+    /// Makes `clipboard` back the `"+` and `"*` registers.
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = clipboard;
+    }
+
+    /// Escape sequences the clipboard wants written to the terminal, which
+    /// are gone once taken.
+    pub fn take_escape_sequences(&mut self) -> Vec<String> {
+        self.clipboard.take_escape_sequences()
+    }
+
+    fn read_register(&self, name: char) -> Result<Register> {
+        let register = match name {
+            '%' => self.window().filename().map(|filename| Register::new(&filename, SelectionKind::Charwise)),
+            name => match ClipboardKind::from_register(name) {
+                Some(kind) => self.paste_from_clipboard(name, kind)?,
+                None => self.registers.get(name)?,
+            },
+        };
+
+        register.ok_or(Error::EmptyRegister(name))
+    }
+
+    /// Text of the system clipboard. If it's what was last copied from
+    /// here, it goes back in as it was copied, otherwise it's lines if it
+    /// ends with a line break.
+    fn paste_from_clipboard(&self, name: char, kind: ClipboardKind) -> Result<Option<Register>> {
+        let text = match self.clipboard.paste(kind)? {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+
+        match self.registers.get(name)? {
+            Some(ref copied) if copied.text == text => Ok(Some(copied.clone())),
+            _ => {
+                let kind = if text.ends_with('\n') { SelectionKind::Linewise } else { SelectionKind::Charwise };
+                Ok(Some(Register::new(&text, kind)))
+            },
+        }
+    }
+
+    /// Stores yanked or deleted text in register `name`, sending it on to
+    /// the system clipboard for `"+` and `"*`.
+    fn store_register(&mut self, name: Option<char>, register: Register, deleted: bool) -> Result<()> {
+        let text = register.text.clone();
+
+        if deleted {
+            self.registers.delete(name, register)?;
+        } else {
+            self.registers.yank(name, register)?;
+        }
+
+        match name.and_then(ClipboardKind::from_register) {
+            Some(kind) => self.clipboard.copy(kind, &text),
+            None => Ok(()),
+        }
+    }
+
+    /// How long to wait for the next key before `handle_timeout` should be
+    /// called, if a mapping is pending.
+    pub fn key_timeout(&self) -> Option<Duration> {
+        if self.pending_keys.is_empty() {
+            None
+        } else {
+            Some(Duration::from_millis(self.timeout as u64))
         }
     }
 
@@ -91,168 +539,1893 @@ impl Editor {
 
     pub fn cursor(&self) -> (usize, usize) {
         match self.mode {
-            Mode::Normal | Mode::Insert => self.window.cursor(),
+            Mode::Normal | Mode::OperatorPending | Mode::Insert |
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => {
+                let (pos_y, pos_x) = self.window().position();
+                let (cur_y, cur_x) = self.window().cursor();
+
+                (pos_y + cur_y, pos_x + cur_x)
+            },
             Mode::Prompt => (self.height - 1, self.prompt.cursor()),
         }
     }
 
     pub fn handle_key(&mut self, key: &Key) -> Result<()> {
-        match self.mode {
-            Mode::Normal => self.handle_key_normal(key),
-            Mode::Insert => self.handle_key_insert(key),
-            Mode::Prompt => self.handle_key_prompt(key),
+        if self.prompt.has_long_message() {
+            self.prompt.clear_message();
         }
-    }
 
-    fn handle_key_normal(&mut self, key: &Key) -> Result<()> {
-        match self.normal_mappings.get(key).cloned() {
-            Some(ref command) => self.run_command(command),
-            None => Ok(()),
+        if let Some(ref mut keys) = self.change_keys {
+            keys.push((*key, false));
+        }
+
+        // keys typed by a macro were recorded as the `@` that played it
+        if let Some((_, ref mut keys)) = self.recording {
+            if self.macro_depth == 0 {
+                keys.push(*key);
+            }
+        }
+
+        self.key_counted = false;
+        let result = self.feed_key(key);
+
+        if self.key_counted {
+            if let Some(&mut (_, ref mut counted)) = self.change_keys.as_mut().and_then(|keys| keys.last_mut()) {
+                *counted = true;
+            }
         }
+
+        self.finish_keys();
+
+        self.typed_result(result)
     }
 
-    fn handle_key_prompt(&mut self, key: &Key) -> Result<()> {
-        match self.prompt_mappings.get(key).cloned() {
-            Some(ref command) => self.run_command(command),
-            None => {
-                match *key {
-                    Key::Char(c) => self.prompt.add_char(c),
-                    _ => Ok(()),
-                }
-            },
+    /// Gives up waiting for a longer mapping, running the one typed so far.
+    pub fn handle_timeout(&mut self) -> Result<()> {
+        let mut result = Ok(());
+
+        while !self.pending_keys.is_empty() && result.is_ok() {
+            result = self.resolve_pending_keys();
         }
+        self.pending_keys.clear();
+
+        self.finish_keys();
+
+        self.typed_result(result)
     }
 
-    fn handle_key_insert(&mut self, key: &Key) -> Result<()> {
-        match self.insert_mappings.get(key).cloned() {
-            Some(ref command) => self.run_command(command),
-            None => {
-                match *key {
-                    Key::Enter => self.window.break_line(),
-                    Key::Char(c) => self.window.add_char(c),
-                    _ => Ok(()),
-                }
-            },
+    /// A failed motion stops the macro or `:normal` playing it, but typed
+    /// on its own it just leaves the cursor where it is.
+    fn typed_result(&self, result: Result<()>) -> Result<()> {
+        match result {
+            Err(Error::MotionFailed) if self.macro_depth == 0 => Ok(()),
+            result => result,
         }
     }
 
-    fn leave_insert(&mut self) -> Result<()> {
-        self.switch_to_normal();
-        self.window.ensure_cursor_over_line();
+    fn finish_keys(&mut self) {
+        self.update_incsearch();
 
-        Ok(())
+        // everything typed in one insert session is undone as a single step
+        if self.mode != Mode::Insert {
+            if self.window().buffer().has_uncommitted_changes() {
+                self.changed = true;
+            }
+
+            self.window_mut().commit_changes();
+        }
+
+        // command lines aren't repeated by `.`
+        if self.mode == Mode::Prompt {
+            self.change_keys = None;
+        }
+
+        // once the editor waits for a new command, the keys typed since the
+        // last time make up the last change if they changed the buffer
+        if self.is_idle() {
+            if let Some(keys) = self.change_keys.take() {
+                if self.changed && !keys.is_empty() {
+                    self.last_change = keys;
+                }
+            }
+
+            self.change_keys = Some(Vec::new());
+            self.changed = false;
+        }
+
+        // other windows may show the buffer that was just changed
+        let current_window = self.current_window;
+        for (_, window) in self.windows.iter_mut().filter(|&(&id, _)| id != current_window) {
+            window.fit();
+        }
     }
 
-    fn run_prompt(&mut self) -> Result<()> {
-        self.switch_to_normal();
+    fn feed_key(&mut self, key: &Key) -> Result<()> {
+        if self.confirm.is_some() {
+            return self.confirm_replacement(key);
+        }
 
-        let text = self.prompt.command_text().to_owned();
-        self.prompt.clear();
+        if let Some((command, _)) = self.char_command.take() {
+            let count = self.count.take();
 
-        if text.is_empty() {
-            return Ok(());
+            return match key.typed_char() {
+                Some(c) => self.run_char_command(command, c, count),
+                None => {
+                    self.take_operator();
+                    self.register_name = None;
+                    Ok(())
+                },
+            };
         }
 
-        let command = Command::parse(&text)?;
+        if self.add_to_count(key) {
+            self.key_counted = true;
+            return Ok(());
+        }
 
-        self.run_command(&command)
+        self.pending_keys.push(*key);
+        self.process_pending_keys()
     }
 
-    fn run_command(&mut self, command: &Command) -> Result<()> {
-        match *command {
-            Command::Quit => self.exit(),
-            Command::Write => self.write(),
-            Command::Edit(ref filename) => self.edit(filename),
-            Command::EnterPrompt(c) => self.enter_prompt(c),
-            Command::CancelPrompt => self.cancel_prompt(),
-            Command::RunPrompt => self.run_prompt(),
-            Command::EnterInsert => self.enter_insert(),
-            Command::EnterInsertAfterCursor => self.enter_insert_after_cursor(),
-            Command::OpenLineAfter => self.open_line_after(),
-            Command::OpenLineBefore => self.open_line_before(),
-            Command::LeaveInsert => self.leave_insert(),
-            Command::DeleteCharBeforeCursor => self.window.delete_char(),
-            Command::DeleteCharBeforeCursorInPrompt => self.delete_char_in_prompt(),
-            Command::Movement(movement) => self.window.move_cursor(movement),
+    /// Collects digits typed before a command into its count. A `0` that
+    /// doesn't follow another digit is left alone, since it moves to the
+    /// start of the line.
+    fn add_to_count(&mut self, key: &Key) -> bool {
+        let counting = match self.mode {
+            Mode::Normal | Mode::OperatorPending | Mode::Visual | Mode::VisualLine | Mode::VisualBlock => true,
+            Mode::Insert | Mode::Prompt => false,
+        };
+        if !counting || !self.pending_keys.is_empty() {
+            return false;
         }
+
+        let digit = match key.typed_char().and_then(|c| c.to_digit(10)) {
+            Some(0) if self.count.is_none() => return false,
+            Some(digit) => digit as usize,
+            None => return false,
+        };
+
+        let count = self.count.unwrap_or(0);
+        self.count = Some(count.saturating_mul(10).saturating_add(digit));
+
+        true
     }
 
-    fn write(&mut self) -> Result<()> {
-        self.window.write()?;
-        self.prompt.display_message("File written");
+    fn process_pending_keys(&mut self) -> Result<()> {
+        let command = match self.mappings(self.mode).lookup(&self.pending_keys) {
+            Match::Full(command) => command.clone(),
+            Match::Partial(_) => return Ok(()),
+            Match::None => return self.resolve_pending_keys(),
+        };
 
-        Ok(())
+        let keys = mem::take(&mut self.pending_keys);
+        self.run_mapping(&command, &keys)
     }
 
-    fn delete_char_in_prompt(&mut self) -> Result<()> {
-        self.prompt.delete_grapheme();
+    fn run_mapping(&mut self, command: &Command, keys: &[Key]) -> Result<()> {
+        let count = self.count.take();
+        let result = self.run_command(command, count);
 
-        if self.prompt.command_text().is_empty() {
-            self.switch_to_normal();
+        // the register picked goes with the next command, or with the
+        // operator that's still waiting, maybe for a search to be typed
+        if self.operator.is_none() && self.char_command.is_none() {
+            self.register_name = None;
+        }
+        result?;
+
+        // a command waiting for its argument keeps its count and shows the
+        // keys that started it
+        if let Some((_, ref mut typed)) = self.char_command {
+            *typed = keys.to_vec();
+            self.count = count;
+        }
+
+        // and so does an operator waiting for its motion
+        if let Some(ref mut pending) = self.operator {
+            if pending.keys.is_empty() {
+                pending.keys = keys.to_vec();
+            }
         }
 
         Ok(())
     }
 
-    fn cancel_prompt(&mut self) -> Result<()> {
-        self.switch_to_normal();
-        self.prompt.clear();
+    /// Runs the longest mapping at the start of the pending keys, or handles
+    /// the first key on its own if none matches, and then goes on with the
+    /// keys left.
+    fn resolve_pending_keys(&mut self) -> Result<()> {
+        let keys = mem::take(&mut self.pending_keys);
+
+        let longest = (1..(keys.len() + 1)).rev().filter_map(|len| {
+            match self.mappings(self.mode).lookup(&keys[..len]) {
+                Match::Full(command) | Match::Partial(Some(command)) => Some((command.clone(), len)),
+                _ => None,
+            }
+        }).next();
+
+        let used = match longest {
+            Some((command, len)) => {
+                self.run_mapping(&command, &keys[..len])?;
+                len
+            },
+            None => {
+                self.count = None;
+                self.handle_unmapped_key(&keys[0])?;
+                1
+            },
+        };
+
+        for key in &keys[used..] {
+            self.feed_key(key)?;
+        }
 
         Ok(())
     }
 
-    fn enter_prompt(&mut self, c: char) -> Result<()> {
-        self.mode = Mode::Prompt;
-        self.prompt.start(c)
+    fn handle_unmapped_key(&mut self, key: &Key) -> Result<()> {
+        match self.mode {
+            Mode::Normal | Mode::Visual | Mode::VisualLine | Mode::VisualBlock => Ok(()),
+            Mode::OperatorPending => {
+                self.take_operator();
+                Ok(())
+            },
+            Mode::Prompt => {
+                match key.typed_char() {
+                    Some(c) => self.prompt.add_char(c),
+                    None => Ok(()),
+                }
+            },
+            Mode::Insert => {
+                if *key == Key::from(KeyCode::Enter) {
+                    self.inserted.push('\n');
+                    return self.window_mut().break_line();
+                }
+
+                match key.typed_char() {
+                    Some(c) => {
+                        self.inserted.push(c);
+                        self.window_mut().add_char(c)
+                    },
+                    None => Ok(()),
+                }
+            },
+        }
     }
 
-    fn enter_insert(&mut self) -> Result<()> {
-        self.mode = Mode::Insert;
+    /// Whether the editor waits for a new command in normal mode, with
+    /// nothing typed towards one.
+    fn is_idle(&self) -> bool {
+        self.mode == Mode::Normal && self.pending_keys.is_empty() && self.count.is_none() &&
+            self.operator.is_none() && self.char_command.is_none() && self.register_name.is_none() &&
+            self.confirm.is_none()
+    }
+
+    /// Types the keys of the last change again. A new count takes the
+    /// place of the counts typed with it, and is kept for the next time.
+    fn repeat_change(&mut self, count: Option<usize>) -> Result<()> {
+        if let Some(count) = count {
+            let digits = count.to_string().chars().map(|c| (Key::from(c), true)).collect::<Vec<_>>();
+            let keys = self.last_change.iter().filter(|&&(_, counted)| !counted).cloned();
+
+            self.last_change = digits.into_iter().chain(keys).collect();
+        }
+
+        // the keys of `.` itself aren't a change to repeat
+        self.change_keys = None;
+
+        for &(key, _) in &self.last_change.clone() {
+            self.feed_key(&key)?;
+        }
+
+        while !self.pending_keys.is_empty() {
+            self.resolve_pending_keys()?;
+        }
 
         Ok(())
     }
 
-    fn enter_insert_after_cursor(&mut self) -> Result<()> {
-        self.mode = Mode::Insert;
-        self.window.advance_cursor()?;
+    /// Stops recording, or starts waiting for the register to record into.
+    fn record(&mut self) -> Result<()> {
+        let (name, mut keys) = match self.recording.take() {
+            Some(recording) => recording,
+            None => {
+                self.char_command = Some((CharCommand::RecordMacro, Vec::new()));
+                return Ok(());
+            },
+        };
 
-        Ok(())
+        // the `q` that stopped recording
+        keys.pop();
+
+        let text = keys.iter().map(key_text).collect::<String>();
+        self.registers.record(name, &text)?;
+
+        match ClipboardKind::from_register(name) {
+            Some(kind) => self.clipboard.copy(kind, &text),
+            None => Ok(()),
+        }
     }
 
-    fn open_line_after(&mut self) -> Result<()> {
-        self.mode = Mode::Insert;
-        self.window.add_line_below()?;
-        self.window.set_cur_x(0);
-        self.window.move_cursor(Movement::Down)?;
+    fn start_recording(&mut self, name: char) -> Result<()> {
+        if !Registers::is_writable(name) || name == '"' {
+            return Err(Error::InvalidRegister(name));
+        }
+
+        self.recording = Some((name, Vec::new()));
 
         Ok(())
     }
 
-    fn open_line_before(&mut self) -> Result<()> {
-        self.mode = Mode::Insert;
-        self.window.add_line_above()?;
-        self.window.set_cur_x(0);
+    /// Types the keys in register `name` `count` times, as if they came from
+    /// the keyboard, stopping at the first error. `@@` plays the register
+    /// played last, and `@:` runs the last command line again.
+    fn play_macro(&mut self, name: char, count: Option<usize>) -> Result<()> {
+        let name = match name {
+            '@' => self.last_macro.ok_or(Error::EmptyRegister('@'))?,
+            name => name,
+        };
+        let register = self.read_register(name)?;
+        self.last_macro = Some(name);
+
+        let keys = if name == ':' {
+            let mut keys = vec![Key::from(':')];
+            keys.extend(register.text.chars().map(Key::from));
+            keys.push(Key::from(KeyCode::Enter));
+            keys
+        } else {
+            // lines yanked back into a register after editing them keep
+            // their last line break out of the keys
+            let mut text = &register.text[..];
+            if register.kind == SelectionKind::Linewise && text.ends_with('\n') {
+                text = &text[..text.len() - 1];
+            }
+
+            if text.is_empty() {
+                return Err(Error::EmptyRegister(name));
+            }
+            input::parse_keys(text)?
+        };
+
+        // a macro playing itself only stops here if nothing fails first
+        if self.macro_depth >= MAX_MACRO_DEPTH {
+            return Err(Error::MacroTooDeep);
+        }
+
+        // the changes made by the macro are what `.` repeats, not `@`
+        self.change_keys = Some(Vec::new());
+        self.changed = false;
+
+        self.macro_depth += 1;
+        let result = self.play_keys(&keys, count.unwrap_or(1));
+        self.macro_depth -= 1;
+
+        result
+    }
+
+    fn play_keys(&mut self, keys: &[Key], count: usize) -> Result<()> {
+        for _ in 0..count {
+            let before = self.state();
+
+            for key in keys {
+                self.handle_key(key)?;
+            }
+
+            // playing the keys again would do nothing more
+            if self.state() == before {
+                break;
+            }
+        }
+
+        if !self.pending_keys.is_empty() {
+            self.handle_timeout()?;
+        }
 
         Ok(())
     }
 
-    fn switch_to_normal(&mut self) {
-        self.mode = Mode::Normal;
+    /// What playing keys can change: the window, the cursor, the mode and
+    /// the buffer contents.
+    fn state(&self) -> (usize, View, Mode, usize, usize) {
+        let buffer = self.window().buffer();
+        (self.current_window, self.window().view(), self.mode, buffer.seq(), buffer.changes())
     }
 
-    fn exit(&mut self) -> Result<()> {
-        self.running = false;
+    fn run_char_command(&mut self, command: CharCommand, c: char, count: Option<usize>) -> Result<()> {
+        match command {
+            CharCommand::Find { backward, till } => {
+                let find = Find { target: c, backward: backward, till: till, repeated: false };
+                self.move_cursor(Movement::FindChar(find), count)
+            },
+            CharCommand::SelectRegister => {
+                if !Registers::is_valid(c) {
+                    return Err(Error::InvalidRegister(c));
+                }
 
-        Ok(())
+                self.register_name = Some(c);
+                self.count = count;
+
+                Ok(())
+            },
+            CharCommand::RecordMacro => self.start_recording(c),
+            CharCommand::PlayMacro => self.play_macro(c, count),
+            CharCommand::SetMark => {
+                if !c.is_ascii_lowercase() {
+                    return Err(Error::InvalidMark(c));
+                }
+
+                let line = self.window().view().line();
+                self.window().buffer_mut().set_mark(c, line);
+
+                Ok(())
+            },
+            CharCommand::InsertRegister => {
+                let register = self.read_register(c)?;
+                self.inserted.push_str(&register.text);
+
+                self.window_mut().insert_text(&register.text)
+            },
+        }
     }
 
-    fn edit(&mut self, filename: &str) -> Result<()> {
-        let buffer = Buffer::for_file(filename)?;
+    /// Moves the cursor, or applies the pending operator to the text it moves
+    /// over. The last `f`, `t`, `F` or `T` is kept so it can be repeated with
+    /// `;` and `,`.
+    fn move_cursor(&mut self, movement: Movement, count: Option<usize>) -> Result<()> {
+        let operator = self.take_operator();
+
+        let movement = match movement {
+            Movement::FindChar(find) => {
+                self.last_find = Some(find);
+                movement
+            },
+            Movement::RepeatFind => match self.last_find {
+                Some(find) => Movement::FindChar(find.repeat()),
+                None => return Err(Error::MotionFailed),
+            },
+            Movement::RepeatFindReverse => match self.last_find {
+                Some(find) => Movement::FindChar(find.reversed().repeat()),
+                None => return Err(Error::MotionFailed),
+            },
+            Movement::SearchNext | Movement::SearchPrevious => {
+                let search = match self.last_search {
+                    Some(ref search) if movement == Movement::SearchPrevious => search.reversed(),
+                    Some(ref search) => search.clone(),
+                    None => return Err(Error::NoPreviousPattern),
+                };
 
-        self.window.set_buffer(buffer);
+                // `2d3n` deletes to the sixth match
+                let count = multiply_counts(operator.as_ref().and_then(|pending| pending.count), count);
+                Movement::Jump(self.find(&search, count)?)
+            },
+            movement => movement,
+        };
 
-        self.prompt.display_message(&format!("\"{}\"", &filename));
+        match operator {
+            Some(pending) => self.apply_operator(pending, movement, count),
+            // arrow keys at the end of a line don't stop an insert
+            None if self.mode == Mode::Insert => {
+                match self.window_mut().move_cursor(movement, count) {
+                    Err(Error::MotionFailed) => Ok(()),
+                    result => result,
+                }
+            },
+            None => self.window_mut().move_cursor(movement, count),
+        }
+    }
 
-        Ok(())
+    fn start_operator(&mut self, operator: Operator, count: Option<usize>) -> Result<()> {
+        if let Some(selection) = self.selection() {
+            self.leave_visual()?;
+            return self.operate_on_selection(operator, selection);
+        }
+
+        match self.take_operator() {
+            Some(pending) => {
+                if pending.operator != operator {
+                    return Ok(());
+                }
+
+                let count = multiply_counts(pending.count, count);
+                let range = self.window().line_range(count);
+
+                self.operate(operator, range)
+            },
+            None => {
+                self.mode = Mode::OperatorPending;
+                self.operator = Some(PendingOperator {
+                    operator: operator,
+                    count: count,
+                    keys: Vec::new(),
+                });
+
+                Ok(())
+            },
+        }
+    }
+
+    /// Ends operator-pending mode, returning the operator if there was one.
+    fn take_operator(&mut self) -> Option<PendingOperator> {
+        if self.mode == Mode::OperatorPending {
+            self.switch_to_normal();
+        }
+
+        self.operator.take()
+    }
+
+    fn apply_operator(&mut self, pending: PendingOperator, movement: Movement, count: Option<usize>) -> Result<()> {
+        let count = multiply_counts(pending.count, count);
+
+        let range = match (pending.operator, movement) {
+            (Operator::Change, Movement::WordForward) => self.window().change_word_range(false, count),
+            (Operator::Change, Movement::BigWordForward) => self.window().change_word_range(true, count),
+            (_, movement) => self.window().motion_range(movement, count),
+        };
+
+        match range {
+            Some(range) => self.operate(pending.operator, range),
+            None => Err(Error::MotionFailed),
+        }
+    }
+
+    fn select_object(&mut self, object: TextObject, count: Option<usize>) -> Result<()> {
+        if visual_kind(self.mode).is_some() {
+            return self.select_object_visually(object, count);
+        }
+
+        let pending = match self.take_operator() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+
+        let count = multiply_counts(pending.count, count);
+        match self.window().object_range(object, count) {
+            Some(range) => self.operate(pending.operator, range),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes the selection cover `object`, going linewise for objects made
+    /// of lines.
+    fn select_object_visually(&mut self, object: TextObject, count: Option<usize>) -> Result<()> {
+        let range = match self.window().object_range(object, count) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        if range.linewise && self.mode == Mode::Visual {
+            self.mode = Mode::VisualLine;
+        }
+
+        self.visual_anchor = self.window_mut().select(range);
+
+        Ok(())
+    }
+
+    fn operate_on_selection(&mut self, operator: Operator, selection: Selection) -> Result<()> {
+        if selection.kind != SelectionKind::Blockwise || operator.is_linewise() {
+            let range = self.window().selection_range(&selection);
+            return self.operate(operator, range);
+        }
+
+        let block = selection.block();
+
+        let name = self.take_register_name(operator)?;
+
+        match operator {
+            Operator::Delete => {
+                let deleted = self.window_mut().delete_block(block)?;
+                self.store_register(name, Register::new(&deleted, SelectionKind::Blockwise), true)?;
+            },
+            Operator::Change => {
+                let deleted = self.window_mut().change_block(block)?;
+                self.store_register(name, Register::new(&deleted, SelectionKind::Blockwise), true)?;
+                self.start_insert(None, false);
+            },
+            Operator::Yank => {
+                let yanked = self.window_mut().yank_block(block);
+                self.store_register(name, Register::new(&yanked, SelectionKind::Blockwise), false)?;
+            },
+            Operator::Lowercase => self.window_mut().convert_block(block, |text| text.to_lowercase())?,
+            Operator::Uppercase => self.window_mut().convert_block(block, |text| text.to_uppercase())?,
+            Operator::ToggleCase => self.window_mut().convert_block(block, toggle_case)?,
+            Operator::ShiftRight | Operator::ShiftLeft => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Applies `operator` to whole lines, for the ex commands like `:delete`.
+    fn line_operator(&mut self, range: Option<&LineRange>, operator: Operator) -> Result<()> {
+        let (first, last) = self.range_lines(range)?;
+        self.operate(operator, Range::lines(first, last))
+    }
+
+    /// First and last line of `range`, or the cursor line without one. The
+    /// lines are swapped when the range goes backward.
+    fn range_lines(&mut self, range: Option<&LineRange>) -> Result<(usize, usize)> {
+        let line = self.window().view().line();
+
+        let (first, last) = match range {
+            Some(range) => {
+                let first = self.address_line(&range.start)?;
+                // after `;` the cursor goes to the first line, which the
+                // second address then counts from
+                if range.from_start {
+                    self.window_mut().goto(first, 0);
+                }
+
+                (first, self.address_line(&range.end)?)
+            },
+            None => (line, line),
+        };
+
+        Ok((min(first, last), max(first, last)))
+    }
+
+    /// The line `address` stands for.
+    fn address_line(&mut self, address: &Address) -> Result<usize> {
+        let len = self.window().buffer().len();
+
+        let line = match address.base {
+            // line 0 is before the first line, which is where commands go
+            Base::Number(0) if address.offset == 0 => 0,
+            Base::Number(number) => number as isize - 1,
+            Base::Current => self.window().view().line() as isize,
+            Base::Last => len as isize - 1,
+            Base::Mark(name) => self.mark_line(name)? as isize,
+            Base::Search(ref search) => self.search_line(search)? as isize,
+        };
+
+        let line = line + address.offset;
+        if line < 0 || line >= len as isize {
+            return Err(Error::InvalidRange);
+        }
+
+        Ok(line as usize)
+    }
+
+    /// The line of mark `name`. `'<` and `'>` are the first and last line of
+    /// the last visual selection, and `'a` to `'z` the lines marked with
+    /// `m`, which may be gone since then.
+    fn mark_line(&self, name: char) -> Result<usize> {
+        let line = match (name, self.last_selection) {
+            ('<', Some(selection)) => selection.start.0,
+            ('>', Some(selection)) => selection.end.0,
+            (name, _) if name.is_ascii_lowercase() => self.window().buffer().mark(name).ok_or(Error::MarkNotSet)?,
+            _ => return Err(Error::MarkNotSet),
+        };
+
+        Ok(min(line, self.window().buffer().len() - 1))
+    }
+
+    /// The line a `/pattern/` or `?pattern?` address finds, which becomes
+    /// the search `n` repeats. An empty pattern is the last search.
+    fn search_line(&mut self, search: &Search) -> Result<usize> {
+        let search = match (&search.pattern[..], &self.last_search) {
+            ("", Some(last)) => Search::new(&last.pattern, search.backward),
+            ("", None) => return Err(Error::NoPreviousPattern),
+            _ => search.clone(),
+        };
+
+        let line = self.window().search_line(&search)?;
+
+        self.last_search = Some(search.clone());
+        self.hlsearch = true;
+
+        line.ok_or(Error::PatternNotFound(search.pattern))
+    }
+
+    /// Moves to the last line of `range`, as `:10` does.
+    fn goto_line(&mut self, range: &LineRange) -> Result<()> {
+        let (_, last) = self.range_lines(Some(range))?;
+        self.window_mut().goto_first_non_blank(last);
+
+        Ok(())
+    }
+
+    /// Types `keys` in normal mode at the start of each line of `range`, or
+    /// of the cursor line. Commands the keys leave unfinished are given up
+    /// as with `<Esc>`.
+    fn normal(&mut self, range: Option<&LineRange>, keys: &str) -> Result<()> {
+        let (first, last) = self.range_lines(range)?;
+        let keys = keys.chars().map(Key::from).collect::<Vec<_>>();
+        let mut result = Ok(());
+
+        self.macro_depth += 1;
+
+        for line in first..(last + 1) {
+            // the keys may have deleted the lines left
+            if line >= self.window().buffer().len() {
+                break;
+            }
+
+            self.window_mut().goto(line, 0);
+            result = self.play_keys(&keys, 1);
+
+            if !self.is_idle() {
+                result = result.and(self.handle_key(&Key::from(KeyCode::Esc)));
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.macro_depth -= 1;
+
+        result
+    }
+
+    /// Sorts the lines of `range`, or of the whole buffer, in a single edit.
+    fn sort(&mut self, range: Option<&LineRange>, reverse: bool, ignore_case: bool) -> Result<()> {
+        let all = LineRange::all();
+        let (first, last) = self.range_lines(Some(range.unwrap_or(&all)))?;
+
+        let mut lines = {
+            let buffer = self.window().buffer();
+            (first..(last + 1)).map(|line| buffer.line(line).into_owned()).collect::<Vec<_>>()
+        };
+
+        if ignore_case {
+            lines.sort_by_cached_key(|line| line.to_lowercase());
+        } else {
+            lines.sort();
+        }
+        if reverse {
+            lines.reverse();
+        }
+
+        self.window_mut().replace_lines(first, last, &lines)
+    }
+
+    /// Runs `command` in the shell and shows what it printed. With a range,
+    /// the lines go through the command and what it prints takes their
+    /// place.
+    fn shell(&mut self, range: Option<&LineRange>, command: &str) -> Result<()> {
+        let range = match range {
+            Some(range) => range,
+            None => {
+                let output = shell::run(command, None)?;
+                self.prompt.display_message(output.trim_end_matches('\n'));
+                return Ok(());
+            },
+        };
+
+        let (first, last) = self.range_lines(Some(range))?;
+
+        let mut input = String::new();
+        for line in first..(last + 1) {
+            input.push_str(&self.window().buffer().line(line));
+            input.push('\n');
+        }
+
+        let output = shell::run(command, Some(&input))?;
+        let lines = output.lines().map(str::to_owned).collect::<Vec<_>>();
+
+        self.window_mut().replace_lines(first, last, &lines)
+    }
+
+    /// Replaces matches in the lines of `range`. An empty pattern stands for
+    /// the last search, and the pattern becomes the one `n` looks for.
+    fn substitute(&mut self, range: Option<&LineRange>, substitution: Option<&Substitution>) -> Result<()> {
+        // `:s` alone does the last one again, but without its flags
+        let mut substitution = match (substitution, &self.last_substitution) {
+            (Some(substitution), _) => substitution.clone(),
+            (None, Some(last)) => Substitution { flags: Default::default(), ..last.clone() },
+            (None, None) => return Err(Error::NoPreviousSubstitution),
+        };
+
+        let backward = match self.last_search {
+            Some(ref search) if substitution.pattern.is_empty() => {
+                substitution.pattern = search.pattern.clone();
+                search.backward
+            },
+            Some(ref search) => search.backward,
+            None if substitution.pattern.is_empty() => return Err(Error::NoPreviousPattern),
+            None => false,
+        };
+
+        let (first, last) = self.range_lines(range)?;
+        let replacements = self.window().replacements(&substitution, first, last)?;
+
+        self.last_search = Some(Search::new(&substitution.pattern, backward));
+        self.hlsearch = true;
+        self.last_substitution = Some(substitution.clone());
+
+        if replacements.is_empty() {
+            return Err(Error::PatternNotFound(substitution.pattern));
+        }
+
+        if substitution.flags.count_only {
+            let message = count_message(&replacements, "match", "matches");
+            self.prompt.display_message(&message);
+        } else if substitution.flags.confirm {
+            self.confirm = Some(Confirm {
+                view: self.window().view(),
+                replacements: replacements,
+                accepted: Vec::new(),
+                current: 0,
+            });
+            self.ask_for_confirmation();
+        } else {
+            self.replace(&replacements)?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves to the match the substitution being confirmed is at, and asks
+    /// what to do with it.
+    fn ask_for_confirmation(&mut self) {
+        let (position, text) = match self.confirm {
+            Some(ref confirm) => {
+                let replacement = &confirm.replacements[confirm.current];
+                (replacement.matched.start, replacement.text.clone())
+            },
+            None => return,
+        };
+
+        self.window_mut().goto_position(position);
+        self.prompt.display_message(&format!("replace with {} (y/n/a/q/l)?", text));
+    }
+
+    /// Answers the question about the current match: `y` replaces it, `n`
+    /// skips it, `a` replaces it and all the rest, `l` replaces it and
+    /// stops, and `q` or `<Esc>` stop.
+    fn confirm_replacement(&mut self, key: &Key) -> Result<()> {
+        let mut confirm = match self.confirm.take() {
+            Some(confirm) => confirm,
+            None => return Ok(()),
+        };
+
+        let current = confirm.replacements[confirm.current].clone();
+        let done = match (key.typed_char(), key.code) {
+            (Some('y'), _) => {
+                confirm.accepted.push(current);
+                false
+            },
+            (Some('n'), _) => false,
+            (Some('a'), _) => {
+                confirm.accepted.extend(confirm.replacements.drain(confirm.current..));
+                true
+            },
+            (Some('l'), _) => {
+                confirm.accepted.push(current);
+                true
+            },
+            (Some('q'), _) | (_, KeyCode::Esc) => true,
+            _ => {
+                // anything else asks again
+                self.confirm = Some(confirm);
+                return Ok(());
+            },
+        };
+
+        confirm.current += 1;
+        if !done && confirm.current < confirm.replacements.len() {
+            self.confirm = Some(confirm);
+            self.ask_for_confirmation();
+            return Ok(());
+        }
+
+        if confirm.accepted.is_empty() {
+            self.window_mut().set_view(confirm.view);
+            self.prompt.clear();
+            return Ok(());
+        }
+
+        self.replace(&confirm.accepted)
+    }
+
+    /// Makes the replacements of a substitution and tells how many there
+    /// were.
+    fn replace(&mut self, replacements: &[Replacement]) -> Result<()> {
+        self.window_mut().substitute(replacements)?;
+
+        let message = count_message(replacements, "substitution", "substitutions");
+        self.prompt.display_message(&message);
+
+        Ok(())
+    }
+
+    fn operate(&mut self, operator: Operator, range: Range) -> Result<()> {
+        let range = if operator.is_linewise() {
+            Range::lines(range.start.line, range.end.line)
+        } else {
+            range
+        };
+        let shiftwidth = self.shiftwidth;
+        let name = self.take_register_name(operator)?;
+        let kind = if range.linewise { SelectionKind::Linewise } else { SelectionKind::Charwise };
+
+        match operator {
+            Operator::Delete => {
+                let deleted = self.window_mut().delete(range)?;
+                self.store_register(name, Register::new(&deleted, kind), true)?;
+            },
+            Operator::Change => {
+                let deleted = self.window_mut().change(range)?;
+                self.store_register(name, Register::new(&deleted, kind), true)?;
+                self.start_insert(None, false);
+            },
+            Operator::Yank => {
+                let yanked = self.window_mut().yank(range);
+                self.store_register(name, Register::new(&yanked, kind), false)?;
+            },
+            Operator::ShiftRight => self.window_mut().shift(range, shiftwidth, true)?,
+            Operator::ShiftLeft => self.window_mut().shift(range, shiftwidth, false)?,
+            Operator::Lowercase => self.window_mut().convert(range, |text| text.to_lowercase())?,
+            Operator::Uppercase => self.window_mut().convert(range, |text| text.to_uppercase())?,
+            Operator::ToggleCase => self.window_mut().convert(range, toggle_case)?,
+        }
+
+        Ok(())
+    }
+
+    /// The register picked for `operator`, checking first that it can be
+    /// written to if the operator stores text.
+    fn take_register_name(&mut self, operator: Operator) -> Result<Option<char>> {
+        let name = self.register_name.take();
+
+        match (operator, name) {
+            (Operator::Delete, Some(name)) | (Operator::Change, Some(name)) | (Operator::Yank, Some(name))
+                if !Registers::is_writable(name) => Err(Error::InvalidRegister(name)),
+            _ => Ok(name),
+        }
+    }
+
+    fn put(&mut self, after: bool, count: Option<usize>) -> Result<()> {
+        let name = self.register_name.unwrap_or('"');
+        let register = self.read_register(name)?;
+
+        self.window_mut().put(&register, after, count.unwrap_or(1))
+    }
+
+    /// Lists the registers with something in them, showing line breaks and
+    /// other control chars as `^J` and the like.
+    fn list_registers(&mut self) -> Result<()> {
+        let mut names = self.registers.names();
+        names.push('%');
+
+        let width = self.width;
+        let mut lines = vec!["Type Name Content".to_owned()];
+
+        for name in names {
+            let register = match self.read_register(name) {
+                Ok(register) => register,
+                Err(_) => continue,
+            };
+
+            let kind = match register.kind {
+                SelectionKind::Charwise => 'c',
+                SelectionKind::Linewise => 'l',
+                SelectionKind::Blockwise => 'b',
+            };
+
+            let mut line = format!("  {}  \"{}   ", kind, name);
+            for c in register.text.chars() {
+                if (c as u32) < 0x20 {
+                    line.push('^');
+                    line.push((c as u8 + b'@') as char);
+                } else {
+                    line.push(c);
+                }
+            }
+
+            lines.push(line.chars().take(width).collect());
+        }
+
+        self.prompt.display_message(&lines.join("\n"));
+
+        Ok(())
+    }
+
+    fn leave_insert(&mut self) -> Result<()> {
+        let inserted = mem::take(&mut self.inserted);
+        self.registers.set_last_inserted(&inserted);
+
+        if self.insert_count > 1 && !inserted.is_empty() {
+            let copy = if self.insert_lines { format!("\n{}", inserted) } else { inserted.clone() };
+            let copies = (1..self.insert_count).map(|_| &copy[..]).collect::<String>();
+
+            self.window_mut().insert_text(&copies)?;
+        }
+
+        self.switch_to_normal();
+        self.window_mut().ensure_cursor_over_line();
+
+        Ok(())
+    }
+
+    fn run_prompt(&mut self) -> Result<()> {
+        self.end_incsearch();
+        self.switch_to_normal();
+
+        let text = self.prompt.command_text().to_owned();
+        self.prompt.clear();
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        if text.starts_with('/') || text.starts_with('?') {
+            let result = self.search(&text[1..], text.starts_with('?'));
+            // the operator waiting for the search is given up if it fails
+            self.operator = None;
+            return result;
+        }
+
+        if text.starts_with(':') && text.len() > 1 {
+            self.registers.set_last_command(&text[1..]);
+        }
+
+        let command = Command::parse(&text)?;
+
+        self.run_command(&command, None)
+    }
+
+    /// Searches for `pattern`, or for the last pattern again when it's
+    /// empty, and makes it the search `n` and `N` repeat.
+    fn search(&mut self, pattern: &str, backward: bool) -> Result<()> {
+        let pattern = match (pattern, &self.last_search) {
+            ("", Some(search)) => search.pattern.clone(),
+            ("", None) => return Err(Error::NoPreviousPattern),
+            (pattern, _) => pattern.to_owned(),
+        };
+
+        self.last_search = Some(Search::new(&pattern, backward));
+
+        let count = self.search_count.take();
+        self.move_cursor(Movement::SearchNext, count)
+    }
+
+    /// Where the `count`th match of `search` is, telling when the search
+    /// went on from the other end of the buffer.
+    fn find(&mut self, search: &Search, count: Option<usize>) -> Result<Position> {
+        let found = match self.window().search(search, count)? {
+            Some(found) => found,
+            None => return Err(Error::PatternNotFound(search.pattern.clone())),
+        };
+
+        self.hlsearch = true;
+
+        if found.wrapped {
+            let message = if search.backward {
+                "search hit TOP, continuing at BOTTOM"
+            } else {
+                "search hit BOTTOM, continuing at TOP"
+            };
+            self.prompt.display_message(message);
+        }
+
+        Ok(found.matched.start)
+    }
+
+    /// Moves the cursor to the match of the search being typed, searching
+    /// from where the cursor was when it started.
+    fn update_incsearch(&mut self) {
+        let view = match self.incsearch {
+            Some(ref incsearch) => incsearch.view,
+            None => return,
+        };
+
+        if self.mode != Mode::Prompt {
+            return self.end_incsearch();
+        }
+
+        let text = self.prompt.command_text().to_owned();
+        let search = Search::new(&text[1..], text.starts_with('?'));
+
+        self.window_mut().set_view(view);
+
+        // a pattern being typed may not be valid yet
+        let matched = match self.window().search(&search, None) {
+            Ok(Some(found)) if !search.pattern.is_empty() => Some(found.matched),
+            _ => None,
+        };
+
+        if let Some(matched) = matched {
+            self.window_mut().goto_position(matched.start);
+        }
+
+        self.incsearch = Some(IncSearch { view: view, matched: matched });
+    }
+
+    /// Puts the cursor back where it was before the search being typed.
+    fn end_incsearch(&mut self) {
+        if let Some(incsearch) = self.incsearch.take() {
+            self.window_mut().set_view(incsearch.view);
+        }
+    }
+
+    /// Runs `command`, `count` times for the commands that can be repeated.
+    fn run_command(&mut self, command: &Command, count: Option<usize>) -> Result<()> {
+        match *command {
+            Command::Quit => self.quit(),
+            Command::ForceQuit => self.force_quit(),
+            Command::WriteQuit => self.write_quit(),
+            Command::UpdateQuit => self.update_quit(),
+            Command::Write(ref range, ref filename) => self.write(range.as_ref(), filename.as_ref().map(|s| &s[..])),
+            Command::Edit(ref filename) => self.edit(filename.as_ref().map(|s| &s[..])),
+            Command::ForceEdit(ref filename) => self.force_edit(filename.as_ref().map(|s| &s[..])),
+            Command::EnterPrompt(c) => self.enter_prompt(c, count),
+            Command::CancelPrompt => self.cancel_prompt(),
+            Command::RunPrompt => self.run_prompt(),
+            Command::EnterInsert => self.enter_insert(count),
+            Command::EnterInsertAfterCursor => self.enter_insert_after_cursor(count),
+            Command::OpenLineAfter => self.open_line_after(count),
+            Command::OpenLineBefore => self.open_line_before(count),
+            Command::LeaveInsert => self.leave_insert(),
+            Command::DeleteCharBeforeCursor => {
+                self.inserted.pop();
+                self.window_mut().delete_char()
+            },
+            Command::DeleteCharBeforeCursorInPrompt => self.delete_char_in_prompt(),
+            Command::Movement(movement) => self.move_cursor(movement, count),
+            Command::Operator(operator) => self.start_operator(operator, count),
+            Command::TextObject(object) => self.select_object(object, count),
+            Command::Visual(kind) => self.enter_visual(kind),
+            Command::LeaveVisual => self.leave_visual(),
+            Command::SwapSelectionEnds => self.swap_selection_ends(),
+            Command::LineOperator(ref range, operator) => self.line_operator(range.as_ref(), operator),
+            Command::Goto(ref range) => self.goto_line(range),
+            Command::Normal(ref range, ref keys) => self.normal(range.as_ref(), keys),
+            Command::Sort { ref range, reverse, ignore_case } => self.sort(range.as_ref(), reverse, ignore_case),
+            Command::Shell(ref range, ref command) => self.shell(range.as_ref(), command),
+            Command::WaitForChar(command) => {
+                self.char_command = Some((command, Vec::new()));
+                Ok(())
+            },
+            Command::RepeatChange => self.repeat_change(count),
+            Command::Record => self.record(),
+            Command::PutAfter => self.put(true, count),
+            Command::PutBefore => self.put(false, count),
+            Command::ListRegisters => self.list_registers(),
+            Command::NoHighlightSearch => {
+                self.hlsearch = false;
+                Ok(())
+            },
+            Command::Substitute(ref range, ref substitution) => self.substitute(range.as_ref(), substitution.as_ref()),
+            Command::Undo => self.undo(count.unwrap_or(1)),
+            Command::Redo => self.redo(count.unwrap_or(1)),
+            Command::Earlier(count) => self.earlier(count),
+            Command::Later(count) => self.later(count),
+            Command::Buffer(number) => self.switch_to_buffer(number),
+            Command::AlternateBuffer => self.alternate_buffer(),
+            Command::NextBuffer => self.next_buffer(),
+            Command::PreviousBuffer => self.previous_buffer(),
+            Command::DeleteBuffer(number) => self.delete_buffer(number, false),
+            Command::ForceDeleteBuffer(number) => self.delete_buffer(number, true),
+            Command::ListBuffers => self.list_buffers(),
+            Command::Split(ref filename) => self.split(filename.as_ref().map(|s| &s[..]), Orientation::Horizontal),
+            Command::VerticalSplit(ref filename) => self.split(filename.as_ref().map(|s| &s[..]), Orientation::Vertical),
+            Command::CloseWindow => self.close_window(),
+            Command::OnlyWindow => self.only_window(),
+            Command::FocusWindow(side) => self.focus_neighbour(side),
+            Command::NextWindow => self.next_window(),
+            Command::ResizeWindow(orientation, size) => self.resize_window(orientation, size),
+            Command::EqualizeWindows => self.equalize_windows(),
+            Command::Set(ref name, ref value) => self.set(name, value.as_ref().map(|s| &s[..])),
+        }
+    }
+
+    /// Writes the buffer to its file, or the lines of `range` to another
+    /// one. Without a range, that's every line.
+    fn write(&mut self, range: Option<&LineRange>, filename: Option<&str>) -> Result<()> {
+        let all = LineRange::all();
+        let (first, last) = self.range_lines(Some(range.unwrap_or(&all)))?;
+        let whole = first == 0 && last == self.window().buffer().len() - 1;
+
+        let written = match filename {
+            Some(filename) if !self.is_current_file(filename) => {
+                self.window().buffer().write_lines(filename, first, last)?
+            },
+            _ if whole => self.window_mut().write()?,
+            _ => return Err(Error::PartialWrite),
+        };
+
+        match written {
+            Written::Atomically => self.prompt.display_message("File written"),
+            Written::InPlace => self.prompt.display_message("File written in place, not atomically"),
+        }
+
+        Ok(())
+    }
+
+    fn set(&mut self, name: &str, value: Option<&str>) -> Result<()> {
+        match name {
+            "fileformat" | "ff" => {
+                match value {
+                    Some("unix") => self.window().buffer_mut().set_file_format(FileFormat::Unix),
+                    Some("dos") => self.window().buffer_mut().set_file_format(FileFormat::Dos),
+                    Some(value) => return Err(Error::InvalidArgument(value.to_owned())),
+                    None => {
+                        let format = self.window().buffer().file_format();
+                        self.prompt.display_message(&format!("fileformat={}", format.name()));
+                    },
+                }
+
+                Ok(())
+            },
+            "fileencoding" | "fenc" => {
+                match value {
+                    Some(value) => {
+                        let encoding = Encoding::from_name(value)?;
+                        self.window().buffer_mut().set_encoding(encoding);
+                    },
+                    None => {
+                        let encoding = self.window().buffer().encoding();
+                        self.prompt.display_message(&format!("fileencoding={}", encoding.name()));
+                    },
+                }
+
+                Ok(())
+            },
+            "timeoutlen" | "tm" => {
+                match value {
+                    Some(value) => {
+                        self.timeout = value.parse().map_err(|_| Error::InvalidArgument(value.to_owned()))?;
+                    },
+                    None => {
+                        let timeout = self.timeout;
+                        self.prompt.display_message(&format!("timeoutlen={}", timeout));
+                    },
+                }
+
+                Ok(())
+            },
+            "clipboard" | "cb" => {
+                match value {
+                    Some(value) => self.clipboard = clipboard::from_name(value)?,
+                    None => return Err(Error::InvalidArgument("".to_owned())),
+                }
+
+                Ok(())
+            },
+            "shiftwidth" | "sw" => {
+                match value {
+                    Some(value) => {
+                        self.shiftwidth = value.parse().map_err(|_| Error::InvalidArgument(value.to_owned()))?;
+                    },
+                    None => {
+                        let shiftwidth = self.shiftwidth;
+                        self.prompt.display_message(&format!("shiftwidth={}", shiftwidth));
+                    },
+                }
+
+                Ok(())
+            },
+            _ => Err(Error::UnknownOption(name.to_owned())),
+        }
+    }
+
+    fn undo(&mut self, count: usize) -> Result<()> {
+        for _ in 0..count {
+            if !self.window_mut().undo() {
+                self.prompt.display_message("Already at oldest change");
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn redo(&mut self, count: usize) -> Result<()> {
+        for _ in 0..count {
+            if !self.window_mut().redo() {
+                self.prompt.display_message("Already at newest change");
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn earlier(&mut self, count: usize) -> Result<()> {
+        if !self.window_mut().earlier(count) {
+            self.prompt.display_message("Already at oldest change");
+        }
+
+        Ok(())
+    }
+
+    fn later(&mut self, count: usize) -> Result<()> {
+        if !self.window_mut().later(count) {
+            self.prompt.display_message("Already at newest change");
+        }
+
+        Ok(())
+    }
+
+    fn delete_char_in_prompt(&mut self) -> Result<()> {
+        self.prompt.delete_grapheme();
+
+        if self.prompt.command_text().is_empty() {
+            return self.cancel_prompt();
+        }
+
+        Ok(())
+    }
+
+    fn cancel_prompt(&mut self) -> Result<()> {
+        self.end_incsearch();
+        self.switch_to_normal();
+        self.prompt.clear();
+
+        // along with the operator waiting for the search, if there's one
+        self.operator = None;
+        self.search_count = None;
+
+        Ok(())
+    }
+
+    /// Starts a command line. From visual mode it starts with the range of
+    /// the selected lines. A search keeps its count, and the operator
+    /// waiting for it, until it's typed.
+    fn enter_prompt(&mut self, c: char, count: Option<usize>) -> Result<()> {
+        let visual = visual_kind(self.mode).is_some();
+        if visual {
+            self.leave_visual()?;
+        }
+
+        self.mode = Mode::Prompt;
+        self.prompt.start(c)?;
+
+        if c == '/' || c == '?' {
+            self.incsearch = Some(IncSearch { view: self.window().view(), matched: None });
+            self.search_count = count;
+        }
+
+        if visual {
+            for c in "'<,'>".chars() {
+                self.prompt.add_char(c)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts selecting text, or switches to another kind of selection. The
+    /// kind of selection already going on ends it.
+    fn enter_visual(&mut self, kind: SelectionKind) -> Result<()> {
+        if visual_kind(self.mode) == Some(kind) {
+            return self.leave_visual();
+        }
+
+        if visual_kind(self.mode).is_none() {
+            let (line, x) = (self.window().view().line(), self.window().cursor().1);
+            self.visual_anchor = (line, x);
+        }
+
+        self.mode = visual_mode(kind);
+
+        Ok(())
+    }
+
+    /// Ends visual mode, remembering the selection for `'<,'>`.
+    fn leave_visual(&mut self) -> Result<()> {
+        if let Some(selection) = self.selection() {
+            self.last_selection = Some(selection);
+        }
+
+        self.switch_to_normal();
+
+        Ok(())
+    }
+
+    /// Moves the cursor to the anchor of the selection and the anchor to
+    /// where the cursor was.
+    fn swap_selection_ends(&mut self) -> Result<()> {
+        let cursor = (self.window().view().line(), self.window().cursor().1);
+        let (line, x) = self.visual_anchor;
+
+        self.window_mut().goto(line, x);
+        self.visual_anchor = cursor;
+
+        Ok(())
+    }
+
+    fn enter_insert(&mut self, count: Option<usize>) -> Result<()> {
+        self.start_insert(count, false);
+
+        Ok(())
+    }
+
+    fn enter_insert_after_cursor(&mut self, count: Option<usize>) -> Result<()> {
+        self.start_insert(count, false);
+        self.window_mut().advance_cursor()?;
+
+        Ok(())
+    }
+
+    fn open_line_after(&mut self, count: Option<usize>) -> Result<()> {
+        self.start_insert(count, true);
+        self.window_mut().add_line_below()?;
+        self.window_mut().set_cur_x(0);
+        self.window_mut().move_cursor(Movement::Down, None)?;
+
+        Ok(())
+    }
+
+    fn open_line_before(&mut self, count: Option<usize>) -> Result<()> {
+        self.start_insert(count, true);
+        self.window_mut().add_line_above()?;
+        self.window_mut().set_cur_x(0);
+
+        Ok(())
+    }
+
+    /// Starts an insert session, whose text `".` holds once it's over. The
+    /// text goes in `count` times, on lines of its own if `lines` is set.
+    fn start_insert(&mut self, count: Option<usize>, lines: bool) {
+        self.mode = Mode::Insert;
+        self.inserted.clear();
+        self.insert_count = count.unwrap_or(1);
+        self.insert_lines = lines;
+    }
+
+    fn switch_to_normal(&mut self) {
+        self.mode = Mode::Normal;
+    }
+
+    fn exit(&mut self) -> Result<()> {
+        self.running = false;
+
+        Ok(())
+    }
+
+    fn quit(&mut self) -> Result<()> {
+        if self.windows.len() > 1 {
+            return self.close_window();
+        }
+
+        self.check_unsaved_changes()?;
+        self.check_hidden_unsaved_changes()?;
+        self.exit()
+    }
+
+    fn force_quit(&mut self) -> Result<()> {
+        if self.windows.len() > 1 {
+            return self.close_window();
+        }
+
+        self.exit()
+    }
+
+    fn write_quit(&mut self) -> Result<()> {
+        self.window_mut().write()?;
+        self.quit_window()
+    }
+
+    fn update_quit(&mut self) -> Result<()> {
+        if self.window().is_modified() {
+            self.window_mut().write()?;
+        }
+
+        self.quit_window()
+    }
+
+    /// Closes the current window, or leaves the editor if it's the last one.
+    fn quit_window(&mut self) -> Result<()> {
+        if self.windows.len() > 1 {
+            return self.close_window();
+        }
+
+        self.check_hidden_unsaved_changes()?;
+        self.exit()
+    }
+
+    fn check_unsaved_changes(&self) -> Result<()> {
+        if self.window().is_modified() {
+            Err(Error::UnsavedChanges)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_hidden_unsaved_changes(&self) -> Result<()> {
+        let current = self.buffers.current();
+
+        for entry in self.buffers.entries() {
+            if entry.number() != current && entry.buffer().borrow().is_modified() {
+                return Err(Error::UnsavedChangesInBuffer(entry.number()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn edit(&mut self, filename: Option<&str>) -> Result<()> {
+        match filename {
+            Some(filename) if !self.is_current_file(filename) => self.open(filename),
+            _ => {
+                self.check_unsaved_changes()?;
+                self.reload()
+            },
+        }
+    }
+
+    fn force_edit(&mut self, filename: Option<&str>) -> Result<()> {
+        match filename {
+            Some(filename) if !self.is_current_file(filename) => self.open(filename),
+            _ => self.reload(),
+        }
+    }
+
+    fn is_current_file(&self, filename: &str) -> bool {
+        self.window().buffer().filename() == Some(filename)
+    }
+
+    /// Switches to the buffer for `filename`, loading it if it isn't open
+    /// yet. An empty, unnamed and unmodified current buffer is reused.
+    fn open(&mut self, filename: &str) -> Result<()> {
+        match self.buffers.find(filename) {
+            Some(number) => self.switch_to_buffer(number),
+            None => {
+                let buffer = Buffer::for_file(filename)?;
+                let shown_elsewhere = self.windows.values()
+                    .filter(|window| window.shows(&self.current_buffer()))
+                    .count() > 1;
+                let reusable = !shown_elsewhere && {
+                    let current = self.window().buffer();
+                    current.filename().is_none() && !current.is_modified() && current.is_empty()
+                };
+
+                if reusable {
+                    *self.window().buffer_mut() = buffer;
+                    let current = self.current_buffer();
+                    self.window_mut().set_buffer(current, View::default());
+                    self.display_file_info();
+
+                    Ok(())
+                } else {
+                    let number = self.buffers.add(buffer);
+                    self.switch_to_buffer(number)
+                }
+            },
+        }
+    }
+
+    /// Rereads the current buffer from its file, dropping any changes.
+    fn reload(&mut self) -> Result<()> {
+        let filename = match self.window().filename() {
+            Some(filename) => filename,
+            None => return Err(Error::NoFileName),
+        };
+
+        let buffer = Buffer::for_file(&filename)?;
+
+        *self.window().buffer_mut() = buffer;
+        let current = self.current_buffer();
+        self.window_mut().set_buffer(current, View::default());
+        self.display_file_info();
+
+        Ok(())
+    }
+
+    fn current_buffer(&self) -> Rc<RefCell<Buffer>> {
+        self.buffers.get(self.buffers.current()).unwrap().buffer().clone()
+    }
+
+    fn switch_to_buffer(&mut self, number: usize) -> Result<()> {
+        let (buffer, view) = match self.buffers.get(number) {
+            Some(entry) => (entry.buffer().clone(), entry.view()),
+            None => return Err(Error::NoSuchBuffer(number)),
+        };
+
+        if number != self.buffers.current() {
+            let current_view = self.window().view();
+            self.buffers.switch(number, current_view);
+            self.window_mut().set_buffer(buffer, view);
+        }
+
+        self.display_file_info();
+
+        Ok(())
+    }
+
+    fn alternate_buffer(&mut self) -> Result<()> {
+        match self.buffers.alternate() {
+            Some(number) => self.switch_to_buffer(number),
+            None => Err(Error::NoAlternateFile),
+        }
+    }
+
+    fn next_buffer(&mut self) -> Result<()> {
+        let number = self.buffers.next(self.buffers.current());
+        self.switch_to_buffer(number)
+    }
+
+    fn previous_buffer(&mut self) -> Result<()> {
+        let number = self.buffers.previous(self.buffers.current());
+        self.switch_to_buffer(number)
+    }
+
+    fn delete_buffer(&mut self, number: Option<usize>, force: bool) -> Result<()> {
+        let current = self.buffers.current();
+        let number = number.unwrap_or(current);
+
+        match self.buffers.get(number) {
+            Some(entry) => {
+                if !force && entry.buffer().borrow().is_modified() {
+                    return Err(Error::UnsavedChangesInBuffer(number));
+                }
+            },
+            None => return Err(Error::NoSuchBuffer(number)),
+        }
+
+        if number == current {
+            let next = match self.buffers.alternate() {
+                Some(alternate) => alternate,
+                None => self.buffers.next(number),
+            };
+
+            if next == number {
+                let empty = self.buffers.add(Buffer::new());
+                self.switch_to_buffer(empty)?;
+            } else {
+                self.switch_to_buffer(next)?;
+            }
+        }
+
+        let deleted = self.buffers.get(number).unwrap().buffer().clone();
+        let replacement = self.current_buffer();
+        for window in self.windows.values_mut() {
+            if window.shows(&deleted) {
+                window.set_buffer(replacement.clone(), View::default());
+            }
+        }
+
+        self.buffers.remove(number);
+
+        Ok(())
+    }
+
+    fn list_buffers(&mut self) -> Result<()> {
+        let current = self.buffers.current();
+        let alternate = self.buffers.alternate();
+
+        let lines = self.buffers.entries().iter().map(|entry| {
+            let buffer = entry.buffer().borrow();
+            let line = if entry.number() == current {
+                self.window().view().line()
+            } else {
+                entry.view().line()
+            };
+
+            format!("{:3} {}{} \"{}\" line {}",
+                    entry.number(),
+                    if entry.number() == current { '%' } else if Some(entry.number()) == alternate { '#' } else { ' ' },
+                    if buffer.is_modified() { '+' } else { ' ' },
+                    buffer.filename().unwrap_or("[No Name]"),
+                    line + 1)
+        }).collect::<Vec<_>>();
+
+        self.prompt.display_message(&lines.join("\n"));
+
+        Ok(())
+    }
+
+    fn display_file_info(&mut self) {
+        let message = {
+            let buffer = self.window().buffer();
+
+            let mut message = format!("\"{}\"", buffer.filename().unwrap_or("[No Name]"));
+            if buffer.encoding() != Encoding::Utf8 {
+                message.push_str(&format!(" [{}]", buffer.encoding().name()));
+            }
+            let invalid = buffer.invalid_bytes();
+            if invalid > 0 {
+                message.push_str(&format!(" [{} invalid {}]", invalid, if invalid == 1 { "byte" } else { "bytes" }));
+            }
+            if buffer.file_format() == FileFormat::Dos {
+                message.push_str(" [dos]");
+            }
+            if !buffer.has_eol() && !buffer.is_empty() {
+                message.push_str(" [noeol]");
+            }
+            if buffer.is_modified() {
+                message.push_str(" [Modified]");
+            }
+
+            message
+        };
+
+        self.prompt.display_message(&message);
+    }
+
+    /// Screen area shared by the windows, everything but the prompt line.
+    fn windows_area(&self) -> Rect {
+        Rect { y: 0, x: 0, height: self.height - 1, width: self.width }
+    }
+
+    fn arrange_windows(&mut self) {
+        let rects = self.layout.arrange(self.windows_area());
+        let status_line = if rects.len() > 1 { 1 } else { 0 };
+
+        for (id, rect) in rects {
+            let height = max(rect.height.saturating_sub(status_line), 1);
+            let width = max(rect.width, 1);
+
+            self.windows.get_mut(&id).unwrap().set_area(rect.y, rect.x, height, width);
+        }
+    }
+
+    fn split(&mut self, filename: Option<&str>, orientation: Orientation) -> Result<()> {
+        let area = self.windows_area();
+        let rect = match self.layout.arrange(area).into_iter().find(|&(id, _)| id == self.current_window) {
+            Some((_, rect)) => rect,
+            None => area,
+        };
+
+        let fits = match orientation {
+            Orientation::Horizontal => rect.height >= 4,
+            Orientation::Vertical => rect.width >= 3 && rect.height >= 2,
+        };
+        if !fits {
+            return Err(Error::NoRoom);
+        }
+
+        let id = self.next_window;
+        self.next_window += 1;
+
+        let mut window = Window::new(1, 1, self.current_buffer());
+        window.set_buffer(self.current_buffer(), self.window().view());
+
+        self.windows.insert(id, window);
+        self.layout.split(self.current_window, id, orientation);
+        self.current_window = id;
+        self.arrange_windows();
+
+        match filename {
+            Some(filename) => self.open(filename),
+            None => Ok(()),
+        }
+    }
+
+    fn close_window(&mut self) -> Result<()> {
+        if self.windows.len() == 1 {
+            return Err(Error::LastWindow);
+        }
+
+        let closing = self.current_window;
+        let order = self.layout.windows();
+        let index = order.iter().position(|&id| id == closing).unwrap();
+        let next = if index + 1 < order.len() { order[index + 1] } else { order[index - 1] };
+
+        self.focus_window(next);
+        self.layout.remove(closing);
+        self.windows.remove(&closing);
+        self.arrange_windows();
+
+        Ok(())
+    }
+
+    fn only_window(&mut self) -> Result<()> {
+        let current = self.current_window;
+
+        for id in self.layout.windows() {
+            if id != current {
+                self.layout.remove(id);
+                self.windows.remove(&id);
+            }
+        }
+
+        self.arrange_windows();
+
+        Ok(())
+    }
+
+    fn focus_window(&mut self, id: usize) {
+        if id == self.current_window {
+            return;
+        }
+
+        let view = self.window().view();
+        self.current_window = id;
+
+        let number = self.buffers.number_of(&self.window().buffer_handle());
+        if let Some(number) = number {
+            self.buffers.switch(number, view);
+        }
+    }
+
+    fn focus_neighbour(&mut self, side: Side) -> Result<()> {
+        let cursor = self.cursor();
+
+        if let Some(id) = self.layout.neighbour(self.current_window, side, self.windows_area(), cursor) {
+            self.focus_window(id);
+        }
+
+        Ok(())
+    }
+
+    fn next_window(&mut self) -> Result<()> {
+        let order = self.layout.windows();
+        let index = order.iter().position(|&id| id == self.current_window).unwrap();
+
+        self.focus_window(order[(index + 1) % order.len()]);
+
+        Ok(())
+    }
+
+    fn resize_window(&mut self, orientation: Orientation, size: Size) -> Result<()> {
+        let area = self.windows_area();
+
+        // heights are given in lines of text, the layout counts the status
+        // line too
+        let size = match (orientation, size) {
+            (Orientation::Horizontal, Size::Absolute(height)) => Size::Absolute(height + 1),
+            _ => size,
+        };
+
+        self.layout.resize(self.current_window, orientation, size, area);
+        self.arrange_windows();
+
+        Ok(())
+    }
+
+    fn equalize_windows(&mut self) -> Result<()> {
+        self.layout.equalize();
+        self.arrange_windows();
+
+        Ok(())
+    }
+}
+
+/// How a key is written in a recorded macro: printable chars as they are,
+/// and other keys in `<>` notation.
+fn key_text(key: &Key) -> String {
+    if *key == Key::from(' ') {
+        " ".to_owned()
+    } else {
+        key.to_string()
+    }
+}
+
+/// How many replacements there are and on how many lines, like "3
+/// substitutions on 2 lines".
+fn count_message(replacements: &[Replacement], singular: &str, plural: &str) -> String {
+    let mut lines = replacements.iter().map(|replacement| replacement.start.0).collect::<Vec<_>>();
+    lines.dedup();
+
+    let count = replacements.len();
+    format!("{} {} on {} {}",
+            count, if count == 1 { singular } else { plural },
+            lines.len(), if lines.len() == 1 { "line" } else { "lines" })
+}
+
+/// The count of an operator and of its motion multiply, so `2d3w` deletes
+/// six words.
+fn multiply_counts(first: Option<usize>, second: Option<usize>) -> Option<usize> {
+    match (first, second) {
+        (Some(first), Some(second)) => Some(first.saturating_mul(second)),
+        (first, second) => first.or(second),
+    }
+}
+
+fn toggle_case(text: &str) -> String {
+    text.chars().flat_map(|c| {
+        if c.is_lowercase() {
+            c.to_uppercase().collect::<Vec<_>>()
+        } else {
+            c.to_lowercase().collect::<Vec<_>>()
+        }
+    }).collect()
+}
+
+fn visual_kind(mode: Mode) -> Option<SelectionKind> {
+    match mode {
+        Mode::Visual => Some(SelectionKind::Charwise),
+        Mode::VisualLine => Some(SelectionKind::Linewise),
+        Mode::VisualBlock => Some(SelectionKind::Blockwise),
+        _ => None,
+    }
+}
+
+fn visual_mode(kind: SelectionKind) -> Mode {
+    match kind {
+        SelectionKind::Charwise => Mode::Visual,
+        SelectionKind::Linewise => Mode::VisualLine,
+        SelectionKind::Blockwise => Mode::VisualBlock,
     }
 }
@@ -0,0 +1,307 @@
+use std::char;
+use std::mem;
+
+use error::{Error, Result};
+
+/// Bytes that can't be decoded are kept in the text as chars from the end of
+/// the last private use plane, one per byte, so they can be displayed as
+/// escapes and written back unchanged. Those chars found in a file are kept
+/// the same way, as the bytes encoding them.
+const PLACEHOLDER_BASE: u32 = 0x10FF00;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Result<Encoding> {
+        match &name.to_lowercase()[..] {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" | "utf-16" | "utf16" => Ok(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            _ => Err(Error::EncodingError(format!("Unknown encoding: {}", name))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+
+    pub fn is_utf16(&self) -> bool {
+        *self == Encoding::Utf16Le || *self == Encoding::Utf16Be
+    }
+
+    pub fn bom(&self) -> &'static [u8] {
+        match *self {
+            Encoding::Utf8 => UTF8_BOM,
+            Encoding::Utf16Le => UTF16LE_BOM,
+            Encoding::Utf16Be => UTF16BE_BOM,
+            Encoding::Latin1 => b"",
+        }
+    }
+
+    /// Checks that every char of `text` can be written in this encoding.
+    pub fn check(&self, text: &str) -> Result<()> {
+        if *self != Encoding::Latin1 {
+            return Ok(());
+        }
+
+        match text.chars().find(|&c| c as u32 > 0xFF && placeholder_byte(c).is_none()) {
+            Some(c) => Err(Error::EncodingError(format!("Can't convert '{}' to {}", c, self.name()))),
+            None => Ok(()),
+        }
+    }
+
+    /// Encodes `text`, writing placeholders back as the bytes they stand
+    /// for. Chars that don't fit the encoding must be ruled out with `check`.
+    pub fn encode(&self, text: &str, bytes: &mut Vec<u8>) {
+        for c in text.chars() {
+            if let Some(byte) = placeholder_byte(c) {
+                bytes.push(byte);
+                continue;
+            }
+
+            match *self {
+                Encoding::Utf8 => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                Encoding::Utf16Le => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        bytes.push(*unit as u8);
+                        bytes.push((*unit >> 8) as u8);
+                    }
+                },
+                Encoding::Utf16Be => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        bytes.push((*unit >> 8) as u8);
+                        bytes.push(*unit as u8);
+                    }
+                },
+                Encoding::Latin1 => bytes.push(c as u32 as u8),
+            }
+        }
+    }
+}
+
+pub fn is_placeholder(c: char) -> bool {
+    placeholder_byte(c).is_some()
+}
+
+pub fn placeholder_byte(c: char) -> Option<u8> {
+    let c = c as u32;
+
+    if (PLACEHOLDER_BASE..=PLACEHOLDER_BASE + 0xFF).contains(&c) {
+        Some((c - PLACEHOLDER_BASE) as u8)
+    } else {
+        None
+    }
+}
+
+fn placeholder(byte: u8) -> char {
+    char::from_u32(PLACEHOLDER_BASE + byte as u32).unwrap()
+}
+
+/// Detects the encoding of `bytes`, and whether they start with its BOM. A
+/// BOM selects UTF-8 or UTF-16, otherwise the text is UTF-8 if it decodes as
+/// such or contains at least one valid multibyte sequence, and Latin-1 if
+/// not.
+pub fn detect(bytes: &[u8]) -> (Encoding, bool) {
+    if bytes.starts_with(UTF8_BOM) {
+        (Encoding::Utf8, true)
+    } else if bytes.starts_with(UTF16LE_BOM) {
+        (Encoding::Utf16Le, true)
+    } else if bytes.starts_with(UTF16BE_BOM) {
+        (Encoding::Utf16Be, true)
+    } else if is_utf8(bytes) {
+        (Encoding::Utf8, false)
+    } else {
+        (Encoding::Latin1, false)
+    }
+}
+
+fn is_utf8(mut bytes: &[u8]) -> bool {
+    let has_multibyte = |text: &str| text.chars().any(|c| c as u32 > 0x7F && !is_placeholder(c));
+    let mut invalid = false;
+
+    loop {
+        match ::std::str::from_utf8(bytes) {
+            Ok(valid) => return !invalid || has_multibyte(valid),
+            Err(err) => {
+                let (valid, rest) = bytes.split_at(err.valid_up_to());
+                if has_multibyte(::std::str::from_utf8(valid).unwrap()) {
+                    return true;
+                }
+
+                invalid = true;
+                bytes = &rest[err.error_len().unwrap_or(rest.len())..];
+            },
+        }
+    }
+}
+
+/// Decodes text given a chunk at a time. A sequence split between chunks is
+/// kept until the next chunk completes it.
+pub struct Decoder {
+    encoding: Encoding,
+    pending: Vec<u8>,
+    invalid_bytes: usize,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Decoder {
+        Decoder {
+            encoding: encoding,
+            pending: Vec::new(),
+            invalid_bytes: 0,
+        }
+    }
+
+    pub fn decode(&mut self, bytes: &[u8], text: &mut String) {
+        self.decode_chunk(bytes, false, text);
+    }
+
+    /// Decodes what was kept back at the end of the last chunk, which can
+    /// only be invalid bytes.
+    pub fn finish(&mut self, text: &mut String) {
+        self.decode_chunk(&[], true, text);
+    }
+
+    pub fn invalid_bytes(&self) -> usize {
+        self.invalid_bytes
+    }
+
+    fn decode_chunk(&mut self, bytes: &[u8], last: bool, text: &mut String) {
+        let mut input = mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+
+        let (used, invalid_bytes) = match self.encoding {
+            Encoding::Utf8 => decode_utf8(&input, last, text),
+            Encoding::Utf16Le => decode_utf16(&input, last, text, |pair| pair[0] as u16 | (pair[1] as u16) << 8),
+            Encoding::Utf16Be => decode_utf16(&input, last, text, |pair| (pair[0] as u16) << 8 | pair[1] as u16),
+            Encoding::Latin1 => {
+                text.extend(input.iter().map(|&byte| byte as char));
+                (input.len(), 0)
+            },
+        };
+
+        input.drain(..used);
+        self.pending = input;
+        self.invalid_bytes += invalid_bytes;
+    }
+}
+
+/// Returns how many bytes were decoded, which leaves out an incomplete
+/// sequence at the end unless it's the `last` chunk, and how many of them
+/// were invalid.
+fn decode_utf8(mut bytes: &[u8], last: bool, text: &mut String) -> (usize, usize) {
+    let total = bytes.len();
+    let mut invalid_bytes = 0;
+
+    loop {
+        match ::std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                push_valid(text, valid);
+                return (total, invalid_bytes);
+            },
+            Err(err) => {
+                let (valid, rest) = bytes.split_at(err.valid_up_to());
+                push_valid(text, ::std::str::from_utf8(valid).unwrap());
+
+                let len = match err.error_len() {
+                    Some(len) => len,
+                    None if !last => return (total - rest.len(), invalid_bytes),
+                    None => rest.len(),
+                };
+                for &byte in &rest[..len] {
+                    text.push(placeholder(byte));
+                }
+
+                invalid_bytes += len;
+                bytes = &rest[len..];
+            },
+        }
+    }
+}
+
+/// Adds decoded UTF-8 text, with the chars used as placeholders turned into
+/// placeholders for their bytes, so they aren't written back as other bytes.
+fn push_valid(text: &mut String, mut valid: &str) {
+    while let Some(start) = valid.find(is_placeholder) {
+        let end = start + valid[start..].chars().next().map_or(1, char::len_utf8);
+
+        text.push_str(&valid[..start]);
+        text.extend(valid[start..end].bytes().map(placeholder));
+
+        valid = &valid[end..];
+    }
+
+    text.push_str(valid);
+}
+
+/// Like `decode_utf8`, for UTF-16 with `unit` reading a code unit from a
+/// pair of bytes.
+fn decode_utf16<F>(bytes: &[u8], last: bool, text: &mut String, unit: F) -> (usize, usize)
+    where F: Fn(&[u8]) -> u16
+{
+    let mut invalid_bytes = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+
+        if rest.len() < 2 {
+            if last {
+                text.push(placeholder(rest[0]));
+                invalid_bytes += 1;
+                i += 1;
+            }
+            break;
+        }
+
+        let first = unit(&rest[..2]);
+        let high = (0xD800..0xDC00).contains(&first);
+
+        // the low surrogate may be in the next chunk
+        if high && rest.len() < 4 && !last {
+            break;
+        }
+
+        let (c, used) = if high && rest.len() >= 4 {
+            let second = unit(&rest[2..4]);
+            if (0xDC00..0xE000).contains(&second) {
+                let code = 0x10000 + (((first as u32) - 0xD800) << 10) + ((second as u32) - 0xDC00);
+                (char::from_u32(code), 4)
+            } else {
+                (None, 2)
+            }
+        } else {
+            (char::from_u32(first as u32), 2)
+        };
+
+        match c {
+            Some(c) if is_placeholder(c) => text.extend(rest[..used].iter().map(|&byte| placeholder(byte))),
+            Some(c) => text.push(c),
+            None => {
+                text.push(placeholder(rest[0]));
+                text.push(placeholder(rest[1]));
+                invalid_bytes += 2;
+            },
+        }
+
+        i += used;
+    }
+
+    (i, invalid_bytes)
+}
@@ -5,6 +5,31 @@ use std::io;
 pub enum Error {
     CommandNotFound(String),
     NoFileName,
+    UnsavedChanges,
+    UnsavedChangesInBuffer(usize),
+    NoSuchBuffer(usize),
+    NoAlternateFile,
+    LastWindow,
+    NoRoom,
+    InvalidKey(String),
+    NoRangeAllowed,
+    InvalidRange,
+    PartialWrite,
+    MarkNotSet,
+    InvalidMark(char),
+    MotionFailed,
+    MacroTooDeep,
+    NoPreviousPattern,
+    NoPreviousSubstitution,
+    PatternNotFound(String),
+    InvalidPattern(String),
+    InvalidRegister(char),
+    EmptyRegister(char),
+    ClipboardError(String),
+    ShellError(String),
+    InvalidArgument(String),
+    UnknownOption(String),
+    EncodingError(String),
     IoError(io::Error),
 }
 
@@ -14,6 +39,31 @@ pub fn error_message(err: Error) -> String {
     match err {
         Error::CommandNotFound(name) => format!("Command not found: {}", name),
         Error::NoFileName => "No file name given".to_owned(),
+        Error::UnsavedChanges => "No write since last change (add ! to override)".to_owned(),
+        Error::UnsavedChangesInBuffer(number) => format!("No write since last change for buffer {} (add ! to override)", number),
+        Error::NoSuchBuffer(number) => format!("Buffer {} does not exist", number),
+        Error::NoAlternateFile => "No alternate file".to_owned(),
+        Error::LastWindow => "Cannot close last window".to_owned(),
+        Error::NoRoom => "Not enough room".to_owned(),
+        Error::InvalidKey(key) => format!("Invalid key: {}", key),
+        Error::NoRangeAllowed => "No range allowed".to_owned(),
+        Error::InvalidRange => "Invalid range".to_owned(),
+        Error::PartialWrite => "Cannot write part of the buffer over its file".to_owned(),
+        Error::MarkNotSet => "Mark not set".to_owned(),
+        Error::InvalidMark(name) => format!("Invalid mark name: {}", name),
+        Error::MotionFailed => "Cannot move there".to_owned(),
+        Error::MacroTooDeep => "Macros play each other too deeply".to_owned(),
+        Error::NoPreviousPattern => "No previous search pattern".to_owned(),
+        Error::NoPreviousSubstitution => "No previous substitute regular expression".to_owned(),
+        Error::PatternNotFound(pattern) => format!("Pattern not found: {}", pattern),
+        Error::InvalidPattern(pattern) => format!("Invalid pattern: {}", pattern),
+        Error::InvalidRegister(name) => format!("Invalid register name: {}", name),
+        Error::EmptyRegister(name) => format!("Nothing in register {}", name),
+        Error::ClipboardError(message) => format!("Clipboard error: {}", message),
+        Error::ShellError(message) => format!("Shell error: {}", message),
+        Error::InvalidArgument(arg) => format!("Invalid argument: {}", arg),
+        Error::UnknownOption(name) => format!("Unknown option: {}", name),
+        Error::EncodingError(message) => message,
         Error::IoError(io_error) => format!("IO error: {}", io_error),
     }
 }
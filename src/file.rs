@@ -0,0 +1,156 @@
+extern crate libc;
+
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const MAX_SYMLINKS: usize = 40;
+
+/// How `write_atomic` saved a file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Written {
+    /// Through a temporary file renamed over the original.
+    Atomically,
+    /// Over the original itself, as the temporary file couldn't be given
+    /// its owner. A failure part way leaves it cut short.
+    InPlace,
+}
+
+/// A temporary file, removed when dropped unless it was renamed over its
+/// target, so no error leaves it behind.
+struct TempFile {
+    path: PathBuf,
+    renamed: bool,
+}
+
+impl TempFile {
+    fn rename(&mut self, target: &Path) -> io::Result<()> {
+        fs::rename(&self.path, target)?;
+        self.renamed = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.renamed {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Saves `filename` by writing a temporary file in the same directory,
+/// syncing it to disk and renaming it over the original, so a crash or a full
+/// disk never leaves a truncated file behind. Symlinks are followed, so the
+/// link itself is kept, and the mode and ownership of the original file are
+/// copied over.
+pub fn write_atomic<F>(filename: &str, write_contents: F) -> io::Result<Written>
+    where F: Fn(&mut BufWriter<File>) -> io::Result<()>
+{
+    let target = resolve_symlinks(Path::new(filename))?;
+
+    let metadata = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata),
+        Err(ref err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+
+    let path = temp_path(&target);
+    let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+    let mut temp = TempFile { path: path, renamed: false };
+
+    if let Some(ref metadata) = metadata {
+        if copy_attributes(&file, metadata).is_err() {
+            // we can't give the new file the original owner, so overwriting
+            // the original in place is the only way to keep it
+            drop(file);
+            drop(temp);
+
+            write_in_place(&target, write_contents)?;
+            return Ok(Written::InPlace);
+        }
+    }
+
+    write_and_sync(file, write_contents)?;
+    temp.rename(&target)?;
+    sync_parent(&target);
+
+    Ok(Written::Atomically)
+}
+
+fn write_in_place<F>(target: &Path, write_contents: F) -> io::Result<()>
+    where F: Fn(&mut BufWriter<File>) -> io::Result<()>
+{
+    let file = File::create(target)?;
+
+    write_and_sync(file, write_contents)
+}
+
+fn write_and_sync<F>(file: File, write_contents: F) -> io::Result<()>
+    where F: Fn(&mut BufWriter<File>) -> io::Result<()>
+{
+    let mut writer = BufWriter::new(file);
+
+    write_contents(&mut writer)?;
+    writer.flush()?;
+
+    writer.get_ref().sync_all()
+}
+
+fn copy_attributes(file: &File, metadata: &Metadata) -> io::Result<()> {
+    let current = file.metadata()?;
+
+    if current.uid() != metadata.uid() || current.gid() != metadata.gid() {
+        let result = unsafe { libc::fchown(file.as_raw_fd(), metadata.uid(), metadata.gid()) };
+
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    file.set_permissions(metadata.permissions())
+}
+
+fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+
+    for _ in 0..MAX_SYMLINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(ref metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&path)?;
+
+                path = match path.parent() {
+                    Some(parent) => parent.join(link),
+                    None => link,
+                };
+            },
+            _ => return Ok(path),
+        }
+    }
+
+    Ok(path)
+}
+
+fn temp_path(target: &Path) -> PathBuf {
+    let name = match target.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => "king".to_owned(),
+    };
+
+    target.with_file_name(format!(".{}.{}.king~", name, process::id()))
+}
+
+fn sync_parent(target: &Path) {
+    let parent = match target.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use backend::{Attributes, Backend};
+use input::Key;
+use unicode;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    grapheme: String,
+    width: usize,
+    attributes: Attributes,
+}
+
+impl Cell {
+    fn blank() -> Cell {
+        Cell {
+            grapheme: " ".to_owned(),
+            width: 1,
+            attributes: Attributes::normal(),
+        }
+    }
+
+    /// The grapheme drawn in this cell. Cells covered by the right half of a
+    /// wide grapheme are empty.
+    pub fn grapheme(&self) -> &str {
+        &self.grapheme
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn attributes(&self) -> Attributes {
+        self.attributes
+    }
+}
+
+/// Backend that draws into a grid of cells in memory and reads keys from a
+/// queue, so tests can look at exactly what would be on the screen.
+pub struct Headless {
+    height: usize,
+    width: usize,
+    cells: Vec<Vec<Cell>>,
+    cursor: (usize, usize),
+    keys: VecDeque<Key>,
+    escape_sequences: Vec<String>,
+}
+
+impl Headless {
+    pub fn new(height: usize, width: usize) -> Headless {
+        Headless {
+            height: height,
+            width: width,
+            cells: vec![vec![Cell::blank(); width]; height],
+            cursor: (0, 0),
+            keys: VecDeque::new(),
+            escape_sequences: Vec::new(),
+        }
+    }
+
+    pub fn cell(&self, y: usize, x: usize) -> &Cell {
+        &self.cells[y][x]
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Text of row `y`, with trailing blanks removed.
+    pub fn row(&self, y: usize) -> String {
+        let text = self.cells[y].iter().map(|cell| &cell.grapheme[..]).collect::<String>();
+
+        text.trim_end_matches(' ').to_owned()
+    }
+
+    /// Text of every row, with trailing blanks removed.
+    pub fn rows(&self) -> Vec<String> {
+        (0..self.height).map(|y| self.row(y)).collect()
+    }
+
+    /// Escape sequences written so far, in order.
+    pub fn escape_sequences(&self) -> &[String] {
+        &self.escape_sequences
+    }
+
+    /// Queues a key for `read_key`.
+    pub fn push_key(&mut self, key: Key) {
+        self.keys.push_back(key);
+    }
+
+    /// Blanks the cell at `(y, x)`, along with the other half of a wide
+    /// grapheme it's part of.
+    fn blank(&mut self, y: usize, x: usize) {
+        if self.cells[y][x].width == 0 && x > 0 {
+            self.cells[y][x - 1] = Cell::blank();
+        }
+
+        let width = self.cells[y][x].width;
+        for column in x..(x + width).min(self.width) {
+            self.cells[y][column] = Cell::blank();
+        }
+
+        self.cells[y][x] = Cell::blank();
+    }
+}
+
+impl Backend for Headless {
+    fn size(&self) -> (usize, usize) {
+        (self.height, self.width)
+    }
+
+    fn clear(&mut self) {
+        self.cells = vec![vec![Cell::blank(); self.width]; self.height];
+    }
+
+    fn put(&mut self, y: usize, x: usize, grapheme: &str, attributes: Attributes) {
+        let width = unicode::width(grapheme);
+
+        // like a terminal, a grapheme that doesn't fit is not drawn
+        if y >= self.height || x + width > self.width {
+            return;
+        }
+
+        for column in x..(x + width) {
+            self.blank(y, column);
+        }
+
+        self.cells[y][x] = Cell {
+            grapheme: grapheme.to_owned(),
+            width: width,
+            attributes: attributes,
+        };
+
+        for column in (x + 1)..(x + width) {
+            self.cells[y][column] = Cell {
+                grapheme: String::new(),
+                width: 0,
+                attributes: attributes,
+            };
+        }
+    }
+
+    fn move_cursor(&mut self, y: usize, x: usize) {
+        self.cursor = (y, x);
+    }
+
+    fn write_escape_sequence(&mut self, sequence: &str) {
+        self.escape_sequences.push(sequence.to_owned());
+    }
+
+    fn flush(&mut self) {
+    }
+
+    /// Takes the next queued key, an empty queue counts as a timeout.
+    fn read_key(&mut self, _timeout: Option<Duration>) -> Option<Key> {
+        self.keys.pop_front()
+    }
+}
@@ -0,0 +1,174 @@
+/// A single reversible modification of the buffer text. Positions are char
+/// indexes into the whole buffer.
+#[derive(Clone, Debug)]
+pub enum Edit {
+    Insert(usize, String),
+    Remove(usize, String),
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        match *self {
+            Edit::Insert(at, ref text) => Edit::Remove(at, text.clone()),
+            Edit::Remove(at, ref text) => Edit::Insert(at, text.clone()),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        match *self {
+            Edit::Insert(at, _) | Edit::Remove(at, _) => at,
+        }
+    }
+}
+
+struct Node {
+    parent: usize,
+    children: Vec<usize>,
+    redo_child: Option<usize>,
+    edits: Vec<Edit>,
+}
+
+/// Undo tree. Node 0 is the state the buffer was loaded in, every other node
+/// holds the group of edits that leads to it from its parent. Node indexes
+/// double as sequence numbers, so they are also the chronological order used
+/// by `:earlier` and `:later`.
+pub struct History {
+    nodes: Vec<Node>,
+    current: usize,
+    pending: Vec<Edit>,
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            nodes: vec![Node {
+                parent: 0,
+                children: Vec::new(),
+                redo_child: None,
+                edits: Vec::new(),
+            }],
+            current: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn seq(&self) -> usize {
+        self.current
+    }
+
+    pub fn last_seq(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        if let Some(last) = self.pending.last_mut() {
+            match (last, &edit) {
+                (&mut Edit::Insert(at, ref mut text), &Edit::Insert(new_at, ref new_text))
+                    if at + text.chars().count() == new_at => {
+                    text.push_str(new_text);
+                    return;
+                },
+                (&mut Edit::Remove(ref mut at, ref mut text), &Edit::Remove(new_at, ref new_text))
+                    if new_at + new_text.chars().count() == *at => {
+                    text.insert_str(0, new_text);
+                    *at = new_at;
+                    return;
+                },
+                _ => {},
+            }
+        }
+
+        self.pending.push(edit);
+    }
+
+    /// Whether there are edits not yet closed into an undo step.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Closes the group of edits recorded so far into a new undo step.
+    pub fn commit(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let id = self.nodes.len();
+        let edits = self.pending.drain(..).collect();
+
+        self.nodes.push(Node {
+            parent: self.current,
+            children: Vec::new(),
+            redo_child: None,
+            edits: edits,
+        });
+
+        self.nodes[self.current].children.push(id);
+        self.nodes[self.current].redo_child = Some(id);
+        self.current = id;
+    }
+
+    /// Moves to the parent of the current node, returning the edits that
+    /// revert the current node in the order they must be applied.
+    pub fn undo(&mut self) -> Option<Vec<Edit>> {
+        if self.current == 0 {
+            return None;
+        }
+
+        let node = self.current;
+        let parent = self.nodes[node].parent;
+
+        self.nodes[parent].redo_child = Some(node);
+        self.current = parent;
+
+        Some(self.nodes[node].edits.iter().rev().map(Edit::inverse).collect())
+    }
+
+    /// Moves to the most recently visited child of the current node,
+    /// returning the edits that lead to it.
+    pub fn redo(&mut self) -> Option<Vec<Edit>> {
+        self.nodes[self.current].redo_child.map(|child| self.enter(child))
+    }
+
+    /// Returns the chain of edits that takes the buffer from the current node
+    /// to node `seq`, undoing up to the common ancestor and redoing down the
+    /// branch that holds `seq`.
+    pub fn goto(&mut self, seq: usize) -> Vec<Edit> {
+        let seq = if seq > self.last_seq() { self.last_seq() } else { seq };
+
+        let mut path = vec![seq];
+        while *path.last().unwrap() != 0 {
+            let parent = self.nodes[*path.last().unwrap()].parent;
+            path.push(parent);
+        }
+
+        let mut edits = Vec::new();
+
+        while !path.contains(&self.current) {
+            if let Some(undo) = self.undo() {
+                edits.extend(undo);
+            }
+        }
+
+        let position = path.iter().position(|&n| n == self.current).unwrap();
+        for &node in path[..position].iter().rev() {
+            edits.extend(self.enter(node));
+        }
+
+        edits
+    }
+
+    fn enter(&mut self, child: usize) -> Vec<Edit> {
+        let parent = self.nodes[child].parent;
+
+        self.nodes[parent].redo_child = Some(child);
+        self.current = child;
+
+        self.nodes[child].edits.clone()
+    }
+}
@@ -1,37 +1,336 @@
-extern crate ncurses;
+use std::fmt;
+use std::time::Duration;
 
-use self::ncurses as nc;
+use backend::Backend;
+use error::{Error, Result};
 
-use std::char;
-
-#[derive(PartialEq, Eq, Hash)]
-pub enum Key {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    Char(char),
     Enter,
     Esc,
     Backspace,
-    Char(char),
+    Tab,
+    Delete,
+    Insert,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
     Unknown,
 }
 
-pub fn read_key() -> Option<Key> {
-    match nc::wget_wch(nc::stdscr()) {
-        Some(nc::WchResult::Char(ic)) => {
-            match ic {
-                27 => Some(Key::Esc),
-                13 => Some(Key::Enter),
-                127 => Some(Key::Backspace),
-                ic => {
-                    match parse_char(ic) {
-                        Some(c) => Some(Key::Char(c)),
-                        None => Some(Key::Unknown),
-                    }
+/// Modifiers held with a key. Shift is only used with keys other than
+/// chars, since it's already part of the char itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: Modifiers) -> Key {
+        // Ctrl ignores case, <C-W> and <C-w> are the same key
+        let code = match code {
+            KeyCode::Char(c) if modifiers.ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+
+        Key {
+            code: code,
+            modifiers: modifiers,
+        }
+    }
+
+    pub fn ctrl(c: char) -> Key {
+        Key::new(KeyCode::Char(c), Modifiers { ctrl: true, ..Modifiers::default() })
+    }
+
+    pub fn alt(code: KeyCode) -> Key {
+        Key::new(code, Modifiers { alt: true, ..Modifiers::default() })
+    }
+
+    pub fn with_alt(self) -> Key {
+        Key::new(self.code, Modifiers { alt: true, ..self.modifiers })
+    }
+
+    /// Parses a single key written as a char or in angle bracket notation,
+    /// like `x`, `<CR>`, `<C-w>`, `<A-x>`, `<S-Tab>` or `<F5>`.
+    pub fn parse(text: &str) -> Result<Key> {
+        let keys = parse_keys(text)?;
+
+        if keys.len() != 1 {
+            return Err(Error::InvalidKey(text.to_owned()));
+        }
+
+        Ok(keys[0])
+    }
+
+    /// The char typed by this key when inserting text.
+    pub fn typed_char(&self) -> Option<char> {
+        if self.modifiers != Modifiers::default() {
+            return None;
+        }
+
+        match self.code {
+            KeyCode::Char(c) => Some(c),
+            KeyCode::Tab => Some('\t'),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Key {
+        Key::new(code, Modifiers::default())
+    }
+}
+
+impl From<char> for Key {
+    fn from(c: char) -> Key {
+        Key::from(KeyCode::Char(c))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char('<') => "lt".to_owned(),
+            KeyCode::Char(' ') => "Space".to_owned(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => NAMES.iter().find(|&&(_, named)| named == code).map(|&(name, _)| name).unwrap_or("Unknown").to_owned(),
+        };
+
+        let plain = self.modifiers == Modifiers::default();
+        if plain && name.chars().count() == 1 {
+            return write!(f, "{}", name);
+        }
+
+        write!(f, "<")?;
+        if self.modifiers.ctrl {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "A-")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "S-")?;
+        }
+        write!(f, "{}>", name)
+    }
+}
+
+/// Names accepted inside angle brackets. The first name of each key is the
+/// one it's displayed with.
+const NAMES: &[(&str, KeyCode)] = &[
+    ("CR", KeyCode::Enter),
+    ("Enter", KeyCode::Enter),
+    ("Return", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("BS", KeyCode::Backspace),
+    ("Backspace", KeyCode::Backspace),
+    ("Tab", KeyCode::Tab),
+    ("Del", KeyCode::Delete),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+    ("Bar", KeyCode::Char('|')),
+    ("Bslash", KeyCode::Char('\\')),
+];
+
+/// Parses a sequence of keys in the notation of `Key::parse`. A `<` that
+/// doesn't start a valid key name stands for itself.
+pub fn parse_keys(text: &str) -> Result<Vec<Key>> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                if let Some(key) = parse_bracketed(&rest[1..end]) {
+                    keys.push(key);
+                    rest = &rest[end + 1..];
+                    continue;
                 }
             }
+        }
+
+        keys.push(Key::from(c));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if keys.is_empty() {
+        return Err(Error::InvalidKey(text.to_owned()));
+    }
+
+    Ok(keys)
+}
+
+fn parse_bracketed(text: &str) -> Option<Key> {
+    let mut modifiers = Modifiers::default();
+    let mut name = text;
+
+    // the name itself may be a dash, as in <C-->
+    while name.len() > 2 && name.as_bytes()[1] == b'-' {
+        match name.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => modifiers.ctrl = true,
+            b'A' | b'M' => modifiers.alt = true,
+            b'S' => modifiers.shift = true,
+            _ => return None,
+        }
+        name = &name[2..];
+    }
+
+    let code = parse_name(name)?;
+
+    // shift is part of the char for printable keys
+    if let KeyCode::Char(c) = code {
+        if modifiers.shift {
+            modifiers.shift = false;
+            return Some(Key::new(KeyCode::Char(c.to_ascii_uppercase()), modifiers));
+        }
+    }
+
+    Some(Key::new(code, modifiers))
+}
+
+fn parse_name(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    if let Some(&(_, code)) = NAMES.iter().find(|&&(named, _)| named.eq_ignore_ascii_case(name)) {
+        return Some(code);
+    }
+
+    if name.starts_with('F') || name.starts_with('f') {
+        if let Ok(n) = name[1..].parse::<u8>() {
+            if (1..=63).contains(&n) {
+                return Some(KeyCode::F(n));
+            }
+        }
+    }
+
+    None
+}
+
+/// Key for a char read from the terminal, where control chars stand for
+/// Ctrl chords and a few special keys.
+pub fn decode_char(c: char) -> Key {
+    match c {
+        '\r' => Key::from(KeyCode::Enter),
+        '\t' => Key::from(KeyCode::Tab),
+        '\u{1b}' => Key::from(KeyCode::Esc),
+        '\u{7f}' => Key::from(KeyCode::Backspace),
+        '\u{0}' => Key::ctrl(' '),
+        '\u{1}'..='\u{1a}' => Key::ctrl((c as u8 - 1 + b'a') as char),
+        '\u{1c}'..='\u{1f}' => Key::ctrl((c as u8 - 0x1c + b'\\') as char),
+        c => Key::from(c),
+    }
+}
+
+/// Decodes what a terminal sent after an ESC: nothing for ESC itself, a
+/// single char for Alt chords, or a CSI (`[`) or SS3 (`O`) sequence for
+/// cursor, editing and function keys, with xterm style modifiers.
+pub fn decode_escape_sequence(sequence: &str) -> Key {
+    let mut chars = sequence.chars();
+
+    match (chars.next(), chars.as_str()) {
+        (None, _) => Key::from(KeyCode::Esc),
+        (Some('['), rest) if !rest.is_empty() => decode_csi(rest),
+        (Some('O'), rest) if !rest.is_empty() => decode_ss3(rest),
+        (Some(c), "") => decode_char(c).with_alt(),
+        _ => Key::from(KeyCode::Unknown),
+    }
+}
+
+fn decode_csi(sequence: &str) -> Key {
+    let last = sequence.chars().last().unwrap();
+    let params = sequence[..sequence.len() - last.len_utf8()]
+        .split(';')
+        .map(|param| param.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+
+    let modifiers = match params.get(1) {
+        Some(&Some(param)) => decode_modifiers(param),
+        _ => Modifiers::default(),
+    };
+
+    let code = match last {
+        '~' => match params[0] {
+            Some(1) | Some(7) => KeyCode::Home,
+            Some(2) => KeyCode::Insert,
+            Some(3) => KeyCode::Delete,
+            Some(4) | Some(8) => KeyCode::End,
+            Some(5) => KeyCode::PageUp,
+            Some(6) => KeyCode::PageDown,
+            Some(n @ 11..=15) => KeyCode::F((n - 10) as u8),
+            Some(n @ 17..=21) => KeyCode::F((n - 11) as u8),
+            Some(n @ 23..=24) => KeyCode::F((n - 12) as u8),
+            _ => KeyCode::Unknown,
         },
-        Some(nc::WchResult::KeyCode(_)) | None => None,
+        'Z' => return Key::new(KeyCode::Tab, Modifiers { shift: true, ..modifiers }),
+        c => cursor_key(c),
+    };
+
+    Key::new(code, modifiers)
+}
+
+fn decode_ss3(sequence: &str) -> Key {
+    match sequence.chars().last().unwrap() {
+        c @ 'P'..='S' => Key::from(KeyCode::F(c as u8 - b'P' + 1)),
+        c => Key::from(cursor_key(c)),
+    }
+}
+
+fn cursor_key(c: char) -> KeyCode {
+    match c {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'P'..='S' => KeyCode::F(c as u8 - b'P' + 1),
+        _ => KeyCode::Unknown,
+    }
+}
+
+/// xterm encodes modifiers as one plus a bit mask of shift, alt and ctrl.
+pub fn decode_modifiers(param: u32) -> Modifiers {
+    let mask = param.saturating_sub(1);
+
+    Modifiers {
+        shift: mask & 1 != 0,
+        alt: mask & 2 != 0,
+        ctrl: mask & 4 != 0,
     }
 }
 
-fn parse_char(ic: u32) -> Option<char> {
-    char::from_u32(ic)
+/// Reads the next key, or `None` if `timeout` passes first.
+pub fn read_key<B: Backend>(backend: &mut B, timeout: Option<Duration>) -> Option<Key> {
+    backend.read_key(timeout)
 }
@@ -0,0 +1,378 @@
+use std::cmp::{max, min};
+use std::mem;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub y: usize,
+    pub x: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+/// How a split arranges its windows. `Horizontal` stacks them one above the
+/// other, like `:split`, and `Vertical` puts them side by side, like
+/// `:vsplit`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+    Above,
+    Below,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Absolute(usize),
+    Relative(isize),
+    /// As large as the other windows of the split leave room for.
+    Max,
+}
+
+enum Node {
+    Window(usize),
+    Split(Orientation, Vec<Child>),
+}
+
+/// A node inside a split. Weights are relative: the space of the split is
+/// shared between its children in proportion to them.
+struct Child {
+    node: Node,
+    weight: usize,
+}
+
+/// Tree of splits with windows, identified by number, at the leaves. Side by
+/// side windows are separated by a one column bar, and every window owns the
+/// last row of its area for a status line when there is more than one.
+pub struct Layout {
+    root: Node,
+}
+
+impl Layout {
+    pub fn new(window: usize) -> Layout {
+        Layout {
+            root: Node::Window(window),
+        }
+    }
+
+    /// Window numbers from top left to bottom right.
+    pub fn windows(&self) -> Vec<usize> {
+        let mut windows = Vec::new();
+        collect_windows(&self.root, &mut windows);
+        windows
+    }
+
+    /// Areas taken by each window when the layout fills `area`.
+    pub fn arrange(&self, area: Rect) -> Vec<(usize, Rect)> {
+        let mut rects = Vec::new();
+        arrange(&self.root, area, &mut rects);
+        rects
+    }
+
+    /// Splits `window` in two, placing `new_window` above or to the left of
+    /// it and sharing its space equally between both.
+    pub fn split(&mut self, window: usize, new_window: usize, orientation: Orientation) {
+        split(&mut self.root, window, new_window, orientation);
+    }
+
+    pub fn remove(&mut self, window: usize) {
+        remove(&mut self.root, window);
+    }
+
+    /// Gives every window in each split the same share of space.
+    pub fn equalize(&mut self) {
+        equalize(&mut self.root);
+    }
+
+    /// Changes the height (for `Horizontal`) or width (for `Vertical`) of
+    /// `window`, taking the space from or giving it to its neighbours.
+    pub fn resize(&mut self, window: usize, orientation: Orientation, size: Size, area: Rect) {
+        resize(&mut self.root, area, window, orientation, size);
+    }
+
+    /// Window next to `window` on the given side, preferring the one that
+    /// lines up with the screen position `(y, x)`.
+    pub fn neighbour(&self, window: usize, side: Side, area: Rect, (y, x): (usize, usize)) -> Option<usize> {
+        let rects = self.arrange(area);
+
+        let current = match rects.iter().find(|&&(id, _)| id == window) {
+            Some(&(_, rect)) => rect,
+            None => return None,
+        };
+
+        let candidates = rects.iter().filter(|&&(_, rect)| {
+            match side {
+                Side::Left => rect.x + rect.width + 1 == current.x && overlaps(rect.y, rect.height, current.y, current.height),
+                Side::Right => current.x + current.width + 1 == rect.x && overlaps(rect.y, rect.height, current.y, current.height),
+                Side::Above => rect.y + rect.height == current.y && overlaps(rect.x, rect.width, current.x, current.width),
+                Side::Below => current.y + current.height == rect.y && overlaps(rect.x, rect.width, current.x, current.width),
+            }
+        }).collect::<Vec<_>>();
+
+        let aligned = candidates.iter().find(|&&&(_, rect)| {
+            match side {
+                Side::Left | Side::Right => y >= rect.y && y < rect.y + rect.height,
+                Side::Above | Side::Below => x >= rect.x && x < rect.x + rect.width,
+            }
+        });
+
+        aligned.or(candidates.first()).map(|&&(id, _)| id)
+    }
+}
+
+fn overlaps(start: usize, len: usize, other_start: usize, other_len: usize) -> bool {
+    start < other_start + other_len && other_start < start + len
+}
+
+fn collect_windows(node: &Node, windows: &mut Vec<usize>) {
+    match *node {
+        Node::Window(id) => windows.push(id),
+        Node::Split(_, ref children) => {
+            for child in children {
+                collect_windows(&child.node, windows);
+            }
+        },
+    }
+}
+
+fn contains(node: &Node, window: usize) -> bool {
+    match *node {
+        Node::Window(id) => id == window,
+        Node::Split(_, ref children) => children.iter().any(|child| contains(&child.node, window)),
+    }
+}
+
+/// Splits `extent` in proportion to `weights`.
+fn distribute(extent: usize, weights: &[usize]) -> Vec<usize> {
+    let total = max(weights.iter().sum(), 1);
+    let mut sizes = Vec::with_capacity(weights.len());
+    let mut cumulative = 0;
+    let mut previous = 0;
+
+    for weight in weights {
+        cumulative += *weight;
+        let boundary = (extent * cumulative + total / 2) / total;
+        sizes.push(boundary - previous);
+        previous = boundary;
+    }
+
+    sizes
+}
+
+fn child_rects(rect: Rect, orientation: Orientation, children: &[Child]) -> Vec<Rect> {
+    let weights = children.iter().map(|child| child.weight).collect::<Vec<_>>();
+    let separators = children.len() - 1;
+
+    match orientation {
+        Orientation::Horizontal => {
+            let mut y = rect.y;
+
+            distribute(rect.height, &weights).into_iter().map(|height| {
+                let child = Rect { y: y, x: rect.x, height: height, width: rect.width };
+                y += height;
+                child
+            }).collect()
+        },
+        Orientation::Vertical => {
+            let mut x = rect.x;
+
+            distribute(rect.width.saturating_sub(separators), &weights).into_iter().map(|width| {
+                let child = Rect { y: rect.y, x: x, height: rect.height, width: width };
+                x += width + 1;
+                child
+            }).collect()
+        },
+    }
+}
+
+fn arrange(node: &Node, rect: Rect, rects: &mut Vec<(usize, Rect)>) {
+    match *node {
+        Node::Window(id) => rects.push((id, rect)),
+        Node::Split(orientation, ref children) => {
+            for (child, child_rect) in children.iter().zip(child_rects(rect, orientation, children)) {
+                arrange(&child.node, child_rect, rects);
+            }
+        },
+    }
+}
+
+fn split(node: &mut Node, window: usize, new_window: usize, orientation: Orientation) -> bool {
+    if let Node::Window(id) = *node {
+        if id != window {
+            return false;
+        }
+
+        *node = Node::Split(orientation, vec![
+            Child { node: Node::Window(new_window), weight: 1 },
+            Child { node: Node::Window(window), weight: 1 },
+        ]);
+
+        return true;
+    }
+
+    if let Node::Split(split_orientation, ref mut children) = *node {
+        let position = children.iter().position(|child| match child.node {
+            Node::Window(id) => id == window,
+            _ => false,
+        });
+
+        if let Some(position) = position {
+            if split_orientation == orientation {
+                for child in children.iter_mut() {
+                    child.weight *= 2;
+                }
+
+                let weight = children[position].weight / 2;
+                children[position].weight = weight;
+                children.insert(position, Child { node: Node::Window(new_window), weight: weight });
+
+                return true;
+            }
+        }
+
+        for child in children.iter_mut() {
+            if split(&mut child.node, window, new_window, orientation) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+fn remove(node: &mut Node, window: usize) -> bool {
+    let collapse = match *node {
+        Node::Window(_) => return false,
+        Node::Split(_, ref mut children) => {
+            let position = children.iter().position(|child| match child.node {
+                Node::Window(id) => id == window,
+                _ => false,
+            });
+
+            match position {
+                Some(position) => {
+                    children.remove(position);
+                },
+                None => {
+                    if !children.iter_mut().any(|child| remove(&mut child.node, window)) {
+                        return false;
+                    }
+                },
+            }
+
+            children.len() == 1
+        },
+    };
+
+    if collapse {
+        let only = match mem::replace(node, Node::Window(0)) {
+            Node::Split(_, mut children) => children.pop().unwrap().node,
+            other => other,
+        };
+
+        *node = only;
+    }
+
+    true
+}
+
+fn equalize(node: &mut Node) {
+    if let Node::Split(_, ref mut children) = *node {
+        for child in children.iter_mut() {
+            child.weight = 1;
+            equalize(&mut child.node);
+        }
+    }
+}
+
+/// Smallest height or width `node` can take: one line of text plus a status
+/// line for each window stacked vertically, one column for each window side
+/// by side plus the separators between them.
+fn min_size(node: &Node, orientation: Orientation) -> usize {
+    match *node {
+        Node::Window(_) => match orientation {
+            Orientation::Horizontal => 2,
+            Orientation::Vertical => 1,
+        },
+        Node::Split(split_orientation, ref children) => {
+            let mins = children.iter().map(|child| min_size(&child.node, orientation));
+
+            if split_orientation != orientation {
+                mins.max().unwrap_or(1)
+            } else if orientation == Orientation::Vertical {
+                mins.sum::<usize>() + children.len() - 1
+            } else {
+                mins.sum()
+            }
+        },
+    }
+}
+
+fn resize(node: &mut Node, rect: Rect, window: usize, orientation: Orientation, size: Size) -> bool {
+    let (split_orientation, children) = match *node {
+        Node::Window(_) => return false,
+        Node::Split(split_orientation, ref mut children) => (split_orientation, children),
+    };
+
+    let rects = child_rects(rect, split_orientation, children);
+
+    let index = match children.iter().position(|child| contains(&child.node, window)) {
+        Some(index) => index,
+        None => return false,
+    };
+
+    if resize(&mut children[index].node, rects[index], window, orientation, size) {
+        return true;
+    }
+
+    if split_orientation != orientation {
+        return false;
+    }
+
+    let mut sizes = rects.iter().map(|rect| match orientation {
+        Orientation::Horizontal => rect.height,
+        Orientation::Vertical => rect.width,
+    }).collect::<Vec<_>>();
+    let mins = children.iter().map(|child| min_size(&child.node, orientation)).collect::<Vec<_>>();
+
+    let others_min = mins.iter().sum::<usize>() - mins[index];
+    let extent = sizes.iter().sum::<usize>();
+    let largest = extent.saturating_sub(others_min);
+
+    let current = sizes[index] as isize;
+    let target = match size {
+        Size::Absolute(size) => size as isize,
+        Size::Relative(delta) => current + delta,
+        Size::Max => largest as isize,
+    };
+    let target = min(max(target, mins[index] as isize), largest as isize);
+
+    let mut diff = target - current;
+    sizes[index] = target as usize;
+
+    // space is exchanged with the windows after this one first, then with
+    // the ones before it
+    let order = ((index + 1)..sizes.len()).chain((0..index).rev()).collect::<Vec<_>>();
+    for j in order {
+        if diff > 0 {
+            let available = (sizes[j] - min(sizes[j], mins[j])) as isize;
+            let taken = min(available, diff);
+            sizes[j] -= taken as usize;
+            diff -= taken;
+        } else if diff < 0 {
+            sizes[j] += (-diff) as usize;
+            diff = 0;
+        }
+    }
+
+    for (child, size) in children.iter_mut().zip(sizes) {
+        child.weight = size;
+    }
+
+    true
+}
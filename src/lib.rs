@@ -1,15 +1,31 @@
-#![feature(splice)]
-
 mod unicode;
 mod movement;
+mod operator;
+mod text_object;
+mod selection;
+mod registers;
+mod pattern;
+mod search;
+mod substitute;
+mod address;
 mod prompt;
-mod command;
-mod buffer;
+mod history;
+mod file;
+mod encoding;
 mod window;
+mod buffer_list;
+mod layout;
 mod mappings;
+mod shell;
 
 pub mod mode;
+pub mod command;
+pub mod buffer;
 pub mod ui;
+pub mod backend;
+pub mod terminal;
+pub mod headless;
 pub mod error;
 pub mod input;
 pub mod editor;
+pub mod clipboard;
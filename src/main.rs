@@ -6,41 +6,51 @@ use libc::signal;
 
 use std::process::exit;
 
-extern fn handler(_: c_int) {
-    ui::finish();
+extern "C" fn handler(_: c_int) {
+    terminal::finish();
     exit(143);
 }
 
 fn get_handler() -> sighandler_t {
-    handler as extern fn(c_int) as *mut c_void as sighandler_t
+    handler as extern "C" fn(c_int) as *mut c_void as sighandler_t
 }
 
 // main
 extern crate king;
 
+use king::backend::Backend;
+use king::clipboard;
 use king::editor::Editor;
 use king::input;
 use king::error::error_message;
+use king::terminal::{self, Terminal};
 use king::ui;
 
 fn main() {
     unsafe { signal(SIGTERM, get_handler()); }
 
-    ui::init();
+    let mut terminal = Terminal::new();
+    let (height, width) = terminal.size();
 
-    let mut editor = Editor::new(ui::getmaxy(), ui::getmaxx());
+    let mut editor = Editor::new(height, width);
+    editor.set_clipboard(clipboard::detect());
 
-    ui::render(&editor);
+    ui::render(&editor, &mut terminal);
 
     while editor.running() {
-        if let Some(key) = input::read_key() {
-            if let Err(err) = editor.handle_key(&key) {
-                editor.display_error(&error_message(err));
-            }
+        let result = match input::read_key(&mut terminal, editor.key_timeout()) {
+            Some(key) => editor.handle_key(&key),
+            None => editor.handle_timeout(),
+        };
+
+        if let Err(err) = result {
+            editor.display_error(&error_message(err));
         }
 
-        ui::render(&editor);
-    }
+        for sequence in editor.take_escape_sequences() {
+            terminal.write_escape_sequence(&sequence);
+        }
 
-    ui::finish();
+        ui::render(&editor, &mut terminal);
+    }
 }
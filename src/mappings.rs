@@ -3,22 +3,66 @@ use std::collections::HashMap;
 use input::Key;
 use command::Command;
 
+/// Result of looking up a sequence of keys.
+pub enum Match<'a> {
+    None,
+    /// The keys start one or more longer mappings, and may be mapped
+    /// themselves, in which case only a timeout tells which one was meant.
+    Partial(Option<&'a Command>),
+    Full(&'a Command),
+}
+
+struct Node {
+    command: Option<Command>,
+    children: HashMap<Key, Node>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            command: None,
+            children: HashMap::new(),
+        }
+    }
+}
+
+/// Key sequences mapped to commands, stored as a trie so prefixes of
+/// longer mappings can be recognized while keys are typed.
 pub struct Mappings {
-    mappings: HashMap<Key, Command>,
+    root: Node,
 }
 
 impl Mappings {
     pub fn new() -> Mappings {
         Mappings {
-            mappings: HashMap::new(),
+            root: Node::new(),
         }
     }
 
-    pub fn get(&self, key: &Key) -> Option<&Command> {
-        self.mappings.get(key)
+    pub fn lookup(&self, keys: &[Key]) -> Match<'_> {
+        let mut node = &self.root;
+
+        for key in keys {
+            node = match node.children.get(key) {
+                Some(child) => child,
+                None => return Match::None,
+            };
+        }
+
+        match node.command {
+            Some(ref command) if node.children.is_empty() => Match::Full(command),
+            _ if !node.children.is_empty() => Match::Partial(node.command.as_ref()),
+            _ => Match::None,
+        }
     }
 
-    pub fn insert(&mut self, key: Key, command: Command) {
-        self.mappings.insert(key, command);
+    pub fn insert(&mut self, keys: &[Key], command: Command) {
+        let mut node = &mut self.root;
+
+        for key in keys {
+            node = node.children.entry(*key).or_insert_with(Node::new);
+        }
+
+        node.command = Some(command);
     }
 }
@@ -1,6 +1,10 @@
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Mode {
     Normal,
+    OperatorPending,
+    Visual,
+    VisualLine,
+    VisualBlock,
     Insert,
     Prompt,
 }
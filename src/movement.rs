@@ -1,7 +1,470 @@
-#[derive(Clone, Copy)]
+use std::cmp::min;
+
+use buffer::Buffer;
+use unicode;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Movement {
     Left,
     Right,
     Up,
-    Down
+    Down,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    BigWordForward,
+    BigWordBackward,
+    BigWordEnd,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FirstLine,
+    LastLine,
+    ParagraphForward,
+    ParagraphBackward,
+    MatchingBracket,
+    FindChar(Find),
+    RepeatFind,
+    RepeatFindReverse,
+    /// `n`, the next match of the last search.
+    SearchNext,
+    /// `N`, the next match the other way.
+    SearchPrevious,
+    /// Straight to a position found some other way, like a search match.
+    Jump(Position),
+}
+
+/// A search for a char in the current line, as done by `f`, `t`, `F` and
+/// `T`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Find {
+    pub target: char,
+    pub backward: bool,
+    /// Stop next to the char instead of on it.
+    pub till: bool,
+    /// Set when repeating with `;` or `,`, so a till search doesn't get stuck
+    /// right before the char it already found.
+    pub repeated: bool,
+}
+
+impl Find {
+    pub fn repeat(self) -> Find {
+        Find { repeated: true, ..self }
+    }
+
+    pub fn reversed(self) -> Find {
+        Find { backward: !self.backward, ..self }
+    }
+}
+
+/// A grapheme in the buffer, as a line and the index of the grapheme in it.
+/// The index one past the last grapheme stands for the line break.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub index: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, index: usize) -> Position {
+        Position {
+            line: line,
+            index: index,
+        }
+    }
+}
+
+/// What a grapheme is made of, as far as words are concerned.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Class {
+    Blank,
+    Punctuation,
+    Word,
+}
+
+/// Grapheme level access to the lines of a buffer, keeping the graphemes
+/// of the last line used since motions mostly look at one line at a time.
+pub struct Text<'a> {
+    buffer: &'a Buffer,
+    line: Option<usize>,
+    graphemes: Vec<String>,
+}
+
+impl<'a> Text<'a> {
+    pub fn new(buffer: &'a Buffer) -> Text<'a> {
+        Text {
+            buffer: buffer,
+            line: None,
+            graphemes: Vec::new(),
+        }
+    }
+
+    pub fn last_line(&self) -> usize {
+        self.buffer.len() - 1
+    }
+
+    pub fn graphemes(&mut self, line: usize) -> &[String] {
+        if self.line != Some(line) {
+            self.graphemes = unicode::graphemes(&self.buffer.line(line)).map(|(_, g)| g.to_owned()).collect();
+            self.line = Some(line);
+        }
+
+        &self.graphemes
+    }
+
+    pub fn len(&mut self, line: usize) -> usize {
+        self.graphemes(line).len()
+    }
+
+    /// Grapheme at `position`, or `None` at the line break.
+    pub fn get(&mut self, position: Position) -> Option<&str> {
+        self.graphemes(position.line).get(position.index).map(|g| &g[..])
+    }
+
+    /// Position of the grapheme under display column `x` of `line`.
+    pub fn position_at(&mut self, line: usize, x: usize) -> Position {
+        let mut column = 0;
+
+        for (index, grapheme) in self.graphemes(line).iter().enumerate() {
+            column += unicode::width(grapheme);
+
+            if column > x {
+                return Position::new(line, index);
+            }
+        }
+
+        let len = self.len(line);
+        Position::new(line, len)
+    }
+
+    /// Display column where the grapheme at `position` starts.
+    pub fn column(&mut self, position: Position) -> usize {
+        self.graphemes(position.line).iter().take(position.index).map(|g| unicode::width(g)).sum()
+    }
+
+    /// Byte offset in its line of the grapheme at `position`.
+    pub fn offset(&mut self, position: Position) -> usize {
+        self.graphemes(position.line).iter().take(position.index).map(|g| g.len()).sum()
+    }
+
+    /// Position of the grapheme byte `offset` of `line` is part of.
+    pub fn position_of_offset(&mut self, line: usize, offset: usize) -> Position {
+        let mut end = 0;
+
+        for (index, grapheme) in self.graphemes(line).iter().enumerate() {
+            end += grapheme.len();
+
+            if end > offset {
+                return Position::new(line, index);
+            }
+        }
+
+        let len = self.len(line);
+        Position::new(line, len)
+    }
+
+    pub fn next(&mut self, position: Position) -> Option<Position> {
+        if position.index < self.len(position.line) {
+            Some(Position::new(position.line, position.index + 1))
+        } else if position.line < self.last_line() {
+            Some(Position::new(position.line + 1, 0))
+        } else {
+            None
+        }
+    }
+
+    pub fn previous(&mut self, position: Position) -> Option<Position> {
+        if position.index > 0 {
+            Some(Position::new(position.line, position.index - 1))
+        } else if position.line > 0 {
+            let len = self.len(position.line - 1);
+            Some(Position::new(position.line - 1, len))
+        } else {
+            None
+        }
+    }
+
+    /// Class of the grapheme at `position`, where line breaks are blanks.
+    /// For big words everything but blanks is part of a word.
+    pub fn class(&mut self, position: Position, big: bool) -> Class {
+        let c = match self.get(position).and_then(|g| g.chars().next()) {
+            Some(c) => c,
+            None => return Class::Blank,
+        };
+
+        if c.is_whitespace() {
+            Class::Blank
+        } else if big || c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else {
+            Class::Punctuation
+        }
+    }
+
+    /// Whether the graphemes at `a` and `b` are part of the same word.
+    pub fn same_class(&mut self, a: Position, b: Position, big: bool) -> bool {
+        let class = self.class(a, big);
+        class != Class::Blank && class == self.class(b, big)
+    }
+
+    /// Empty lines count as words, so word motions stop at them.
+    fn is_empty_line(&mut self, position: Position) -> bool {
+        position.index == 0 && self.len(position.line) == 0
+    }
+
+    pub fn first_non_blank(&mut self, line: usize) -> Position {
+        let index = self.graphemes(line).iter()
+            .position(|g| !g.chars().all(char::is_whitespace))
+            .unwrap_or(0);
+
+        Position::new(line, index)
+    }
+}
+
+/// Where `movement` takes the cursor from `position`, for the motions that
+/// work on graphemes. Moving up, down, left and right is left to the window,
+/// which also knows about its width and scrolling.
+///
+/// Most motions are repeated `count` times. `gg` and `G` go to line `count`
+/// instead, `%` to `count` percent of the buffer, and `$` to the end of the
+/// line `count - 1` lines down.
+pub fn target(text: &mut Text, position: Position, movement: Movement, count: Option<usize>) -> Option<Position> {
+    let last = text.last_line();
+
+    match (movement, count) {
+        (Movement::FirstLine, Some(count)) | (Movement::LastLine, Some(count)) => {
+            let line = min(count.saturating_sub(1), last);
+            Some(text.first_non_blank(line))
+        },
+        (Movement::MatchingBracket, Some(count)) => {
+            if count > 100 {
+                return None;
+            }
+
+            let line = (count * (last + 1)).div_ceil(100).saturating_sub(1);
+            Some(text.first_non_blank(line))
+        },
+        (Movement::LineEnd, Some(count)) => {
+            let line = min(position.line + count.saturating_sub(1), last);
+            step(text, Position::new(line, 0), movement)
+        },
+        (movement, count) => {
+            let mut position = step(text, position, movement)?;
+
+            // later finds must get past the char the last one stopped at
+            let movement = match movement {
+                Movement::FindChar(find) => Movement::FindChar(find.repeat()),
+                movement => movement,
+            };
+
+            for _ in 1..count.unwrap_or(1) {
+                let next = step(text, position, movement)?;
+                if next == position {
+                    break;
+                }
+                position = next;
+            }
+
+            Some(position)
+        },
+    }
+}
+
+fn step(text: &mut Text, position: Position, movement: Movement) -> Option<Position> {
+    match movement {
+        Movement::WordForward => Some(word_forward(text, position, false)),
+        Movement::BigWordForward => Some(word_forward(text, position, true)),
+        Movement::WordBackward => Some(word_backward(text, position, false)),
+        Movement::BigWordBackward => Some(word_backward(text, position, true)),
+        Movement::WordEnd => Some(word_end(text, position, false)),
+        Movement::BigWordEnd => Some(word_end(text, position, true)),
+        Movement::LineStart => Some(Position::new(position.line, 0)),
+        Movement::FirstNonBlank => Some(text.first_non_blank(position.line)),
+        Movement::LineEnd => {
+            let len = text.len(position.line);
+            Some(Position::new(position.line, len.saturating_sub(1)))
+        },
+        Movement::FirstLine => Some(text.first_non_blank(0)),
+        Movement::LastLine => {
+            let last = text.last_line();
+            Some(text.first_non_blank(last))
+        },
+        Movement::ParagraphForward => Some(paragraph_forward(text, position)),
+        Movement::ParagraphBackward => Some(paragraph_backward(text, position)),
+        Movement::MatchingBracket => matching_bracket(text, position),
+        Movement::FindChar(find) => find_char(text, position, find),
+        Movement::Jump(target) => Some(target),
+        _ => None,
+    }
+}
+
+fn word_forward(text: &mut Text, start: Position, big: bool) -> Position {
+    let mut position = start;
+
+    let class = text.class(position, big);
+    if class != Class::Blank {
+        while text.class(position, big) == class {
+            position = match text.next(position) {
+                Some(next) => next,
+                None => return position,
+            };
+        }
+    }
+
+    loop {
+        if text.class(position, big) != Class::Blank {
+            return position;
+        }
+
+        if position != start && text.is_empty_line(position) {
+            return position;
+        }
+
+        position = match text.next(position) {
+            Some(next) => next,
+            None => return position,
+        };
+    }
+}
+
+fn word_end(text: &mut Text, start: Position, big: bool) -> Position {
+    let mut position = match text.next(start) {
+        Some(next) => next,
+        None => return start,
+    };
+
+    while text.class(position, big) == Class::Blank {
+        position = match text.next(position) {
+            Some(next) => next,
+            None => return position,
+        };
+    }
+
+    let class = text.class(position, big);
+    loop {
+        match text.next(position) {
+            Some(next) if text.class(next, big) == class => position = next,
+            _ => return position,
+        }
+    }
+}
+
+fn word_backward(text: &mut Text, start: Position, big: bool) -> Position {
+    let mut position = match text.previous(start) {
+        Some(previous) => previous,
+        None => return start,
+    };
+
+    while text.class(position, big) == Class::Blank {
+        if text.is_empty_line(position) {
+            return position;
+        }
+
+        position = match text.previous(position) {
+            Some(previous) => previous,
+            None => return position,
+        };
+    }
+
+    let class = text.class(position, big);
+    loop {
+        match text.previous(position) {
+            Some(previous) if text.class(previous, big) == class => position = previous,
+            _ => return position,
+        }
+    }
+}
+
+/// Paragraphs are separated by empty lines. Moving forward stops at the
+/// next empty line after the paragraph, or at the end of the buffer.
+fn paragraph_forward(text: &mut Text, position: Position) -> Position {
+    let last = text.last_line();
+    let mut line = position.line;
+
+    while line < last && text.len(line) == 0 {
+        line += 1;
+    }
+    while line < last && text.len(line) > 0 {
+        line += 1;
+    }
+
+    let len = text.len(line);
+    Position::new(line, len.saturating_sub(1))
+}
+
+fn paragraph_backward(text: &mut Text, position: Position) -> Position {
+    let mut line = position.line;
+
+    while line > 0 && text.len(line) == 0 {
+        line -= 1;
+    }
+    while line > 0 && text.len(line) > 0 {
+        line -= 1;
+    }
+
+    Position::new(line, 0)
+}
+
+const BRACKETS: &[(&str, &str)] = &[("(", ")"), ("[", "]"), ("{", "}")];
+
+/// Finds the first bracket at or after `position` in its line and jumps to
+/// the bracket matching it.
+fn matching_bracket(text: &mut Text, position: Position) -> Option<Position> {
+    let len = text.len(position.line);
+
+    let (index, open, close, forward) = (position.index..len).filter_map(|index| {
+        let grapheme = text.get(Position::new(position.line, index)).unwrap_or("").to_owned();
+
+        BRACKETS.iter().filter_map(|&(open, close)| {
+            if grapheme == open {
+                Some((index, open, close, true))
+            } else if grapheme == close {
+                Some((index, open, close, false))
+            } else {
+                None
+            }
+        }).next()
+    }).next()?;
+
+    let mut current = Position::new(position.line, index);
+    let mut depth = 0;
+
+    loop {
+        match text.get(current) {
+            Some(g) if g == open => depth += if forward { 1 } else { -1 },
+            Some(g) if g == close => depth += if forward { -1 } else { 1 },
+            _ => (),
+        }
+
+        if depth == 0 {
+            return Some(current);
+        }
+
+        current = if forward { text.next(current)? } else { text.previous(current)? };
+    }
+}
+
+fn find_char(text: &mut Text, position: Position, find: Find) -> Option<Position> {
+    let target = find.target.to_string();
+    let len = text.len(position.line);
+
+    // a repeated till search starts one further, past the char it stopped
+    // next to
+    let skip = if find.till && find.repeated { 2 } else { 1 };
+
+    let found = if find.backward {
+        (0..(position.index + 1).saturating_sub(skip)).rev()
+            .find(|&index| text.get(Position::new(position.line, index)) == Some(&target[..]))
+    } else {
+        ((position.index + skip)..len)
+            .find(|&index| text.get(Position::new(position.line, index)) == Some(&target[..]))
+    }?;
+
+    let index = match (find.till, find.backward) {
+        (true, false) => found - 1,
+        (true, true) => found + 1,
+        (false, _) => found,
+    };
+
+    Some(Position::new(position.line, index))
 }
@@ -0,0 +1,181 @@
+use std::cmp::min;
+
+use movement::{self, Movement, Position, Text};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+    ShiftRight,
+    ShiftLeft,
+    Lowercase,
+    Uppercase,
+    ToggleCase,
+}
+
+impl Operator {
+    /// Shifting always works on whole lines, whatever the motion.
+    pub fn is_linewise(&self) -> bool {
+        matches!(*self, Operator::ShiftRight | Operator::ShiftLeft)
+    }
+}
+
+/// Text an operator works on. Charwise ranges go from `start` up to, but
+/// not including, `end`. Linewise ranges cover whole lines, from the line of
+/// `start` to the line of `end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+    pub linewise: bool,
+}
+
+impl Range {
+    pub fn lines(first: usize, last: usize) -> Range {
+        Range {
+            start: Position::new(first, 0),
+            end: Position::new(last, 0),
+            linewise: true,
+        }
+    }
+}
+
+/// How an operator treats the text between the cursor and the end of a
+/// motion.
+enum Kind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+fn kind(movement: Movement) -> Kind {
+    match movement {
+        Movement::Up | Movement::Down | Movement::FirstLine | Movement::LastLine => Kind::Linewise,
+        Movement::WordEnd | Movement::BigWordEnd | Movement::LineEnd | Movement::MatchingBracket => Kind::Inclusive,
+        Movement::FindChar(find) if !find.backward => Kind::Inclusive,
+        _ => Kind::Exclusive,
+    }
+}
+
+/// The range covered by moving from `position` with `movement`, or `None`
+/// when the motion fails.
+pub fn range(text: &mut Text, position: Position, movement: Movement, count: Option<usize>) -> Option<Range> {
+    let target = motion_target(text, position, movement, count)?;
+    let (start, end) = if target < position { (target, position) } else { (position, target) };
+
+    match kind(movement) {
+        Kind::Linewise => Some(Range::lines(start.line, end.line)),
+        Kind::Inclusive => {
+            let len = text.len(end.line);
+            Some(Range { start: start, end: Position::new(end.line, min(end.index + 1, len)), linewise: false })
+        },
+        Kind::Exclusive => {
+            // an exclusive motion that ends at the start of a line stops at
+            // the end of the line before, and covers whole lines when it
+            // starts in the indent
+            if end.index == 0 && end.line > start.line {
+                if start.index <= text.first_non_blank(start.line).index {
+                    return Some(Range::lines(start.line, end.line - 1));
+                }
+
+                let len = text.len(end.line - 1);
+                return Some(Range { start: start, end: Position::new(end.line - 1, len), linewise: false });
+            }
+
+            Some(Range { start: start, end: end, linewise: false })
+        },
+    }
+}
+
+/// `cw` changes to the end of the word like `ce`, but changes a single char
+/// when the cursor is already at the end of a word.
+/// On a blank it works like `dw`.
+pub fn change_word_range(text: &mut Text, position: Position, big: bool, count: Option<usize>) -> Option<Range> {
+    if !text.same_class(position, position, big) {
+        let movement = if big { Movement::BigWordForward } else { Movement::WordForward };
+        return range(text, position, movement, count);
+    }
+
+    let movement = if big { Movement::BigWordEnd } else { Movement::WordEnd };
+
+    let mut end = match text.next(position) {
+        Some(next) if next.line == position.line && text.same_class(position, next, big) => {
+            movement::target(text, position, movement, None)?
+        },
+        _ => position,
+    };
+
+    for _ in 1..count.unwrap_or(1) {
+        end = movement::target(text, end, movement, None)?;
+    }
+
+    let len = text.len(end.line);
+    Some(Range { start: position, end: Position::new(end.line, min(end.index + 1, len)), linewise: false })
+}
+
+/// Lines covered by the doubled form of an operator, like `3dd`.
+pub fn line_range(text: &mut Text, position: Position, count: Option<usize>) -> Range {
+    let last = text.last_line();
+    let end = position.line.saturating_add(count.unwrap_or(1).saturating_sub(1));
+
+    Range::lines(position.line, min(end, last))
+}
+
+/// Where a motion ends for an operator. Motions that move the cursor
+/// cell by cell are worked out here since the window does them for plain
+/// cursor movement, and `w` stops at the end of the line instead of going
+/// on to the next one, so `dw` on the last word of a line keeps the line
+/// break.
+fn motion_target(text: &mut Text, position: Position, movement: Movement, count: Option<usize>) -> Option<Position> {
+    let n = count.unwrap_or(1);
+    let last = text.last_line();
+
+    match movement {
+        Movement::Left => {
+            if position.index == 0 {
+                return None;
+            }
+
+            Some(Position::new(position.line, position.index.saturating_sub(n)))
+        },
+        Movement::Right => {
+            let len = text.len(position.line);
+            if len == 0 {
+                return None;
+            }
+
+            Some(Position::new(position.line, min(position.index.saturating_add(n), len)))
+        },
+        Movement::Up => {
+            if position.line == 0 {
+                return None;
+            }
+
+            Some(Position::new(position.line.saturating_sub(n), 0))
+        },
+        Movement::Down => {
+            if position.line == last {
+                return None;
+            }
+
+            Some(Position::new(min(position.line.saturating_add(n), last), 0))
+        },
+        Movement::WordForward | Movement::BigWordForward => {
+            let before = if n > 1 {
+                movement::target(text, position, movement, Some(n - 1))?
+            } else {
+                position
+            };
+
+            let target = movement::target(text, before, movement, None)?;
+            if target.line > before.line {
+                let len = text.len(before.line);
+                Some(Position::new(before.line, len))
+            } else {
+                Some(target)
+            }
+        },
+        movement => movement::target(text, position, movement, count),
+    }
+}
@@ -0,0 +1,297 @@
+extern crate regex;
+
+use self::regex::{Regex, RegexBuilder};
+
+use error::{Error, Result};
+
+/// Chars that mean something in a pattern, either on their own or after a
+/// backslash depending on the magic level.
+const SPECIAL: &str = "^$.*[~+?={()|<>@%&";
+
+/// How much of the pattern syntax works without a backslash. Patterns
+/// switch between the levels with `\v`, `\m`, `\M` and `\V`.
+#[derive(Clone, Copy, PartialEq)]
+enum Magic {
+    /// `\v`, where every special char works on its own, like in most
+    /// regex syntaxes.
+    Very,
+    /// `\m`, the default: `^ $ . * [ ~` work on their own.
+    On,
+    /// `\M`: only `^` and `$` work on their own.
+    Off,
+    /// `\V`: everything is literal unless it follows a backslash.
+    VeryOff,
+}
+
+impl Magic {
+    fn is_special(self, c: char) -> bool {
+        match self {
+            Magic::Very => SPECIAL.contains(c),
+            Magic::On => "^$.*[~".contains(c),
+            Magic::Off => "^$".contains(c),
+            Magic::VeryOff => false,
+        }
+    }
+}
+
+/// A Vim style search pattern, compiled to a regex. `^` and `$` match at
+/// line breaks, `.` doesn't match them and `\n` does, so patterns can span
+/// lines when they run on the whole text of a buffer.
+pub struct Pattern {
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Lowercase patterns ignore case, unless they say otherwise with `\c`
+    /// or `\C`.
+    pub fn new(pattern: &str) -> Result<Pattern> {
+        let translation = Translation::new(pattern)?;
+        let ignore_case = translation.ignore_case.unwrap_or(!translation.has_uppercase);
+
+        let regex = RegexBuilder::new(&translation.regex)
+            .multi_line(true)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|_| Error::InvalidPattern(pattern.to_owned()))?;
+
+        Ok(Pattern { regex: regex })
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+struct Translation {
+    regex: String,
+    ignore_case: Option<bool>,
+    has_uppercase: bool,
+}
+
+impl Translation {
+    fn new(pattern: &str) -> Result<Translation> {
+        let chars = pattern.chars().collect::<Vec<_>>();
+        let invalid = || Error::InvalidPattern(pattern.to_owned());
+
+        let mut translation = Translation { regex: String::new(), ignore_case: None, has_uppercase: false };
+        let mut magic = Magic::On;
+        // `^` only anchors at the start of the pattern or of a branch
+        let mut branch_start = true;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let escaped = chars[i] == '\\' && i + 1 < chars.len();
+            if escaped {
+                i += 1;
+            }
+            let c = chars[i];
+            i += 1;
+
+            let at_start = branch_start;
+            branch_start = false;
+
+            if escaped && !SPECIAL.contains(c) {
+                match c {
+                    'v' => magic = Magic::Very,
+                    'm' => magic = Magic::On,
+                    'M' => magic = Magic::Off,
+                    'V' => magic = Magic::VeryOff,
+                    'c' => translation.ignore_case = Some(true),
+                    'C' => translation.ignore_case = Some(false),
+                    'n' => translation.regex.push_str("\\n"),
+                    't' => translation.regex.push_str("\\t"),
+                    'e' => translation.regex.push_str("\\x1b"),
+                    'r' => translation.regex.push_str("\\r"),
+                    '_' => {
+                        // `\_x` is `x` matching line breaks as well
+                        let c = *chars.get(i).ok_or_else(invalid)?;
+                        i += 1;
+
+                        match c {
+                            '.' => translation.regex.push_str("(?s:.)"),
+                            '^' => translation.regex.push('^'),
+                            '$' => translation.regex.push('$'),
+                            '[' => {
+                                let class = bracket(&chars, &mut i, true).ok_or_else(invalid)?;
+                                translation.push_class(&class);
+                            },
+                            c => translation.regex.push_str(&class(c, true).ok_or_else(invalid)?),
+                        }
+                    },
+                    '1'..='9' => return Err(invalid()),
+                    c => match class(c, false) {
+                        Some(class) => translation.regex.push_str(&class),
+                        None => translation.push_literal(c),
+                    },
+                }
+
+                // switches don't take the place of what follows
+                if "vmMVcC".contains(c) {
+                    branch_start = at_start;
+                }
+                continue;
+            }
+
+            if magic.is_special(c) == escaped {
+                translation.push_literal(c);
+                continue;
+            }
+
+            match c {
+                '^' if at_start => translation.regex.push('^'),
+                '$' if at_branch_end(&chars[i..], magic) => translation.regex.push('$'),
+                '^' | '$' | '~' => translation.push_literal(c),
+                '.' => translation.regex.push('.'),
+                '*' | '+' => translation.regex.push(c),
+                '?' | '=' => translation.regex.push('?'),
+                '{' => translation.regex.push_str(&repetition(&chars, &mut i).ok_or_else(invalid)?),
+                '(' | '|' => {
+                    translation.regex.push(c);
+                    branch_start = true;
+                },
+                ')' => translation.regex.push(')'),
+                '<' => translation.regex.push_str("\\b{start}"),
+                '>' => translation.regex.push_str("\\b{end}"),
+                '%' if chars.get(i) == Some(&'(') => {
+                    i += 1;
+                    translation.regex.push_str("(?:");
+                    branch_start = true;
+                },
+                '[' => match bracket(&chars, &mut i, false) {
+                    Some(class) => translation.push_class(&class),
+                    None => translation.push_literal('['),
+                },
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(translation)
+    }
+
+    fn push_literal(&mut self, c: char) {
+        self.has_uppercase |= c.is_uppercase();
+        self.regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+    }
+
+    fn push_class(&mut self, class: &str) {
+        self.has_uppercase |= class.chars().any(char::is_uppercase);
+        self.regex.push_str(class);
+    }
+}
+
+/// `$` only anchors at the end of the pattern, of a branch or of a group,
+/// or before a line break.
+fn at_branch_end(rest: &[char], magic: Magic) -> bool {
+    let escaped = |c| rest.len() >= 2 && rest[0] == '\\' && rest[1] == c;
+
+    match rest.first() {
+        None => true,
+        Some(&c) if magic == Magic::Very && (c == '|' || c == ')') => true,
+        _ => escaped('n') || magic != Magic::Very && (escaped('|') || escaped(')')),
+    }
+}
+
+/// The class a backslash and `c` stand for, like `\s` or `\d`. Classes
+/// don't match line breaks, unless `newline` says they do as for `\_s`.
+fn class(c: char, newline: bool) -> Option<String> {
+    let (chars, negated) = match c {
+        's' | 'S' => ("\\t ", c == 'S'),
+        'd' | 'D' => ("0-9", c == 'D'),
+        'w' | 'W' => ("0-9A-Za-z_", c == 'W'),
+        'h' | 'H' => ("A-Za-z_", c == 'H'),
+        'a' | 'A' => ("A-Za-z", c == 'A'),
+        'l' | 'L' => ("a-z", c == 'L'),
+        'u' | 'U' => ("A-Z", c == 'U'),
+        'x' | 'X' => ("0-9A-Fa-f", c == 'X'),
+        _ => return None,
+    };
+
+    Some(match (negated, newline) {
+        (false, false) => format!("[{}]", chars),
+        (false, true) => format!("[{}\\n]", chars),
+        (true, false) => format!("[^{}\\n]", chars),
+        (true, true) => format!("[^{}]", chars),
+    })
+}
+
+/// Translates the bracket expression starting at `i`, right after its `[`,
+/// or gives `None` when it isn't closed and the `[` is just a char.
+fn bracket(chars: &[char], i: &mut usize, newline: bool) -> Option<String> {
+    let mut j = *i;
+    let mut class = String::from("[");
+
+    let negated = chars.get(j) == Some(&'^');
+    if negated {
+        class.push('^');
+        j += 1;
+    }
+    if negated != newline {
+        class.push_str("\\n");
+    }
+
+    let first = j;
+    loop {
+        let c = *chars.get(j)?;
+        j += 1;
+
+        match c {
+            ']' if j - 1 > first => break,
+            '[' if chars.get(j) == Some(&':') => {
+                // character classes like `[:alpha:]` work the same way
+                let end = (j..chars.len()).find(|&k| chars[k] == ']')?;
+                class.extend(&chars[(j - 1)..(end + 1)]);
+                j = end + 1;
+            },
+            '\\' => {
+                let c = *chars.get(j)?;
+                j += 1;
+
+                match c {
+                    'n' => class.push_str("\\n"),
+                    't' => class.push_str("\\t"),
+                    'e' => class.push_str("\\x1b"),
+                    'r' => class.push_str("\\r"),
+                    c => class.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                }
+            },
+            '-' => class.push('-'),
+            c => class.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+
+    class.push(']');
+    *i = j;
+
+    Some(class)
+}
+
+/// Translates `\{n,m}` starting at `i`, right after its `{`. A `-` first
+/// makes it match as few times as it can.
+fn repetition(chars: &[char], i: &mut usize) -> Option<String> {
+    let end = (*i..chars.len()).find(|&k| chars[k] == '}')?;
+    let mut inside = chars[*i..end].iter().collect::<String>();
+    *i = end + 1;
+
+    // the closing brace may have a backslash too
+    if inside.ends_with('\\') {
+        inside.pop();
+    }
+
+    let lazy = inside.starts_with('-');
+    if lazy {
+        inside.remove(0);
+    }
+
+    if !inside.chars().all(|c| c.is_ascii_digit() || c == ',') || inside.matches(',').count() > 1 {
+        return None;
+    }
+
+    let repetition = match inside.find(',') {
+        _ if inside.is_empty() => "*".to_owned(),
+        None => format!("{{{}}}", inside),
+        Some(0) => format!("{{0{}}}", inside),
+        Some(_) => format!("{{{}}}", inside),
+    };
+
+    Some(if lazy { format!("{}?", repetition) } else { repetition })
+}
@@ -55,6 +55,19 @@ impl Prompt {
         self.message = Some(text.to_owned());
     }
 
+    /// Messages spanning several lines are drawn over the bottom of the
+    /// window, so they are only kept until the next key.
+    pub fn has_long_message(&self) -> bool {
+        match self.message {
+            Some(ref text) => text.contains('\n'),
+            None => false,
+        }
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
     pub fn delete_grapheme(&mut self) {
         if let Some(c) = self.command_text.pop() {
             self.cur_x -= unicode::width_char(c);
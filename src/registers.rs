@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use error::{Error, Result};
+use selection::SelectionKind;
+
+/// Text held by a register, and whether it was taken from whole lines or a
+/// block, which tells how it's put back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register {
+    pub text: String,
+    pub kind: SelectionKind,
+}
+
+impl Register {
+    pub fn new(text: &str, kind: SelectionKind) -> Register {
+        Register {
+            text: text.to_owned(),
+            kind: kind,
+        }
+    }
+
+    /// Adds `other` at the end, as Vim does for uppercase register names.
+    /// Anything appended to lines, or lines appended to anything, goes on
+    /// lines of its own.
+    fn append(&mut self, other: &Register) {
+        if self.kind == SelectionKind::Linewise || other.kind == SelectionKind::Linewise {
+            if !self.text.ends_with('\n') {
+                self.text.push('\n');
+            }
+            self.text.push_str(&other.text);
+            if !self.text.ends_with('\n') {
+                self.text.push('\n');
+            }
+            self.kind = SelectionKind::Linewise;
+        } else {
+            if self.kind == SelectionKind::Blockwise {
+                self.text.push('\n');
+            }
+            self.text.push_str(&other.text);
+        }
+    }
+}
+
+/// The registers text is yanked to and put from. Besides the named ones,
+/// `"0` holds the last yank, `"1` to `"9` the last deletes of lines, and
+/// `"-` the last smaller delete. `"_` drops what's written to it. What's
+/// written to `"+` and `"*` is kept too, so its kind isn't lost on the
+/// way through the system clipboard.
+pub struct Registers {
+    unnamed: Option<Register>,
+    numbered: Vec<Option<Register>>,
+    small_delete: Option<Register>,
+    named: HashMap<char, Register>,
+    selections: HashMap<char, Register>,
+    last_inserted: Option<String>,
+    last_command: Option<String>,
+}
+
+impl Default for Registers {
+    fn default() -> Registers {
+        Registers::new()
+    }
+}
+
+impl Registers {
+    pub fn new() -> Registers {
+        Registers {
+            unnamed: None,
+            numbered: vec![None; 10],
+            small_delete: None,
+            named: HashMap::new(),
+            selections: HashMap::new(),
+            last_inserted: None,
+            last_command: None,
+        }
+    }
+
+    /// Whether `name` is a register at all.
+    pub fn is_valid(name: char) -> bool {
+        Registers::is_writable(name) || name == '.' || name == '%' || name == ':'
+    }
+
+    /// Whether `name` can be written to.
+    pub fn is_writable(name: char) -> bool {
+        name.is_ascii_alphanumeric() || name == '"' || name == '-' || name == '_' || name == '+' || name == '*'
+    }
+
+    /// Stores yanked text, in register `name` if given, or else in `"0`.
+    pub fn yank(&mut self, name: Option<char>, register: Register) -> Result<()> {
+        match name {
+            Some(name) if name != '"' => self.write_unnamed(name, register),
+            _ => {
+                self.numbered[0] = Some(register.clone());
+                self.unnamed = Some(register);
+                Ok(())
+            },
+        }
+    }
+
+    /// Stores deleted text, in register `name` if given. Otherwise deletes
+    /// of lines, or spanning lines, go to `"1`, pushing the older ones on
+    /// to `"9`, and smaller ones to `"-`.
+    pub fn delete(&mut self, name: Option<char>, register: Register) -> Result<()> {
+        match name {
+            Some(name) if name != '"' => self.write_unnamed(name, register),
+            _ => {
+                if register.kind == SelectionKind::Linewise || register.text.contains('\n') {
+                    self.numbered.pop();
+                    self.numbered.insert(1, Some(register.clone()));
+                } else {
+                    self.small_delete = Some(register.clone());
+                }
+
+                self.unnamed = Some(register);
+                Ok(())
+            },
+        }
+    }
+
+    /// Stores keys recorded with `q`, as text. The unnamed register is
+    /// left alone.
+    pub fn record(&mut self, name: char, text: &str) -> Result<()> {
+        self.write(name, Register::new(text, SelectionKind::Charwise)).map(|_| ())
+    }
+
+    /// Writes register `name`, and makes the unnamed register hold what's
+    /// in it now.
+    fn write_unnamed(&mut self, name: char, register: Register) -> Result<()> {
+        if let Some(written) = self.write(name, register)? {
+            self.unnamed = Some(written);
+        }
+
+        Ok(())
+    }
+
+    /// Writes register `name`, returning what's in it now, which for an
+    /// uppercase name is the register appended to.
+    fn write(&mut self, name: char, register: Register) -> Result<Option<Register>> {
+        if !Registers::is_writable(name) || name == '"' {
+            return Err(Error::InvalidRegister(name));
+        }
+
+        match name {
+            '_' => return Ok(None),
+            '-' => self.small_delete = Some(register.clone()),
+            '+' | '*' => {
+                self.selections.insert(name, register.clone());
+            },
+            '0'..='9' => self.numbered[name as usize - '0' as usize] = Some(register.clone()),
+            'A'..='Z' => {
+                let name = name.to_ascii_lowercase();
+                let appended = match self.named.remove(&name) {
+                    Some(mut existing) => {
+                        existing.append(&register);
+                        existing
+                    },
+                    None => register,
+                };
+
+                self.named.insert(name, appended.clone());
+                return Ok(Some(appended));
+            },
+            _ => {
+                self.named.insert(name, register.clone());
+            },
+        }
+
+        Ok(Some(register))
+    }
+
+    pub fn set_last_inserted(&mut self, text: &str) {
+        self.last_inserted = Some(text.to_owned());
+    }
+
+    pub fn set_last_command(&mut self, text: &str) {
+        self.last_command = Some(text.to_owned());
+    }
+
+    /// The contents of register `name`. `"%`, the current file name, is
+    /// left to the editor, and so is looking at the system clipboard for
+    /// `"+` and `"*`, which give what was last written to them here.
+    pub fn get(&self, name: char) -> Result<Option<Register>> {
+        let register = match name {
+            '"' => self.unnamed.clone(),
+            '-' => self.small_delete.clone(),
+            '0'..='9' => self.numbered[name as usize - '0' as usize].clone(),
+            'a'..='z' | 'A'..='Z' => self.named.get(&name.to_ascii_lowercase()).cloned(),
+            '+' | '*' => self.selections.get(&name).cloned(),
+            '.' => self.last_inserted.as_ref().map(|text| Register::new(text, SelectionKind::Charwise)),
+            ':' => self.last_command.as_ref().map(|text| Register::new(text, SelectionKind::Charwise)),
+            '_' | '%' => None,
+            _ => return Err(Error::InvalidRegister(name)),
+        };
+
+        Ok(register)
+    }
+
+    /// Names of the registers with something in them, in the order
+    /// `:registers` lists them.
+    pub fn names(&self) -> Vec<char> {
+        let mut names = vec!['"'];
+        names.extend("0123456789".chars());
+        names.extend((b'a'..(b'z' + 1)).map(|b| b as char));
+        names.extend("-*+.:".chars());
+
+        names.into_iter().filter(|&name| matches!(self.get(name), Ok(Some(_)))).collect()
+    }
+}
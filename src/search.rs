@@ -0,0 +1,240 @@
+use std::cmp::min;
+
+use buffer::Buffer;
+use error::Result;
+use movement::{Position, Text};
+use pattern::Pattern;
+
+/// A search typed at the `/` or `?` prompt, which `n` and `N` repeat.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Search {
+    pub pattern: String,
+    pub backward: bool,
+}
+
+impl Search {
+    pub fn new(pattern: &str, backward: bool) -> Search {
+        Search {
+            pattern: pattern.to_owned(),
+            backward: backward,
+        }
+    }
+
+    pub fn reversed(&self) -> Search {
+        Search { backward: !self.backward, ..self.clone() }
+    }
+}
+
+/// Text a pattern matched, from the grapheme at `start` up to, but not
+/// including, the one at `end`. A match that takes in a line break ends at
+/// the start of the next line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Match {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Where a search stopped, and whether it went past an end of the buffer
+/// to get there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Found {
+    pub matched: Match,
+    pub wrapped: bool,
+}
+
+/// Lines of a buffer joined by line breaks, so patterns can match across
+/// them, along with where each line starts.
+pub struct Haystack {
+    text: String,
+    first: usize,
+    starts: Vec<usize>,
+}
+
+impl Haystack {
+    pub fn new(buffer: &Buffer) -> Haystack {
+        Haystack::with_lines(buffer, 0, buffer.len())
+    }
+
+    /// Only has the lines from `first` up to, but not including, `end`.
+    pub fn with_lines(buffer: &Buffer, first: usize, end: usize) -> Haystack {
+        let mut text = String::new();
+        let mut starts = Vec::with_capacity(end - first);
+
+        for line in first..end {
+            if line > first {
+                text.push('\n');
+            }
+
+            starts.push(text.len());
+            text.push_str(&buffer.line(line));
+        }
+
+        Haystack {
+            text: text,
+            first: first,
+            starts: starts,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset in the text of the grapheme at `position`.
+    pub fn offset(&self, text: &mut Text, position: Position) -> usize {
+        self.starts[position.line - self.first] + text.offset(position)
+    }
+
+    /// Offset in the text where `line` starts, if it's there.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        line.checked_sub(self.first).and_then(|index| self.starts.get(index).cloned())
+    }
+
+    /// Line and byte offset in it of byte `offset` of the text, where a line
+    /// break belongs to the line it ends.
+    pub fn point(&self, offset: usize) -> (usize, usize) {
+        let index = match self.starts.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+
+        (self.first + index, offset - self.starts[index])
+    }
+
+    /// Position of the grapheme byte `offset` of the text is part of.
+    pub fn position(&self, text: &mut Text, offset: usize) -> Position {
+        let (line, offset) = self.point(offset);
+        text.position_of_offset(line, offset)
+    }
+}
+
+/// The `count`th match of `search` from `position`, going on from the other
+/// end of the buffer when it reaches one.
+pub fn find(buffer: &Buffer, position: Position, search: &Search, count: usize) -> Result<Option<Found>> {
+    let pattern = Pattern::new(&search.pattern)?;
+    let haystack = buffer.haystack();
+    let mut text = Text::new(buffer);
+
+    let mut position = position;
+    let mut found = None;
+    let mut wrapped = false;
+
+    for _ in 0..count {
+        let offset = haystack.offset(&mut text, position);
+
+        let next = if search.backward {
+            previous_match(&pattern, haystack.text(), offset)
+        } else {
+            // matches start after the grapheme at the cursor, or after the
+            // line break when the cursor is past the end of the line
+            let len = text.get(position).map_or(1, str::len);
+            next_match(&pattern, haystack.text(), offset + len)
+        };
+
+        let (start, end, next_wrapped) = match next {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+
+        let matched = Match {
+            start: haystack.position(&mut text, start),
+            end: haystack.position(&mut text, end),
+        };
+
+        position = matched.start;
+        found = Some(matched);
+        wrapped |= next_wrapped;
+    }
+
+    Ok(found.map(|matched| Found { matched: matched, wrapped: wrapped }))
+}
+
+/// The line of the first match of `search` after `line`, or of the last
+/// one before it going backward, going on from the other end of the buffer
+/// when there's none.
+pub fn find_line(buffer: &Buffer, line: usize, search: &Search) -> Result<Option<usize>> {
+    let pattern = Pattern::new(&search.pattern)?;
+    let haystack = buffer.haystack();
+
+    let found = if search.backward {
+        previous_match(&pattern, haystack.text(), haystack.line_start(line).unwrap_or(0))
+    } else {
+        let start = haystack.line_start(line + 1).unwrap_or(haystack.text().len() + 1);
+        next_match(&pattern, haystack.text(), start)
+    };
+
+    Ok(found.map(|(start, _, _)| haystack.point(start).0))
+}
+
+/// Matches of `pattern` in the lines from `first` up to, but not including,
+/// `end`, leaving out the empty ones.
+pub fn matches(buffer: &Buffer, pattern: &Pattern, first: usize, end: usize) -> Vec<Match> {
+    let haystack = Haystack::with_lines(buffer, first, end);
+    let mut text = Text::new(buffer);
+
+    pattern.regex().find_iter(haystack.text())
+        .filter(|found| found.start() < found.end())
+        .map(|found| Match {
+            start: haystack.position(&mut text, found.start()),
+            end: haystack.position(&mut text, found.end()),
+        })
+        .collect()
+}
+
+/// Offsets of the first match starting at `start` or later, or of the
+/// first one in the text.
+fn next_match(pattern: &Pattern, text: &str, start: usize) -> Option<(usize, usize, bool)> {
+    if start <= text.len() {
+        if let Some(found) = pattern.regex().find_at(text, start) {
+            return Some((found.start(), found.end(), false));
+        }
+    }
+
+    pattern.regex().find(text).map(|found| (found.start(), found.end(), true))
+}
+
+/// Offsets of the last match starting before `end`, or of the last one in
+/// the text.
+fn previous_match(pattern: &Pattern, text: &str, end: usize) -> Option<(usize, usize, bool)> {
+    if let Some((start, end)) = last_match_before(pattern, text, end) {
+        return Some((start, end, false));
+    }
+
+    last_match_before(pattern, text, text.len() + 1).map(|(start, end)| (start, end, true))
+}
+
+/// Offsets of the last match starting before `end`. Matches may overlap,
+/// so every start is tried, in parts of the text going back from `end`
+/// that get twice as large each time, so a match close to it is found
+/// without going through everything before.
+fn last_match_before(pattern: &Pattern, text: &str, end: usize) -> Option<(usize, usize)> {
+    let mut part_end = min(end, text.len() + 1);
+    let mut size = 1024;
+
+    loop {
+        let mut part_start = part_end.saturating_sub(size);
+        while !text.is_char_boundary(part_start) {
+            part_start -= 1;
+        }
+
+        let mut start = part_start;
+        let mut last = None;
+
+        while start < part_end && start <= text.len() {
+            let found = match pattern.regex().find_at(text, start) {
+                Some(found) if found.start() < part_end => (found.start(), found.end()),
+                _ => break,
+            };
+
+            last = Some(found);
+            start = found.0 + text[found.0..].chars().next().map_or(1, char::len_utf8);
+        }
+
+        if last.is_some() || part_start == 0 {
+            return last;
+        }
+
+        part_end = part_start;
+        size *= 2;
+    }
+}
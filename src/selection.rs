@@ -0,0 +1,85 @@
+use movement::{Position, Text};
+use operator::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionKind {
+    Charwise,
+    Linewise,
+    Blockwise,
+}
+
+/// Text selected in visual mode, between the anchor where the selection
+/// started and the cursor. Points are a buffer line and the display column
+/// where a grapheme starts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Selection {
+    pub kind: SelectionKind,
+    /// The first and last selected graphemes, whichever of the anchor and
+    /// the cursor they are.
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    /// Display columns a blockwise selection covers, from `left` up to but
+    /// not including `right`.
+    pub left: usize,
+    pub right: usize,
+}
+
+/// A rectangle of text, made of the graphemes of each line from `first` to
+/// `last` that are at least partly between display columns `left` and
+/// `right`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Block {
+    pub first: usize,
+    pub last: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Selection {
+    /// Whether a grapheme of `width` cells at display column `x` of `line`
+    /// is selected.
+    pub fn contains(&self, line: usize, x: usize, width: usize) -> bool {
+        if line < self.start.0 || line > self.end.0 {
+            return false;
+        }
+
+        match self.kind {
+            SelectionKind::Linewise => true,
+            SelectionKind::Charwise => (line, x) >= self.start && (line, x) <= self.end,
+            SelectionKind::Blockwise => x < self.right && x + width > self.left,
+        }
+    }
+
+    /// The text an operator works on. Blocks are left to `block`, and come
+    /// out as their lines here.
+    pub fn range(&self, text: &mut Text) -> Range {
+        match self.kind {
+            SelectionKind::Linewise | SelectionKind::Blockwise => Range::lines(self.start.0, self.end.0),
+            SelectionKind::Charwise => {
+                let start = text.position_at(self.start.0, self.start.1);
+                let last = text.position_at(self.end.0, self.end.1);
+                let len = text.len(last.line);
+
+                // the line break is selected along with an empty line
+                let end = if last.index < len {
+                    Position::new(last.line, last.index + 1)
+                } else if last.line < text.last_line() {
+                    Position::new(last.line + 1, 0)
+                } else {
+                    Position::new(last.line, len)
+                };
+
+                Range { start: start, end: end, linewise: false }
+            },
+        }
+    }
+
+    pub fn block(&self) -> Block {
+        Block {
+            first: self.start.0,
+            last: self.end.0,
+            left: self.left,
+            right: self.right,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use error::{Error, Result};
+
+/// Runs `command` with `sh -c`, giving it `input` on stdin, and gives back
+/// what it wrote to stdout. A command that fails gives what it wrote to
+/// stderr as the error.
+pub fn run(command: &str, input: Option<&str>) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(Error::IoError)?;
+
+    // the input goes in while the output is read, so a command writing a
+    // lot before it's done reading doesn't block on a full pipe
+    let writer = match (child.stdin.take(), input) {
+        (Some(mut stdin), Some(input)) => {
+            let input = input.to_owned();
+            Some(thread::spawn(move || stdin.write_all(input.as_bytes())))
+        },
+        _ => None,
+    };
+
+    let output = child.wait_with_output().map_err(Error::IoError)?;
+
+    // commands may exit without reading all of it, which isn't an error
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim_end().to_owned();
+        return Err(Error::ShellError(match output.status.code() {
+            _ if !message.is_empty() => message,
+            Some(code) => format!("{} returned {}", command, code),
+            None => format!("{} was killed", command),
+        }));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,259 @@
+extern crate regex;
+
+use self::regex::Captures;
+
+use buffer::Buffer;
+use error::{Error, Result};
+use movement::Text;
+use pattern::Pattern;
+use search::Match;
+
+/// What `:substitute` changes, as typed in `:s/pattern/replacement/flags`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    pub flags: Flags,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Flags {
+    /// `g`, every match in a line instead of the first one.
+    pub global: bool,
+    /// `i` and `I`, which ignore case or not whatever the pattern says.
+    pub ignore_case: Option<bool>,
+    /// `c`, asking before each replacement.
+    pub confirm: bool,
+    /// `n`, counting the matches without replacing them.
+    pub count_only: bool,
+}
+
+impl Substitution {
+    /// Parses what follows `:s`, where the first char is the delimiter.
+    /// The replacement and the flags can be left out along with the
+    /// delimiters before them.
+    pub fn parse(text: &str) -> Result<Substitution> {
+        let delimiter = match text.chars().next() {
+            Some(c) if !c.is_alphanumeric() && c != '\\' && c != '"' && c != '|' && c != ' ' => c,
+            _ => return Err(Error::InvalidArgument(text.to_owned())),
+        };
+
+        let mut parts = split(&text[delimiter.len_utf8()..], delimiter).into_iter();
+        let pattern = parts.next().unwrap_or_default();
+        let replacement = parts.next().unwrap_or_default();
+        let flags = parse_flags(&parts.next().unwrap_or_default())?;
+
+        Ok(Substitution {
+            pattern: pattern,
+            replacement: replacement,
+            flags: flags,
+        })
+    }
+
+    fn compile(&self) -> Result<Pattern> {
+        match self.flags.ignore_case {
+            Some(true) => Pattern::new(&format!("{}\\c", self.pattern)),
+            Some(false) => Pattern::new(&format!("{}\\C", self.pattern)),
+            None => Pattern::new(&self.pattern),
+        }
+    }
+}
+
+/// Splits `text` at the delimiters that aren't escaped, into three parts
+/// at most. The backslashes are kept, except those escaping a delimiter.
+fn split(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == delimiter && parts.len() < 3 {
+            parts.push(String::new());
+            continue;
+        }
+
+        let part = parts.last_mut().unwrap();
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delimiter => part.push(next),
+                Some(next) => {
+                    part.push(c);
+                    part.push(next);
+                },
+                None => part.push(c),
+            }
+        } else {
+            part.push(c);
+        }
+    }
+
+    parts
+}
+
+fn parse_flags(text: &str) -> Result<Flags> {
+    let mut flags = Flags::default();
+
+    for c in text.trim().chars() {
+        match c {
+            'g' => flags.global = true,
+            'i' => flags.ignore_case = Some(true),
+            'I' => flags.ignore_case = Some(false),
+            'c' => flags.confirm = true,
+            'n' => flags.count_only = true,
+            _ => return Err(Error::InvalidArgument(text.to_owned())),
+        }
+    }
+
+    Ok(flags)
+}
+
+/// A match to replace, from `start` up to `end`, both given as a line and a
+/// byte offset in it like `Buffer` takes them, with the text to put there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacement {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub text: String,
+    pub matched: Match,
+}
+
+/// What `substitution` replaces in the lines from `first` to `last`. Only
+/// matches starting in those lines count, but they can go on past them.
+pub fn replacements(buffer: &Buffer, substitution: &Substitution, first: usize, last: usize) -> Result<Vec<Replacement>> {
+    let pattern = substitution.compile()?;
+    let haystack = buffer.haystack();
+    let mut text = Text::new(buffer);
+    let mut replacements = Vec::new();
+
+    let mut offset = match haystack.line_start(first) {
+        Some(offset) => offset,
+        None => return Ok(replacements),
+    };
+
+    while offset <= haystack.text().len() {
+        let captures = match pattern.regex().captures_at(haystack.text(), offset) {
+            Some(captures) => captures,
+            None => break,
+        };
+        let whole = captures.get(0).unwrap();
+
+        let start = haystack.point(whole.start());
+        if start.0 > last {
+            break;
+        }
+
+        replacements.push(Replacement {
+            start: start,
+            end: haystack.point(whole.end()),
+            text: expand(&substitution.replacement, &captures),
+            matched: Match {
+                start: haystack.position(&mut text, whole.start()),
+                end: haystack.position(&mut text, whole.end()),
+            },
+        });
+
+        offset = if !substitution.flags.global {
+            match haystack.line_start(start.0 + 1) {
+                Some(offset) => offset,
+                None => break,
+            }
+        } else if whole.end() > whole.start() {
+            whole.end()
+        } else {
+            whole.end() + haystack.text()[whole.end()..].chars().next().map_or(1, char::len_utf8)
+        };
+    }
+
+    Ok(replacements)
+}
+
+/// Puts the replacements in place, which must come in order and not
+/// overlap, all in a single edit. Gives the line the last one starts in
+/// once they're all done.
+pub fn apply(buffer: &mut Buffer, replacements: &[Replacement]) -> Result<usize> {
+    let (first, last) = match (replacements.first(), replacements.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(0),
+    };
+
+    let mut text = String::new();
+    let mut line = last.start.0;
+
+    for (i, replacement) in replacements.iter().enumerate() {
+        if i > 0 {
+            text.push_str(&buffer.text_range(replacements[i - 1].end, replacement.start));
+        }
+        text.push_str(&replacement.text);
+
+        // line breaks put in or taken out before the last one move it
+        if i + 1 < replacements.len() {
+            let added = replacement.text.matches('\n').count();
+            line = line + added - (replacement.end.0 - replacement.start.0);
+        }
+    }
+
+    buffer.replace_range(first.start, last.end, &text)?;
+
+    Ok(line)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+/// Replacement text being put together, with the case changes that are on.
+#[derive(Default)]
+struct Expansion {
+    text: String,
+    /// From `\u` or `\l`, only for the next char.
+    next_case: Option<Case>,
+    /// From `\U` or `\L`, until `\E`.
+    case: Option<Case>,
+}
+
+impl Expansion {
+    fn push(&mut self, c: char) {
+        match self.next_case.take().or(self.case) {
+            Some(Case::Upper) => self.text.extend(c.to_uppercase()),
+            Some(Case::Lower) => self.text.extend(c.to_lowercase()),
+            None => self.text.push(c),
+        }
+    }
+}
+
+/// The text a match is replaced with. `&` and `\0` stand for the whole
+/// match and `\1` to `\9` for its groups. `\u` and `\l` change the case of
+/// the next char, `\U` and `\L` that of everything up to `\E` or `\e`.
+/// `\r` and `\n` break the line.
+fn expand(replacement: &str, captures: &Captures) -> String {
+    let mut expansion = Expansion::default();
+    let mut chars = replacement.chars();
+
+    while let Some(c) = chars.next() {
+        let group = match c {
+            '&' => Some(0),
+            '\\' => match chars.next() {
+                Some(digit @ '0'..='9') => digit.to_digit(10),
+                Some('u') => { expansion.next_case = Some(Case::Upper); None },
+                Some('l') => { expansion.next_case = Some(Case::Lower); None },
+                Some('U') => { expansion.case = Some(Case::Upper); None },
+                Some('L') => { expansion.case = Some(Case::Lower); None },
+                Some('E') | Some('e') => { expansion.case = None; None },
+                Some('r') | Some('n') => { expansion.text.push('\n'); None },
+                Some('t') => { expansion.push('\t'); None },
+                Some(c) => { expansion.push(c); None },
+                None => { expansion.push('\\'); None },
+            },
+            c => { expansion.push(c); None },
+        };
+
+        if let Some(matched) = group.and_then(|group| captures.get(group as usize)) {
+            for c in matched.as_str().chars() {
+                expansion.push(c);
+            }
+        }
+    }
+
+    expansion.text
+}
@@ -0,0 +1,207 @@
+extern crate ncurses;
+
+use self::ncurses as nc;
+
+use std::char;
+use std::env;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use backend::{Attributes, Backend};
+use input::{self, Key, KeyCode, Modifiers};
+
+/// Milliseconds to wait for the rest of an escape sequence.
+const ESCAPE_DELAY: i32 = 25;
+
+/// Backend drawing on the terminal through ncurses. The terminal is set up
+/// when it's created and restored when it's dropped.
+pub struct Terminal;
+
+impl Terminal {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Terminal {
+        env::set_var("ESCDELAY", "20");
+        nc::setlocale(nc::LcCategory::all, "");
+        nc::initscr();
+        check(nc::keypad(nc::stdscr(), true));
+        check(nc::noecho());
+        check(nc::raw());
+        check(nc::nonl());
+
+        Terminal
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        finish();
+    }
+}
+
+impl Backend for Terminal {
+    fn size(&self) -> (usize, usize) {
+        (nc::getmaxy(nc::stdscr()) as usize, nc::getmaxx(nc::stdscr()) as usize)
+    }
+
+    fn clear(&mut self) {
+        check(nc::erase());
+    }
+
+    fn put(&mut self, y: usize, x: usize, grapheme: &str, attributes: Attributes) {
+        let mut attr = nc::A_NORMAL();
+        if attributes.reverse {
+            attr |= nc::A_REVERSE();
+        }
+        if attributes.bold {
+            attr |= nc::A_BOLD();
+        }
+
+        nc::attrset(attr);
+        nc::mvaddstr(y as i32, x as i32, grapheme);
+        nc::attrset(nc::A_NORMAL());
+    }
+
+    fn move_cursor(&mut self, y: usize, x: usize) {
+        nc::mv(y as i32, x as i32);
+    }
+
+    fn write_escape_sequence(&mut self, sequence: &str) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(sequence.as_bytes()).and_then(|_| stdout.flush());
+    }
+
+    fn flush(&mut self) {
+        nc::refresh();
+    }
+
+    fn read_key(&mut self, timeout: Option<Duration>) -> Option<Key> {
+        if let Some(timeout) = timeout {
+            let millis = timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000;
+            nc::timeout(millis as i32);
+        }
+
+        let result = nc::wget_wch(nc::stdscr());
+        nc::timeout(-1);
+
+        match result {
+            Some(nc::WchResult::Char(27)) => Some(read_escape_sequence()),
+            Some(nc::WchResult::Char(ic)) => {
+                match char::from_u32(ic) {
+                    Some(c) => Some(input::decode_char(c)),
+                    None => Some(Key::from(KeyCode::Unknown)),
+                }
+            },
+            Some(nc::WchResult::KeyCode(code)) => Some(decode_keycode(code)),
+            None => None,
+        }
+    }
+}
+
+/// Reads what follows an ESC. Terminals send escape sequences all at once,
+/// so the sequence ends when nothing more arrives within a short delay.
+fn read_escape_sequence() -> Key {
+    let mut sequence = String::new();
+
+    nc::timeout(ESCAPE_DELAY);
+
+    while let Some(nc::WchResult::Char(ic)) = nc::wget_wch(nc::stdscr()) {
+        let c = match char::from_u32(ic) {
+            Some(c) => c,
+            None => break,
+        };
+
+        sequence.push(c);
+
+        if sequence_complete(&sequence) {
+            break;
+        }
+    }
+
+    nc::timeout(-1);
+
+    input::decode_escape_sequence(&sequence)
+}
+
+fn sequence_complete(sequence: &str) -> bool {
+    let mut chars = sequence.chars();
+
+    match (chars.next(), chars.as_str()) {
+        (Some('['), rest) => rest.chars().last().is_some_and(|c| ('@'..='~').contains(&c)),
+        (Some('O'), rest) => !rest.is_empty(),
+        _ => true,
+    }
+}
+
+fn decode_keycode(code: i32) -> Key {
+    let key_code = match code {
+        nc::KEY_UP => KeyCode::Up,
+        nc::KEY_DOWN => KeyCode::Down,
+        nc::KEY_LEFT => KeyCode::Left,
+        nc::KEY_RIGHT => KeyCode::Right,
+        nc::KEY_HOME => KeyCode::Home,
+        nc::KEY_END => KeyCode::End,
+        nc::KEY_PPAGE => KeyCode::PageUp,
+        nc::KEY_NPAGE => KeyCode::PageDown,
+        nc::KEY_DC => KeyCode::Delete,
+        nc::KEY_IC => KeyCode::Insert,
+        nc::KEY_BACKSPACE => KeyCode::Backspace,
+        nc::KEY_ENTER => KeyCode::Enter,
+        nc::KEY_BTAB => return shifted(KeyCode::Tab),
+        nc::KEY_SLEFT => return shifted(KeyCode::Left),
+        nc::KEY_SRIGHT => return shifted(KeyCode::Right),
+        nc::KEY_SHOME => return shifted(KeyCode::Home),
+        nc::KEY_SEND => return shifted(KeyCode::End),
+        nc::KEY_SDC => return shifted(KeyCode::Delete),
+        code if code > nc::KEY_F0 && code < nc::KEY_F0 + 64 => KeyCode::F((code - nc::KEY_F0) as u8),
+        code => return decode_extended_keycode(code),
+    };
+
+    Key::from(key_code)
+}
+
+fn shifted(code: KeyCode) -> Key {
+    Key::new(code, Modifiers { shift: true, ..Modifiers::default() })
+}
+
+/// Keys with modifiers don't have fixed codes, terminfo names them like
+/// `kUP5`, with the xterm modifier parameter at the end.
+fn decode_extended_keycode(code: i32) -> Key {
+    let name = match nc::keyname(code) {
+        Some(name) => name,
+        None => return Key::from(KeyCode::Unknown),
+    };
+
+    let split = name.find(|c: char| c.is_ascii_digit()).unwrap_or(name.len());
+    let (base, param) = name.split_at(split);
+
+    let key_code = match base {
+        "kUP" => KeyCode::Up,
+        "kDN" => KeyCode::Down,
+        "kLFT" => KeyCode::Left,
+        "kRIT" => KeyCode::Right,
+        "kHOM" => KeyCode::Home,
+        "kEND" => KeyCode::End,
+        "kPRV" => KeyCode::PageUp,
+        "kNXT" => KeyCode::PageDown,
+        "kDC" => KeyCode::Delete,
+        "kIC" => KeyCode::Insert,
+        _ => KeyCode::Unknown,
+    };
+
+    let modifiers = param.parse().map(input::decode_modifiers).unwrap_or_default();
+
+    Key::new(key_code, modifiers)
+}
+
+/// Restores the terminal. Safe to call more than once, so it can also be
+/// used from signal handlers.
+pub fn finish() {
+    nc::endwin();
+}
+
+fn check(result: i32) {
+    if result == nc::ERR {
+        finish();
+        panic!("Ncurses error");
+    }
+}
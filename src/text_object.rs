@@ -0,0 +1,429 @@
+use std::cmp::min;
+
+use movement::{Class, Position, Text};
+use operator::Range;
+
+/// Text around the cursor an operator can work on, selected with `i`
+/// (inner, without the surrounding white space or delimiters) or `a`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextObject {
+    Word { big: bool, inner: bool },
+    Sentence { inner: bool },
+    Paragraph { inner: bool },
+    Quote { quote: char, inner: bool },
+    Block { open: char, close: char, inner: bool },
+    Tag { inner: bool },
+}
+
+/// The range `object` covers around `position`, or `None` when there is no
+/// such object there.
+pub fn range(text: &mut Text, position: Position, object: TextObject, count: Option<usize>) -> Option<Range> {
+    let count = count.unwrap_or(1);
+
+    match object {
+        TextObject::Word { big, inner } => word(text, position, big, inner, count),
+        TextObject::Sentence { inner } => sentence(text, position, inner, count),
+        TextObject::Paragraph { inner } => paragraph(text, position, inner, count),
+        TextObject::Quote { quote, inner } => quoted(text, position, quote, inner),
+        TextObject::Block { open, close, inner } => block(text, position, open, close, inner, count),
+        TextObject::Tag { inner } => tag(text, position, inner, count),
+    }
+}
+
+fn charwise(start: Position, end: Position) -> Range {
+    Range { start: start, end: end, linewise: false }
+}
+
+fn is_blank(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Runs of graphemes of the same class in `line`, as (start, end) indexes.
+fn runs(text: &mut Text, line: usize, big: bool) -> Vec<(usize, usize, Class)> {
+    let len = text.len(line);
+    let mut runs: Vec<(usize, usize, Class)> = Vec::new();
+
+    for index in 0..len {
+        let class = text.class(Position::new(line, index), big);
+
+        match runs.last_mut() {
+            Some(run) if run.2 == class => run.1 = index + 1,
+            _ => runs.push((index, index + 1, class)),
+        }
+    }
+
+    runs
+}
+
+/// `iw` selects words and the blanks between them alike, each counting
+/// as one. `aw` takes a word with the blanks after it, or before it when
+/// there are none after.
+fn word(text: &mut Text, position: Position, big: bool, inner: bool, count: usize) -> Option<Range> {
+    let line = position.line;
+    let runs = runs(text, line, big);
+    let current = runs.iter().position(|&(start, end, _)| start <= position.index && position.index < end)?;
+
+    let last = if inner {
+        min(current + count - 1, runs.len() - 1)
+    } else {
+        let mut last = current;
+        for i in 0..count {
+            let first = if i == 0 { current } else { last + 1 };
+            if first >= runs.len() {
+                break;
+            }
+
+            // a blank and the word after it, or a word and the blank after it
+            last = if runs[first].2 == Class::Blank || first + 1 >= runs.len() || runs[first + 1].2 == Class::Blank {
+                min(first + 1, runs.len() - 1)
+            } else {
+                first
+            };
+        }
+        last
+    };
+
+    let mut start = runs[current].0;
+    let end = runs[last].1;
+
+    let trailing_blank = runs[last].2 == Class::Blank;
+    if !inner && !trailing_blank && runs[current].2 != Class::Blank && current > 0 && runs[current - 1].2 == Class::Blank {
+        start = runs[current - 1].0;
+    }
+
+    Some(charwise(Position::new(line, start), Position::new(line, end)))
+}
+
+/// The graphemes of lines `first` to `last` with their positions, with the
+/// line breaks between them as `"\n"`.
+fn flatten(text: &mut Text, first: usize, last: usize) -> Vec<(Position, String)> {
+    let mut graphemes = Vec::new();
+
+    for line in first..(last + 1) {
+        let len = text.len(line);
+
+        for (index, grapheme) in text.graphemes(line).iter().enumerate() {
+            graphemes.push((Position::new(line, index), grapheme.clone()));
+        }
+
+        if line < last {
+            graphemes.push((Position::new(line, len), "\n".to_owned()));
+        }
+    }
+
+    graphemes
+}
+
+/// Position of the flattened grapheme `index`, or the end of the last line
+/// past them.
+fn flat_position(text: &mut Text, graphemes: &[(Position, String)], index: usize, last: usize) -> Position {
+    match graphemes.get(index) {
+        Some(&(position, _)) => position,
+        None => Position::new(last, text.len(last)),
+    }
+}
+
+fn is_blank_line(text: &mut Text, line: usize) -> bool {
+    text.graphemes(line).iter().all(|g| is_blank(g))
+}
+
+/// Sentences end with `.`, `!` or `?`, maybe followed by closing quotes or
+/// brackets, and then a blank or the end of the paragraph. `is` selects a
+/// sentence or the blanks after it, `as` a sentence with its blanks.
+fn sentence(text: &mut Text, position: Position, inner: bool, count: usize) -> Option<Range> {
+    if is_blank_line(text, position.line) {
+        return None;
+    }
+
+    let mut first = position.line;
+    while first > 0 && !is_blank_line(text, first - 1) {
+        first -= 1;
+    }
+    let mut last = position.line;
+    while last < text.last_line() && !is_blank_line(text, last + 1) {
+        last += 1;
+    }
+
+    let graphemes = flatten(text, first, last);
+    let blank = graphemes.iter().map(|(_, g)| is_blank(g)).collect::<Vec<_>>();
+
+    // alternating spans of sentences and of the blanks between them
+    let mut spans: Vec<(usize, usize, bool)> = Vec::new();
+    let mut i = 0;
+    while i < graphemes.len() {
+        let start = i;
+
+        if blank[i] {
+            while i < graphemes.len() && blank[i] {
+                i += 1;
+            }
+            spans.push((start, i, true));
+            continue;
+        }
+
+        while i < graphemes.len() {
+            let ends = ".!?".contains(&graphemes[i].1[..]);
+            i += 1;
+
+            if ends {
+                while i < graphemes.len() && ")]\"'".contains(&graphemes[i].1[..]) {
+                    i += 1;
+                }
+                if i == graphemes.len() || blank[i] {
+                    break;
+                }
+            }
+        }
+        spans.push((start, i, false));
+    }
+
+    let cursor = graphemes.iter().position(|&(p, _)| p == position).unwrap_or(graphemes.len().saturating_sub(1));
+    let current = spans.iter().position(|&(start, end, _)| start <= cursor && cursor < end)?;
+
+    // each count is one span inside, and a sentence with the blanks after
+    // it, or blanks with the sentence after them, around
+    let spans_taken = if inner { count } else { count.saturating_mul(2) };
+    let last_span = min(current.saturating_add(spans_taken - 1), spans.len() - 1);
+
+    let mut start = spans[current].0;
+    let end = spans[last_span].1;
+
+    // without blanks after it, a sentence takes the ones before it
+    if !inner && !spans[current].2 && !spans[last_span].2 && current > 0 {
+        start = spans[current - 1].0;
+    }
+
+    let start = flat_position(text, &graphemes, start, last);
+    let end = flat_position(text, &graphemes, end, last);
+
+    Some(charwise(start, end))
+}
+
+/// `ip` selects the lines of a paragraph, or a run of blank lines. `ap`
+/// adds the blank lines after a paragraph, or the paragraph after blank
+/// lines.
+fn paragraph(text: &mut Text, position: Position, inner: bool, count: usize) -> Option<Range> {
+    let last_line = text.last_line();
+
+    let run_end = |text: &mut Text, start: usize| {
+        let blank = is_blank_line(text, start);
+        let mut end = start;
+        while end < last_line && is_blank_line(text, end + 1) == blank {
+            end += 1;
+        }
+        end
+    };
+
+    let mut first = position.line;
+    let blank = is_blank_line(text, first);
+    while first > 0 && is_blank_line(text, first - 1) == blank {
+        first -= 1;
+    }
+
+    let runs = if inner { count } else { count * 2 };
+    let mut last = run_end(text, first);
+    let mut taken = 1;
+    while taken < runs && last < last_line {
+        last = run_end(text, last + 1);
+        taken += 1;
+    }
+
+    // a paragraph at the end of the buffer takes the blank lines before it
+    if !inner && taken < runs && !blank {
+        while first > 0 && is_blank_line(text, first - 1) {
+            first -= 1;
+        }
+    }
+
+    Some(Range::lines(first, last))
+}
+
+/// Quoted text in the current line. On a quote, quotes are paired up from
+/// the start of the line to tell whether it opens or closes the text.
+/// `a"` also takes the blanks after the closing quote, or before the
+/// opening one when there are none after.
+fn quoted(text: &mut Text, position: Position, quote: char, inner: bool) -> Option<Range> {
+    let line = position.line;
+    let quote = quote.to_string();
+
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    for (index, grapheme) in text.graphemes(line).iter().enumerate() {
+        if *grapheme == quote && !escaped {
+            quotes.push(index);
+        }
+        escaped = grapheme == "\\" && !escaped;
+    }
+
+    let (open, close) = match quotes.iter().position(|&index| index == position.index) {
+        Some(i) if i % 2 == 0 => (quotes[i], *quotes.get(i + 1)?),
+        Some(i) => (quotes[i - 1], quotes[i]),
+        None => {
+            match quotes.iter().position(|&index| index > position.index) {
+                Some(0) => (quotes[0], *quotes.get(1)?),
+                Some(i) => (quotes[i - 1], quotes[i]),
+                None => return None,
+            }
+        },
+    };
+
+    if inner {
+        return Some(charwise(Position::new(line, open + 1), Position::new(line, close)));
+    }
+
+    let len = text.len(line);
+    let blank_at = |text: &mut Text, index: usize| text.get(Position::new(line, index)).is_some_and(is_blank);
+
+    let mut start = open;
+    let mut end = close + 1;
+    if end < len && blank_at(text, end) {
+        while end < len && blank_at(text, end) {
+            end += 1;
+        }
+    } else {
+        while start > 0 && blank_at(text, start - 1) {
+            start -= 1;
+        }
+    }
+
+    Some(charwise(Position::new(line, start), Position::new(line, end)))
+}
+
+/// Text between a pair of brackets around the cursor, `count` pairs out.
+/// When the brackets end and start their lines, the inner text is the
+/// lines between them.
+fn block(text: &mut Text, position: Position, open: char, close: char, inner: bool, count: usize) -> Option<Range> {
+    let (open, close) = (open.to_string(), close.to_string());
+
+    let mut levels = count;
+    let mut depth = 0;
+    let mut current = position;
+
+    let open_position = loop {
+        match text.get(current) {
+            Some(g) if g == open && depth == 0 => {
+                levels -= 1;
+                if levels == 0 {
+                    break current;
+                }
+            },
+            Some(g) if g == open => depth -= 1,
+            Some(g) if g == close && current != position => depth += 1,
+            _ => (),
+        }
+
+        current = text.previous(current)?;
+    };
+
+    let mut depth = 0;
+    let mut current = open_position;
+    let close_position = loop {
+        current = text.next(current)?;
+
+        match text.get(current) {
+            Some(g) if g == open => depth += 1,
+            Some(g) if g == close && depth == 0 => break current,
+            Some(g) if g == close => depth -= 1,
+            _ => (),
+        }
+    };
+
+    if !inner {
+        let end = Position::new(close_position.line, close_position.index + 1);
+        return Some(charwise(open_position, end));
+    }
+
+    let opens_line = open_position.index + 1 == text.len(open_position.line);
+    let closes_line = close_position.index <= text.first_non_blank(close_position.line).index;
+
+    if opens_line && closes_line && close_position.line > open_position.line + 1 {
+        return Some(Range::lines(open_position.line + 1, close_position.line - 1));
+    }
+
+    let start = Position::new(open_position.line, open_position.index + 1);
+    Some(charwise(start, close_position))
+}
+
+/// A pair of matching tags, as byte offsets into the flattened text: where
+/// the opening tag starts and ends, and where the closing tag starts and
+/// ends.
+struct TagPair {
+    open: (usize, usize),
+    close: (usize, usize),
+}
+
+/// Opening and closing tags paired up by name. Self-closing tags and tags
+/// left unclosed are skipped.
+fn tag_pairs(flat: &str) -> Vec<TagPair> {
+    let mut pairs = Vec::new();
+    let mut stack: Vec<(String, usize, usize)> = Vec::new();
+    let mut offset = 0;
+
+    while let Some(found) = flat[offset..].find('<') {
+        let start = offset + found;
+        let end = match flat[start..].find('>') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        offset = start + 1;
+
+        let inside = &flat[(start + 1)..(end - 1)];
+        let closing = inside.starts_with('/');
+        let name = inside.trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || "-_:.".contains(c)) {
+            continue;
+        }
+
+        if closing {
+            if let Some(i) = stack.iter().rposition(|(open, _, _)| open == name) {
+                let (_, open_start, open_end) = stack[i].clone();
+                stack.truncate(i);
+                pairs.push(TagPair { open: (open_start, open_end), close: (start, end) });
+            }
+        } else if !inside.ends_with('/') {
+            stack.push((name.to_owned(), start, end));
+        }
+
+        offset = end;
+    }
+
+    pairs
+}
+
+/// `it` selects what's between a pair of tags around the cursor, `at`
+/// the tags too.
+fn tag(text: &mut Text, position: Position, inner: bool, count: usize) -> Option<Range> {
+    let last = text.last_line();
+    let graphemes = flatten(text, 0, last);
+
+    let mut flat = String::new();
+    let mut offsets = Vec::with_capacity(graphemes.len());
+    let mut cursor = 0;
+    for &(grapheme_position, ref grapheme) in &graphemes {
+        if grapheme_position == position {
+            cursor = flat.len();
+        }
+        offsets.push(flat.len());
+        flat.push_str(grapheme);
+    }
+
+    let mut around = tag_pairs(&flat).into_iter()
+        .filter(|pair| pair.open.0 <= cursor && cursor < pair.close.1)
+        .collect::<Vec<_>>();
+    around.sort_by_key(|pair| pair.close.1 - pair.open.0);
+
+    let pair = around.get(count - 1)?;
+    let (start, end) = if inner { (pair.open.1, pair.close.0) } else { (pair.open.0, pair.close.1) };
+
+    let index = |offset: usize| offsets.binary_search(&offset).unwrap_or_else(|i| i);
+    let (start, end) = (index(start), index(end));
+
+    let start = flat_position(text, &graphemes, start, last);
+    let end = flat_position(text, &graphemes, end, last);
+
+    Some(charwise(start, end))
+}
@@ -1,58 +1,117 @@
-extern crate ncurses;
-
-use self::ncurses as nc;
-
-use std::env;
+use std::ptr;
 
+use backend::{Attributes, Backend};
 use editor::Editor;
+use encoding;
+use movement::Position;
+use search::Match;
+use selection::Selection;
 use unicode;
+use window::Window;
 
-pub fn init() {
-    env::set_var("ESCDELAY", "20");
-    nc::setlocale(nc::LcCategory::all, "");
-    nc::initscr();
-    check(nc::keypad(nc::stdscr(), true));
-    check(nc::noecho());
-    check(nc::raw());
-    check(nc::nonl());
-}
+const PENDING_KEYS_WIDTH: usize = 10;
 
-pub fn getmaxy() -> usize {
-    nc::getmaxy(nc::stdscr()) as usize
+/// Text drawn with other attributes, from the grapheme at `start` up to, but
+/// not including, the one at `end`.
+struct Span {
+    start: Position,
+    end: Position,
+    attributes: Attributes,
 }
 
-pub fn getmaxx() -> usize {
-    nc::getmaxx(nc::stdscr()) as usize
-}
+impl Span {
+    fn new(matched: Match, attributes: Attributes) -> Span {
+        Span {
+            start: matched.start,
+            end: matched.end,
+            attributes: attributes,
+        }
+    }
+
+    fn contains(&self, position: Position) -> bool {
+        self.start <= position && position < self.end
+    }
 
-pub fn finish() {
-    nc::endwin();
+    fn covers_line(&self, line: usize) -> bool {
+        self.start.line <= line && line <= self.end.line
+    }
 }
 
-pub fn render(ed: &Editor) {
-    erase();
+pub fn render<B: Backend>(ed: &Editor, backend: &mut B) {
+    backend.clear();
+
+    let windows = ed.windows();
+    for window in &windows {
+        let current = ptr::eq(*window, ed.window());
+        let selection = if current { ed.selection() } else { None };
+
+        // the match of a search being typed, or the one a substitution asks
+        // about, stands out from the others
+        let mut spans = ed.highlighted_matches(window).into_iter()
+            .map(|matched| Span::new(matched, Attributes::reverse()))
+            .collect::<Vec<_>>();
+        if let (true, Some(matched)) = (current, ed.current_match()) {
+            spans.push(Span::new(matched, Attributes { reverse: true, bold: true }));
+        }
+
+        render_window(window, selection.as_ref(), &spans, backend);
+
+        // with a single window the status line is left to the prompt
+        if windows.len() > 1 {
+            render_status_line(window, current, backend);
+            render_separator(window, backend);
+        }
+    }
 
-    render_prompt(ed);
-    render_window(ed);
+    render_prompt(ed, backend);
+    render_pending_keys(ed, backend);
 
     let (cur_y, cur_x) = ed.cursor();
 
-    render_cursor(cur_y, cur_x);
+    backend.move_cursor(cur_y, cur_x);
+    backend.flush();
+}
+
+fn render_prompt<B: Backend>(ed: &Editor, backend: &mut B) {
+    // like Vim, recording shows while there's nothing else to show
+    if let (Some(name), "") = (ed.recording(), ed.prompt().text()) {
+        render_text(&format!("recording @{}", name), ed.prompt().pos(), 0, Attributes::normal(), backend);
+        return;
+    }
+
+    let lines = ed.prompt().text().split('\n').collect::<Vec<_>>();
+    let top = (ed.prompt().pos() + 1).saturating_sub(lines.len());
 
-    nc::refresh();
+    for (i, line) in lines.iter().enumerate() {
+        if lines.len() > 1 {
+            clear_line(top + i, backend);
+        }
+
+        render_text(line, top + i, 0, Attributes::normal(), backend);
+    }
 }
 
-fn render_cursor(y: usize, x: usize) {
-    nc::mv(y as i32, x as i32);
+/// Shows the keys of an unfinished mapping at the right of the prompt line,
+/// where Vim shows them with `showcmd`.
+fn render_pending_keys<B: Backend>(ed: &Editor, backend: &mut B) {
+    let (height, width) = backend.size();
+    let x = width.saturating_sub(PENDING_KEYS_WIDTH + 1);
+
+    render_text_clipped(&ed.pending_keys(), height - 1, x, PENDING_KEYS_WIDTH, Attributes::normal(), backend);
 }
 
-fn render_prompt(ed: &Editor) {
-    render_text(ed.prompt().text(), ed.prompt().pos(), 0);
+fn clear_line<B: Backend>(y: usize, backend: &mut B) {
+    let (_, width) = backend.size();
+
+    for x in 0..width {
+        backend.put(y, x, " ", Attributes::normal());
+    }
 }
 
-fn render_window(ed: &Editor) {
-    let window = ed.window();
+fn render_window<B: Backend>(window: &Window, selection: Option<&Selection>, spans: &[Span], backend: &mut B) {
     let (height, width) = window.size();
+    let (top, left) = window.position();
+    let top_line = window.top_line();
     let content_view = window.content_view();
 
     let mut row = 0;
@@ -66,63 +125,125 @@ fn render_window(ed: &Editor) {
         }
 
         let line = content_view.line(row);
+        let spans = spans.iter().filter(|span| span.covers_line(top_line + row)).collect::<Vec<_>>();
 
-        if unicode::width(line) <= width {
-            render_text(line, row, 0);
+        if selection.is_some() || !spans.is_empty() {
+            render_line(&line, top_line + row, selection, &spans, (top + row, left), width, backend);
+        } else if unicode::width(&line) <= width {
+            render_text(&line, top + row, left, Attributes::normal(), backend);
         } else {
-            render_text_clipped(line, row, 0, width);
+            render_text_clipped(&line, top + row, left, width, Attributes::normal(), backend);
         }
 
         row += 1;
     }
 
     while row < height {
-        mv(row, 0);
-        addstr("~");
+        backend.put(top + row, left, "~", Attributes::normal());
         row += 1;
     }
 }
 
-fn render_text(text: &str, y: usize, x: usize) {
-    let mut column = x;
-    for (_, grapheme) in unicode::graphemes(text) {
-        column += render_grapheme(grapheme, y, column);
+/// Draws buffer line `line` in reverse video where it's selected, and with
+/// the attributes of the spans over the rest, the last span winning where
+/// they overlap. A selected empty line shows as a single reversed cell, and
+/// a span over a line break as a cell past the end of the line.
+fn render_line<B: Backend>(text: &str, line: usize, selection: Option<&Selection>, spans: &[&Span], (y, x): (usize, usize), width: usize, backend: &mut B) {
+    let attributes = |index: usize, column: usize, grapheme_width: usize| {
+        match selection {
+            Some(selection) if selection.contains(line, column, grapheme_width) => Attributes::reverse(),
+            _ => spans.iter().rev()
+                .find(|span| span.contains(Position::new(line, index)))
+                .map_or(Attributes::normal(), |span| span.attributes),
+        }
+    };
+
+    if text.is_empty() {
+        let attributes = attributes(0, 0, 1);
+        if attributes != Attributes::normal() {
+            backend.put(y, x, " ", attributes);
+        }
+        return;
     }
-}
 
-fn render_text_clipped(text: &str, y: usize, x: usize, width: usize) {
-    let mut column = x;
+    let mut column = 0;
+    let mut index = 0;
     for (_, grapheme) in unicode::graphemes(text) {
-        column += render_grapheme(grapheme, y, column);
+        let grapheme_width = unicode::width(grapheme);
+        if column + grapheme_width > width {
+            return;
+        }
 
-        if column >= width {
-            break;
+        column += render_grapheme(grapheme, y, x + column, attributes(index, column, grapheme_width), backend);
+        index += 1;
+    }
+
+    let line_break = Position::new(line, index);
+    if let Some(span) = spans.iter().rev().find(|span| span.contains(line_break)) {
+        if column < width {
+            backend.put(y, x + column, " ", span.attributes);
         }
     }
 }
 
-fn render_grapheme(grapheme: &str, y: usize, x: usize) -> usize {
-    mv(y, x);
-    addstr(grapheme);
+fn render_status_line<B: Backend>(window: &Window, current: bool, backend: &mut B) {
+    let (height, width) = window.size();
+    let (top, left) = window.position();
 
-    unicode::width(grapheme)
+    let mut status = format!(" {}", window.filename().unwrap_or("[No Name]".to_owned()));
+    if window.is_modified() {
+        status.push_str(" [+]");
+    }
+
+    let padding = width.saturating_sub(unicode::width(&status));
+    status.extend((0..padding).map(|_| ' '));
+
+    let attributes = Attributes { reverse: true, bold: current };
+    render_text_clipped(&status, top + height, left, width, attributes, backend);
 }
 
-fn addstr(s: &str) {
-    nc::addstr(s);
+/// Draws the bar between `window` and the window to its right, if any.
+fn render_separator<B: Backend>(window: &Window, backend: &mut B) {
+    let (height, width) = window.size();
+    let (top, left) = window.position();
+
+    if left + width >= backend.size().1 {
+        return;
+    }
+
+    for row in top..(top + height + 1) {
+        backend.put(row, left + width, "|", Attributes::reverse());
+    }
 }
 
-fn mv(y: usize, x: usize) {
-    check(nc::mv(y as i32, x as i32));
+fn render_text<B: Backend>(text: &str, y: usize, x: usize, attributes: Attributes, backend: &mut B) {
+    let mut column = x;
+    for (_, grapheme) in unicode::graphemes(text) {
+        column += render_grapheme(grapheme, y, column, attributes, backend);
+    }
 }
 
-fn erase() {
-    check(nc::erase());
+fn render_text_clipped<B: Backend>(text: &str, y: usize, x: usize, width: usize, attributes: Attributes, backend: &mut B) {
+    let mut column = x;
+    for (_, grapheme) in unicode::graphemes(text) {
+        // a wide char that doesn't fit would spill into the next window
+        if column + unicode::width(grapheme) > x + width {
+            break;
+        }
+
+        column += render_grapheme(grapheme, y, column, attributes, backend);
+    }
 }
 
-fn check(result: i32) {
-    if result == nc::ERR {
-        finish();
-        panic!("Ncurses error");
+fn render_grapheme<B: Backend>(grapheme: &str, y: usize, x: usize, attributes: Attributes, backend: &mut B) -> usize {
+    match grapheme.chars().next().and_then(encoding::placeholder_byte) {
+        Some(byte) => {
+            for (i, c) in format!("<{:02x}>", byte).chars().enumerate() {
+                backend.put(y, x + i, c.encode_utf8(&mut [0; 4]), attributes);
+            }
+        },
+        None => backend.put(y, x, grapheme, attributes),
     }
+
+    unicode::width(grapheme)
 }
@@ -4,14 +4,27 @@ extern crate unicode_segmentation;
 use self::unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use self::unicode_segmentation::{UnicodeSegmentation, GraphemeIndices};
 
+use encoding;
+
+/// Undecodable bytes are displayed as `<xx>` escapes.
+const PLACEHOLDER_WIDTH: usize = 4;
+
 pub fn width(text: &str) -> usize {
-    UnicodeWidthStr::width(text)
+    if text.chars().any(encoding::is_placeholder) {
+        text.chars().map(width_char).sum()
+    } else {
+        UnicodeWidthStr::width(text)
+    }
 }
 
 pub fn width_char(c: char) -> usize {
-    UnicodeWidthChar::width(c).unwrap_or(0)
+    if encoding::is_placeholder(c) {
+        PLACEHOLDER_WIDTH
+    } else {
+        UnicodeWidthChar::width(c).unwrap_or(0)
+    }
 }
 
-pub fn graphemes(text: &str) -> GraphemeIndices {
+pub fn graphemes(text: &str) -> GraphemeIndices<'_> {
     UnicodeSegmentation::grapheme_indices(text, true)
 }
@@ -1,22 +1,50 @@
+use std::borrow::Cow;
+use std::cell::{Ref, RefCell, RefMut};
 use std::char;
-use std::cmp::min;
-
-use movement::Movement;
-use error::Result;
+use std::cmp::{max, min};
+use std::rc::Rc;
+
+use movement::{self, Movement, Position, Text};
+use operator::{self, Range};
+use text_object::{self, TextObject};
+use selection::{Block, Selection, SelectionKind};
+use registers::Register;
+use search::{self, Found, Match, Search};
+use substitute::{self, Replacement, Substitution};
+use pattern::Pattern;
+use error::{Error, Result};
 use buffer::Buffer;
+use file::Written;
 use unicode;
 
+/// Scroll and cursor position of a window, kept for each buffer so switching
+/// back to it restores where the user was.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct View {
+    scroll_pos: usize,
+    cur_y: usize,
+    cur_x: usize,
+}
+
+impl View {
+    pub fn line(&self) -> usize {
+        self.scroll_pos + self.cur_y
+    }
+}
+
 pub struct Window {
-    buffer: Buffer,
+    buffer: Rc<RefCell<Buffer>>,
     scroll_pos: usize,
     cur_y: usize,
     cur_x: usize,
+    pos_y: usize,
+    pos_x: usize,
     height: usize,
     width: usize,
 }
 
 pub struct ContentView<'a> {
-    buffer: &'a Buffer,
+    buffer: Ref<'a, Buffer>,
     height: usize,
     vertical_offset: usize,
 }
@@ -26,18 +54,20 @@ impl<'a> ContentView<'a> {
         self.height
     }
 
-    pub fn line(&self, i: usize) -> &str {
+    pub fn line(&self, i: usize) -> Cow<'_, str> {
         self.buffer.line(i + self.vertical_offset)
     }
 }
 
 impl Window {
-    pub fn new(height: usize, width: usize, buffer: Buffer) -> Window {
+    pub fn new(height: usize, width: usize, buffer: Rc<RefCell<Buffer>>) -> Window {
         Window {
             buffer: buffer,
             scroll_pos: 0,
             cur_y: 0,
             cur_x: 0,
+            pos_y: 0,
+            pos_x: 0,
             height: height,
             width: width,
         }
@@ -47,28 +77,92 @@ impl Window {
         (self.height, self.width)
     }
 
-    pub fn filename(&self) -> Option<&str> {
-        self.buffer.filename()
+    /// Screen position of the top left corner of the window.
+    pub fn position(&self) -> (usize, usize) {
+        (self.pos_y, self.pos_x)
+    }
+
+    /// Moves the window to a new area of the screen, scrolling if needed to
+    /// keep the cursor visible.
+    pub fn set_area(&mut self, y: usize, x: usize, height: usize, width: usize) {
+        self.pos_y = y;
+        self.pos_x = x;
+        self.height = height;
+        self.width = width;
+
+        self.fit();
+    }
+
+    /// Brings the cursor back inside the buffer and the window, which is
+    /// needed after the buffer was changed through another window.
+    pub fn fit(&mut self) {
+        let line = self.line();
+        let x = min(self.cur_x, self.width.saturating_sub(1));
+        self.goto(line, x);
+    }
+
+    pub fn buffer(&self) -> Ref<'_, Buffer> {
+        self.buffer.borrow()
+    }
+
+    pub fn buffer_mut(&self) -> RefMut<'_, Buffer> {
+        self.buffer.borrow_mut()
+    }
+
+    pub fn buffer_handle(&self) -> Rc<RefCell<Buffer>> {
+        self.buffer.clone()
+    }
+
+    pub fn shows(&self, buffer: &Rc<RefCell<Buffer>>) -> bool {
+        Rc::ptr_eq(&self.buffer, buffer)
+    }
+
+    pub fn filename(&self) -> Option<String> {
+        self.buffer().filename().map(|s| s.to_owned())
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.buffer().is_modified()
+    }
+
+    pub fn view(&self) -> View {
+        View {
+            scroll_pos: self.scroll_pos,
+            cur_y: self.cur_y,
+            cur_x: self.cur_x,
+        }
     }
 
     pub fn cursor(&self) -> (usize, usize) {
         (self.cur_y, self.cur_x)
     }
 
+    /// The first buffer line shown.
+    pub fn top_line(&self) -> usize {
+        self.scroll_pos
+    }
+
     pub fn set_cur_x(&mut self, x: usize) {
         self.cur_x = x;
     }
 
-    pub fn content_view(&self) -> ContentView {
+    pub fn content_view(&self) -> ContentView<'_> {
+        let buffer = self.buffer();
+        let height = min(self.height, buffer.len().saturating_sub(self.scroll_pos));
+
         ContentView {
-            buffer: &self.buffer,
-            height: min(self.height, self.buffer.len()),
+            buffer: buffer,
+            height: height,
             vertical_offset: self.scroll_pos,
         }
     }
 
+    fn line(&self) -> usize {
+        self.scroll_pos + self.cur_y
+    }
+
     fn scroll_up(&mut self) {
-        let contents_len = self.buffer.len();
+        let contents_len = self.buffer().len();
 
         if self.cur_y + self.scroll_pos < contents_len - 1 {
             self.scroll_pos += 1;
@@ -82,25 +176,609 @@ impl Window {
     }
 
     pub fn ensure_cursor_over_line(&mut self) {
-        let line = self.buffer.line(self.cur_y + self.scroll_pos);
-        let line_width = unicode::width(line);
+        let buffer = self.buffer.borrow();
+        let line = buffer.line(self.cur_y + self.scroll_pos);
+        let line_width = unicode::width(&line);
         if self.cur_x >= line_width {
-            if let Some((_, ref grapheme)) = self.buffer.grapheme_at(self.cur_y + self.scroll_pos, line_width.saturating_sub(1)) {
+            if let Some((_, ref grapheme)) = buffer.grapheme_at(self.cur_y + self.scroll_pos, line_width.saturating_sub(1)) {
                 self.cur_x = line_width - unicode::width(grapheme);
             }
         }
     }
 
     pub fn ensure_cursor_not_in_middle_of_widechar(&mut self) {
-        if let Some((_, ref grapheme)) = self.buffer.grapheme_at(self.cur_y + self.scroll_pos, self.cur_x) {
-            let size = unicode::width(grapheme);
-            if size > 1 {
-                self.cur_x -= size - 1;
+        let buffer = self.buffer.borrow();
+        if let Some((offset, _)) = buffer.grapheme_at(self.cur_y + self.scroll_pos, self.cur_x) {
+            let line = buffer.line(self.cur_y + self.scroll_pos);
+            self.cur_x = unicode::width(&line[..offset]);
+        }
+    }
+
+    /// Moves the cursor `count` times, or to line `count` for the motions
+    /// that go to a line. Fails when it can't go anywhere, like `j` on the
+    /// last line or `f` without a match.
+    pub fn move_cursor(&mut self, movement: Movement, count: Option<usize>) -> Result<()> {
+        match movement {
+            Movement::Left | Movement::Right | Movement::Up | Movement::Down => {
+                let (line, x) = (self.line(), self.cur_x);
+
+                // a huge count stops where the cursor can't go any further
+                for _ in 0..count.unwrap_or(1) {
+                    let before = (self.line(), self.cur_x);
+                    self.step(movement);
+                    if (self.line(), self.cur_x) == before {
+                        break;
+                    }
+                }
+
+                // a count going past the end still moves as far as it can
+                let moved = match movement {
+                    Movement::Left | Movement::Right => self.cur_x != x,
+                    _ => self.line() != line,
+                };
+                if !moved {
+                    return Err(Error::MotionFailed);
+                }
+            },
+            movement => {
+                let target = {
+                    let buffer = self.buffer();
+                    let mut text = Text::new(&buffer);
+                    let position = text.position_at(self.line(), self.cur_x);
+
+                    movement::target(&mut text, position, movement, count)
+                        .map(|target| (target.line, text.column(target)))
+                };
+
+                match target {
+                    Some((line, x)) => self.goto(line, x),
+                    None => return Err(Error::MotionFailed),
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Text covered by `movement` from the cursor, for an operator.
+    pub fn motion_range(&self, movement: Movement, count: Option<usize>) -> Option<Range> {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+        let position = text.position_at(self.line(), self.cur_x);
+
+        operator::range(&mut text, position, movement, count)
+    }
+
+    /// The `count`th match of `search` from the cursor.
+    pub fn search(&self, search: &Search, count: Option<usize>) -> Result<Option<Found>> {
+        let buffer = self.buffer();
+        let position = Text::new(&buffer).position_at(self.line(), self.cur_x);
+
+        search::find(&buffer, position, search, count.unwrap_or(1))
+    }
+
+    /// The line of the next match of `search` after the cursor line, or of
+    /// the previous one before it.
+    pub fn search_line(&self, search: &Search) -> Result<Option<usize>> {
+        search::find_line(&self.buffer(), self.line(), search)
+    }
+
+    /// Matches of `pattern` in the lines shown.
+    pub fn matches(&self, pattern: &Pattern) -> Vec<Match> {
+        let buffer = self.buffer();
+        let end = min(self.scroll_pos + self.height, buffer.len());
+
+        search::matches(&buffer, pattern, self.scroll_pos, end)
+    }
+
+    /// What `substitution` replaces in the lines from `first` to `last`.
+    pub fn replacements(&self, substitution: &Substitution, first: usize, last: usize) -> Result<Vec<Replacement>> {
+        substitute::replacements(&self.buffer(), substitution, first, last)
+    }
+
+    /// Makes the replacements in a single edit, leaving the cursor on the
+    /// line of the last one.
+    pub fn substitute(&mut self, replacements: &[Replacement]) -> Result<()> {
+        let line = substitute::apply(&mut self.buffer_mut(), replacements)?;
+        self.goto_first_non_blank(line);
+
+        Ok(())
+    }
+
+    /// Text covered by `object` around the cursor.
+    pub fn object_range(&self, object: TextObject, count: Option<usize>) -> Option<Range> {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+        let position = text.position_at(self.line(), self.cur_x);
+
+        text_object::range(&mut text, position, object, count)
+    }
+
+    /// The selection from `anchor`, a line and display column, to the
+    /// cursor.
+    pub fn selection(&self, kind: SelectionKind, anchor: (usize, usize)) -> Selection {
+        let buffer = self.buffer();
+        let anchor = (min(anchor.0, buffer.len() - 1), anchor.1);
+        let cursor = (self.line(), self.cur_x);
+
+        let (start, end) = if anchor <= cursor { (anchor, cursor) } else { (cursor, anchor) };
+
+        // a block reaches to the right edge of the wider of the graphemes
+        // at its corners
+        let width_at = |(line, x): (usize, usize)| {
+            buffer.grapheme_at(line, x).map(|(_, g)| unicode::width(&g)).unwrap_or(1)
+        };
+
+        Selection {
+            kind: kind,
+            start: start,
+            end: end,
+            left: min(anchor.1, cursor.1),
+            right: max(anchor.1 + width_at(anchor), cursor.1 + width_at(cursor)),
+        }
+    }
+
+    /// Text an operator works on for `selection`.
+    pub fn selection_range(&self, selection: &Selection) -> Range {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+
+        selection.range(&mut text)
+    }
+
+    /// Puts the cursor on the last grapheme of `range`, returning the line
+    /// and display column where it starts, to select it.
+    pub fn select(&mut self, range: Range) -> (usize, usize) {
+        let (anchor, last) = {
+            let buffer = self.buffer();
+            let mut text = Text::new(&buffer);
+
+            if range.linewise {
+                ((range.start.line, 0), (range.end.line, self.cur_x))
+            } else {
+                let last = text.previous(range.end).unwrap_or(range.start);
+                let last = if last < range.start { range.start } else { last };
+
+                ((range.start.line, text.column(range.start)), (last.line, text.column(last)))
+            }
+        };
+
+        self.goto(last.0, last.1);
+
+        anchor
+    }
+
+    /// Text covered by `cw` or `cW` from the cursor.
+    pub fn change_word_range(&self, big: bool, count: Option<usize>) -> Option<Range> {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+        let position = text.position_at(self.line(), self.cur_x);
+
+        operator::change_word_range(&mut text, position, big, count)
+    }
+
+    /// `count` lines from the cursor line, for the doubled operators.
+    pub fn line_range(&self, count: Option<usize>) -> Range {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+        let position = text.position_at(self.line(), self.cur_x);
+
+        operator::line_range(&mut text, position, count)
+    }
+
+    /// The text in `range`. Linewise text ends with a line break.
+    pub fn yank(&mut self, range: Range) -> String {
+        let (start, end) = self.range_points(range);
+        let mut yanked = self.buffer().text_range(start, end);
+
+        if range.linewise {
+            yanked.push('\n');
+
+            if range.start.line < self.line() {
+                let x = self.cur_x;
+                self.goto(range.start.line, x);
             }
+        } else {
+            self.goto_position(range.start);
         }
+
+        yanked
     }
 
-    pub fn move_cursor(&mut self, movement: Movement) -> Result<()> {
+    /// Removes the text in `range`, returning it like `yank` does.
+    pub fn delete(&mut self, range: Range) -> Result<String> {
+        if !range.linewise {
+            let (start, end) = self.range_points(range);
+            let deleted = self.buffer_mut().delete_range(start, end)?;
+            self.goto_position(range.start);
+
+            return Ok(deleted);
+        }
+
+        let (first, last) = (range.start.line, range.end.line);
+        let (start, end) = {
+            let buffer = self.buffer();
+            let last_line = buffer.len() - 1;
+
+            // the line break before the lines goes when they're at the end
+            if last < last_line {
+                ((first, 0), (last + 1, 0))
+            } else if first > 0 {
+                ((first - 1, buffer.line(first - 1).len()), (last, buffer.line(last).len()))
+            } else {
+                ((0, 0), (last, buffer.line(last).len()))
+            }
+        };
+
+        let deleted = self.yank(range);
+        self.buffer_mut().delete_range(start, end)?;
+
+        self.goto_first_non_blank(first);
+
+        Ok(deleted)
+    }
+
+    /// Removes the text in `range`, leaving the cursor where text should be
+    /// inserted. Linewise, the lines are replaced with a single empty one.
+    pub fn change(&mut self, range: Range) -> Result<String> {
+        let (start, end) = self.range_points(range);
+        let mut deleted = self.buffer_mut().delete_range(start, end)?;
+
+        if range.linewise {
+            deleted.push('\n');
+        }
+
+        let x = self.position_column(range.start);
+        self.goto(range.start.line, x);
+        self.cur_x = x;
+
+        Ok(deleted)
+    }
+
+    /// Replaces the text in `range` with what `convert` makes of it.
+    pub fn convert<F>(&mut self, range: Range, convert: F) -> Result<()>
+        where F: Fn(&str) -> String
+    {
+        let (start, end) = self.range_points(range);
+        let text = self.buffer().text_range(start, end);
+
+        self.buffer_mut().replace_range(start, end, &convert(&text))?;
+        self.goto_position(range.start);
+
+        Ok(())
+    }
+
+    /// Puts `lines` in place of the lines from `first` to `last` in a single
+    /// edit, leaving the cursor on the first of them. No lines at all
+    /// deletes them.
+    pub fn replace_lines(&mut self, first: usize, last: usize, lines: &[String]) -> Result<()> {
+        if lines.is_empty() {
+            self.delete(Range::lines(first, last))?;
+            return Ok(());
+        }
+
+        let end = (last, self.buffer().line(last).len());
+        self.buffer_mut().replace_range((first, 0), end, &lines.join("\n"))?;
+        self.goto_first_non_blank(first);
+
+        Ok(())
+    }
+
+    /// Indents the lines in `range` by `width` columns, or takes up to
+    /// `width` columns of indent away from them. A tab counts as a whole
+    /// `width`.
+    pub fn shift(&mut self, range: Range, width: usize, right: bool) -> Result<()> {
+        for line in range.start.line..(range.end.line + 1) {
+            let text = self.buffer().line(line).into_owned();
+
+            if right {
+                if !text.is_empty() {
+                    let indent = (0..width).map(|_| ' ').collect::<String>();
+                    self.buffer_mut().replace_range((line, 0), (line, 0), &indent)?;
+                }
+            } else {
+                let mut removed = 0;
+                let mut columns = 0;
+
+                for c in text.chars() {
+                    if columns >= width {
+                        break;
+                    }
+
+                    columns += match c {
+                        ' ' => 1,
+                        '\t' => width,
+                        _ => break,
+                    };
+                    removed += c.len_utf8();
+                }
+
+                self.buffer_mut().delete_range((line, 0), (line, removed))?;
+            }
+        }
+
+        self.goto_first_non_blank(range.start.line);
+
+        Ok(())
+    }
+
+    /// The lines of `block`, separated by line breaks.
+    pub fn yank_block(&mut self, block: Block) -> String {
+        let pieces = self.block_points(block).into_iter()
+            .map(|(start, end)| self.buffer().text_range(start, end))
+            .collect::<Vec<_>>();
+
+        self.goto(block.first, block.left);
+
+        pieces.join("\n")
+    }
+
+    pub fn delete_block(&mut self, block: Block) -> Result<String> {
+        let yanked = self.yank_block(block);
+
+        for (start, end) in self.block_points(block) {
+            self.buffer_mut().delete_range(start, end)?;
+        }
+
+        self.goto(block.first, block.left);
+
+        Ok(yanked)
+    }
+
+    /// Removes the text in `block`, leaving the cursor where it started in
+    /// its first line, to insert text there.
+    pub fn change_block(&mut self, block: Block) -> Result<String> {
+        let deleted = self.delete_block(block)?;
+
+        let width = unicode::width(&self.buffer().line(block.first));
+        self.cur_x = min(block.left, width);
+
+        Ok(deleted)
+    }
+
+    pub fn convert_block<F>(&mut self, block: Block, convert: F) -> Result<()>
+        where F: Fn(&str) -> String
+    {
+        for (start, end) in self.block_points(block) {
+            let text = self.buffer().text_range(start, end);
+            self.buffer_mut().replace_range(start, end, &convert(&text))?;
+        }
+
+        self.goto(block.first, block.left);
+
+        Ok(())
+    }
+
+    /// Puts the text of `register` `count` times after the cursor, or
+    /// before it. Lines go below or above the cursor line, and a block goes
+    /// into the lines from the cursor line down.
+    pub fn put(&mut self, register: &Register, after: bool, count: usize) -> Result<()> {
+        match register.kind {
+            SelectionKind::Charwise => self.put_chars(&register.text, after, count),
+            SelectionKind::Linewise => self.put_lines(&register.text, after, count),
+            SelectionKind::Blockwise => self.put_block(&register.text, after, count),
+        }
+    }
+
+    /// Leaves the cursor on the last grapheme put, or on the first one when
+    /// the text spans lines.
+    fn put_chars(&mut self, text: &str, after: bool, count: usize) -> Result<()> {
+        let text = (0..count).map(|_| text).collect::<String>();
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let line = self.line();
+        let (position, offset) = {
+            let buffer = self.buffer();
+            let mut text = Text::new(&buffer);
+            let mut position = text.position_at(line, self.cur_x);
+
+            if after && position.index < text.len(line) {
+                position.index += 1;
+            }
+
+            (position, text.offset(position))
+        };
+
+        self.buffer_mut().replace_range((line, offset), (line, offset), &text)?;
+
+        if text.contains('\n') {
+            self.goto_position(position);
+        } else {
+            let len = unicode::graphemes(&text).count();
+            self.goto_position(Position::new(line, position.index + len - 1));
+        }
+
+        Ok(())
+    }
+
+    fn put_lines(&mut self, text: &str, after: bool, count: usize) -> Result<()> {
+        let mut lines = text.to_owned();
+        if !lines.ends_with('\n') {
+            lines.push('\n');
+        }
+        let lines = (0..count).map(|_| &lines[..]).collect::<String>();
+
+        let line = if after { self.line() + 1 } else { self.line() };
+        let last_line = self.buffer().len() - 1;
+
+        if line <= last_line {
+            self.buffer_mut().replace_range((line, 0), (line, 0), &lines)?;
+        } else {
+            // there's no line to put them before, so the line break goes
+            // in front of them
+            let end = self.buffer().line(last_line).len();
+            let text = format!("\n{}", &lines[..lines.len() - 1]);
+            self.buffer_mut().replace_range((last_line, end), (last_line, end), &text)?;
+        }
+
+        self.goto_first_non_blank(line);
+
+        Ok(())
+    }
+
+    /// Puts each line of the block at the same display column, padding
+    /// lines too short to reach it and adding lines past the end of the
+    /// buffer. Copies of a line are padded to the block width, except
+    /// where nothing follows them.
+    fn put_block(&mut self, text: &str, after: bool, count: usize) -> Result<()> {
+        let line = self.line();
+        let column = {
+            let buffer = self.buffer();
+            let mut text = Text::new(&buffer);
+            let position = text.position_at(line, self.cur_x);
+            let column = text.column(position);
+
+            match text.get(position).map(unicode::width) {
+                Some(width) if after => column + width,
+                _ => column,
+            }
+        };
+
+        let rows = text.split('\n').collect::<Vec<_>>();
+        let block_width = rows.iter().map(|row| unicode::width(row)).max().unwrap_or(0);
+
+        for (i, row) in rows.iter().enumerate() {
+            let target = line + i;
+
+            let last_line = self.buffer().len() - 1;
+            if target > last_line {
+                let end = self.buffer().line(last_line).len();
+                self.buffer_mut().replace_range((last_line, end), (last_line, end), "\n")?;
+            }
+
+            let current = self.buffer().line(target).into_owned();
+            let mut offset = current.len();
+            let mut line_column = 0;
+
+            for (grapheme_offset, grapheme) in unicode::graphemes(&current) {
+                if line_column >= column {
+                    offset = grapheme_offset;
+                    break;
+                }
+                line_column += unicode::width(grapheme);
+            }
+
+            let at_end = offset == current.len();
+            let padding = (line_column..column).map(|_| ' ').collect::<String>();
+            let row_padding = (unicode::width(row)..block_width).map(|_| ' ').collect::<String>();
+
+            let mut piece = padding;
+            for copy in 0..count {
+                piece.push_str(row);
+                if !at_end || copy + 1 < count {
+                    piece.push_str(&row_padding);
+                }
+            }
+
+            self.buffer_mut().replace_range((target, offset), (target, offset), &piece)?;
+        }
+
+        self.goto(line, column);
+
+        Ok(())
+    }
+
+    /// Inserts `text` at the cursor, leaving the cursor after it, as if it
+    /// were typed in insert mode.
+    pub fn insert_text(&mut self, text: &str) -> Result<()> {
+        let line = self.line();
+        let offset = {
+            let buffer = self.buffer();
+            let mut text = Text::new(&buffer);
+            let position = text.position_at(line, self.cur_x);
+
+            text.offset(position)
+        };
+
+        self.buffer_mut().replace_range((line, offset), (line, offset), text)?;
+
+        let breaks = text.matches('\n').count();
+        let x = match text.rfind('\n') {
+            Some(last_break) => unicode::width(&text[(last_break + 1)..]),
+            None => self.cur_x + unicode::width(text),
+        };
+
+        self.goto(line + breaks, x);
+        self.cur_x = x;
+
+        Ok(())
+    }
+
+    /// Buffer points of the part of each line in `block`. A wide char
+    /// partly inside the block is taken whole.
+    fn block_points(&self, block: Block) -> Vec<((usize, usize), (usize, usize))> {
+        let buffer = self.buffer();
+        let last = min(block.last, buffer.len() - 1);
+
+        (block.first..(last + 1)).map(|line| {
+            let text = buffer.line(line);
+            let mut column = 0;
+            let mut start = None;
+            let mut end = text.len();
+
+            for (offset, grapheme) in unicode::graphemes(&text) {
+                if column >= block.right {
+                    end = offset;
+                    break;
+                }
+
+                let width = unicode::width(grapheme);
+                if start.is_none() && column + width > block.left {
+                    start = Some(offset);
+                }
+
+                column += width;
+            }
+
+            let start = start.unwrap_or(text.len());
+            ((line, start), (line, max(start, end)))
+        }).collect()
+    }
+
+    /// Buffer points, as used by `Buffer::text_range`, of the text in
+    /// `range`. For a linewise range they leave out the last line break.
+    fn range_points(&self, range: Range) -> ((usize, usize), (usize, usize)) {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+
+        if range.linewise {
+            let last = range.end.line;
+            ((range.start.line, 0), (last, buffer.line(last).len()))
+        } else {
+            ((range.start.line, text.offset(range.start)), (range.end.line, text.offset(range.end)))
+        }
+    }
+
+    fn position_column(&self, position: Position) -> usize {
+        let buffer = self.buffer();
+        let mut text = Text::new(&buffer);
+
+        text.column(position)
+    }
+
+    /// Places the cursor on the grapheme at `position`.
+    pub fn goto_position(&mut self, position: Position) {
+        let x = self.position_column(position);
+        self.goto(position.line, x);
+    }
+
+    /// Places the cursor on the first char of `line` that isn't a blank.
+    pub fn goto_first_non_blank(&mut self, line: usize) {
+        let line = min(line, self.buffer().len() - 1);
+        let x = {
+            let buffer = self.buffer();
+            let mut text = Text::new(&buffer);
+            let position = text.first_non_blank(line);
+
+            text.column(position)
+        };
+
+        self.goto(line, x);
+    }
+
+    fn step(&mut self, movement: Movement) {
         match movement {
             Movement::Left => {
                 if self.cur_x > 0 {
@@ -110,9 +788,10 @@ impl Window {
                 self.ensure_cursor_not_in_middle_of_widechar();
             },
             Movement::Right => {
-                if let Some((_, ref grapheme)) = self.buffer.grapheme_at(self.cur_y + self.scroll_pos, self.cur_x) {
+                let buffer = self.buffer.borrow();
+                if let Some((_, ref grapheme)) = buffer.grapheme_at(self.cur_y + self.scroll_pos, self.cur_x) {
                     let size = unicode::width(grapheme);
-                    let line_width = unicode::width(self.buffer.line(self.cur_y + self.scroll_pos));
+                    let line_width = unicode::width(&buffer.line(self.cur_y + self.scroll_pos));
                     if self.cur_x + size < line_width {
                         self.cur_x += size;
 
@@ -142,7 +821,7 @@ impl Window {
                     self.scroll_up();
                 }
 
-                let contents_len = self.buffer.len();
+                let contents_len = self.buffer().len();
 
                 if self.cur_y >= contents_len {
                     self.cur_y = contents_len - 1;
@@ -151,14 +830,12 @@ impl Window {
                 self.ensure_cursor_over_line();
                 self.ensure_cursor_not_in_middle_of_widechar();
             },
+            _ => (),
         }
-
-        Ok(())
     }
 
     pub fn advance_cursor(&mut self) -> Result<()> {
-        let line = self.buffer.line(self.scroll_pos + self.cur_y);
-        let line_len = unicode::width(line);
+        let line_len = unicode::width(&self.buffer().line(self.line()));
 
         if line_len > 0 {
             self.cur_x += 1;
@@ -168,41 +845,99 @@ impl Window {
     }
 
     pub fn add_line_below(&mut self) -> Result<()> {
-        self.buffer.add_line(self.cur_y + 1)
+        let line = self.line();
+        self.buffer_mut().add_line(line + 1)
     }
 
     pub fn add_line_above(&mut self) -> Result<()> {
-        self.buffer.add_line(self.cur_y)
+        let line = self.line();
+        self.buffer_mut().add_line(line)
     }
 
-    pub fn write(&mut self) -> Result<()> {
-        self.buffer.write()
+    pub fn commit_changes(&mut self) {
+        self.buffer_mut().commit_changes();
     }
 
-    pub fn set_buffer(&mut self, buffer: Buffer) {
+    pub fn undo(&mut self) -> bool {
+        let position = self.buffer_mut().undo();
+        self.restore(position)
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let position = self.buffer_mut().redo();
+        self.restore(position)
+    }
+
+    pub fn earlier(&mut self, count: usize) -> bool {
+        let position = self.buffer_mut().earlier(count);
+        self.restore(position)
+    }
+
+    pub fn later(&mut self, count: usize) -> bool {
+        let position = self.buffer_mut().later(count);
+        self.restore(position)
+    }
+
+    fn restore(&mut self, position: Option<(usize, usize)>) -> bool {
+        match position {
+            Some((line, x)) => {
+                self.goto(line, x);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Places the cursor at column `x` of buffer line `line`, scrolling the
+    /// window if the line is not visible.
+    pub fn goto(&mut self, line: usize, x: usize) {
+        let line = min(line, self.buffer().len() - 1);
+
+        if line < self.scroll_pos {
+            self.scroll_pos = line;
+        } else if line >= self.scroll_pos + self.height {
+            self.scroll_pos = line + 1 - self.height;
+        }
+
+        self.cur_y = line - self.scroll_pos;
+        self.cur_x = x;
+
+        self.ensure_cursor_over_line();
+        self.ensure_cursor_not_in_middle_of_widechar();
+    }
+
+    pub fn write(&mut self) -> Result<Written> {
+        self.buffer_mut().write()
+    }
+
+    /// Shows `buffer` in this window, placing the cursor where `view` says.
+    pub fn set_buffer(&mut self, buffer: Rc<RefCell<Buffer>>, view: View) {
         self.buffer = buffer;
-        self.cur_y = 0;
-        self.cur_x = 0;
+        self.set_view(view);
+    }
+
+    /// Scrolls and places the cursor back where they were for `view`.
+    pub fn set_view(&mut self, view: View) {
+        self.scroll_pos = view.scroll_pos;
+        self.cur_y = view.cur_y;
+        self.cur_x = view.cur_x;
+
+        let line = self.line();
+        let x = self.cur_x;
+        self.goto(line, x);
     }
 
     pub fn break_line(&mut self) -> Result<()> {
-        self.buffer.break_line(self.scroll_pos + self.cur_y, self.cur_x)?;
-        self.cur_y += 1;
-        self.cur_x = 0;
+        let line = self.line();
+        self.buffer_mut().break_line(line, self.cur_x)?;
+        self.goto(line + 1, 0);
 
         Ok(())
     }
 
     pub fn add_char(&mut self, c: char) -> Result<()> {
-        if self.cur_x >= unicode::width(self.buffer.line(self.scroll_pos + self.cur_y)) {
-            let current_line = self.buffer.line_mut(self.scroll_pos + self.cur_y);
-            current_line.push(c);
-        } else {
-            if let Some((offset, _)) = self.buffer.grapheme_at(self.scroll_pos + self.cur_y, self.cur_x) {
-                let current_line = self.buffer.line_mut(self.scroll_pos + self.cur_y);
-                current_line.insert(offset, c);
-            }
-        }
+        let line = self.line();
+        self.buffer_mut().insert_char(line, self.cur_x, c)?;
 
         self.cur_x += unicode::width_char(c);
 
@@ -211,22 +946,26 @@ impl Window {
 
     pub fn delete_char(&mut self) -> Result<()> {
         if self.cur_x == 0 {
-            if self.cur_y == 0 {
+            let line = self.line();
+
+            if line == 0 {
                 return Ok(())
             } else {
-                let pos_x = unicode::width(self.buffer.line(self.cur_y - 1 + self.scroll_pos));
-                self.buffer.join_lines(self.cur_y - 1 + self.scroll_pos)?;
+                let pos_x = unicode::width(&self.buffer().line(line - 1));
+                self.buffer_mut().join_lines(line - 1)?;
 
-                self.cur_y -= 1;
+                self.goto(line - 1, pos_x);
                 self.cur_x = pos_x;
 
                 return Ok(())
             }
         }
 
-        let line_position = self.scroll_pos + self.cur_y;
+        let line_position = self.line();
+
+        let deleted = self.buffer_mut().delete_char_at(line_position, self.cur_x - 1)?;
 
-        if let Some(grapheme) = self.buffer.delete_char_at(line_position, self.cur_x - 1)? {
+        if let Some(grapheme) = deleted {
             self.cur_x -= unicode::width(&grapheme);
         }
 
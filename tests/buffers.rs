@@ -0,0 +1,169 @@
+extern crate king;
+
+use king::editor::Editor;
+use king::error::Error;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, run};
+
+fn editor_with_two_files() -> Editor {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":edit tests/fixtures/file_with_contents").unwrap();
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+
+    ed
+}
+
+fn filename(ed: &Editor) -> String {
+    ed.window().filename().unwrap()
+}
+
+#[test]
+fn the_first_file_reuses_the_empty_buffer() {
+    let mut ed = Editor::new(10, 40);
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    run(&mut ed, ":ls").unwrap();
+
+    assert_eq!(ed.prompt().text(), "  1 %  \"tests/fixtures/file2\" line 1");
+}
+
+#[test]
+fn listing_buffers() {
+    let mut ed = editor_with_two_files();
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "x");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":ls").unwrap();
+
+    assert_eq!(ed.prompt().text(),
+               "  1 #  \"tests/fixtures/file_with_contents\" line 1\n  2 %+ \"tests/fixtures/file2\" line 1");
+}
+
+#[test]
+fn long_messages_are_cleared_by_the_next_key() {
+    let mut ed = editor_with_two_files();
+
+    run(&mut ed, ":ls").unwrap();
+    input_text(&mut ed, "j");
+
+    assert_eq!(ed.prompt().text(), "");
+}
+
+#[test]
+fn editing_an_open_file_switches_to_its_buffer() {
+    let mut ed = editor_with_two_files();
+
+    run(&mut ed, ":edit tests/fixtures/file_with_contents").unwrap();
+    run(&mut ed, ":ls").unwrap();
+
+    assert_eq!(ed.prompt().text().lines().count(), 2);
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+}
+
+#[test]
+fn switching_by_number() {
+    let mut ed = editor_with_two_files();
+
+    run(&mut ed, ":buffer 1").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+
+    run(&mut ed, ":b 2").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file2");
+
+    match run(&mut ed, ":b 7") {
+        Err(Error::NoSuchBuffer(7)) => {},
+        _ => panic!("expected NoSuchBuffer"),
+    }
+}
+
+#[test]
+fn next_and_previous_wrap_around() {
+    let mut ed = editor_with_two_files();
+
+    run(&mut ed, ":bnext").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+
+    run(&mut ed, ":bprev").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file2");
+
+    run(&mut ed, ":bp").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+}
+
+#[test]
+fn alternate_buffer() {
+    let mut ed = Editor::new(10, 20);
+
+    match run(&mut ed, ":b#") {
+        Err(Error::NoAlternateFile) => {},
+        _ => panic!("expected NoAlternateFile"),
+    }
+
+    run(&mut ed, ":edit tests/fixtures/file_with_contents").unwrap();
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    run(&mut ed, ":b#").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+
+    run(&mut ed, ":buffer #").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file2");
+}
+
+#[test]
+fn cursor_position_is_remembered_per_buffer() {
+    let mut ed = Editor::new(3, 20);
+
+    run(&mut ed, ":edit tests/fixtures/file_with_contents").unwrap();
+    input_text(&mut ed, "jjll");
+    assert_eq!(ed.cursor(), (1, 2));
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+
+    run(&mut ed, ":b#").unwrap();
+    assert_eq!(ed.cursor(), (1, 2));
+    assert_eq!(ed.window().content_view().line(0), "line 2");
+}
+
+#[test]
+fn deleting_the_current_buffer_switches_to_the_alternate() {
+    let mut ed = editor_with_two_files();
+
+    run(&mut ed, ":bdelete").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+
+    run(&mut ed, ":ls").unwrap();
+    assert_eq!(ed.prompt().text(), "  1 %  \"tests/fixtures/file_with_contents\" line 1");
+}
+
+#[test]
+fn deleting_the_last_buffer_leaves_an_empty_one() {
+    let mut ed = Editor::new(10, 40);
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    run(&mut ed, ":bd 1").unwrap();
+
+    assert_eq!(ed.window().filename(), None);
+    run(&mut ed, ":ls").unwrap();
+    assert_eq!(ed.prompt().text(), "  2 %  \"[No Name]\" line 1");
+}
+
+#[test]
+fn deleting_a_modified_buffer_needs_a_bang() {
+    let mut ed = editor_with_two_files();
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "x");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    match run(&mut ed, ":bdelete 2") {
+        Err(Error::UnsavedChangesInBuffer(2)) => {},
+        _ => panic!("expected UnsavedChangesInBuffer"),
+    }
+
+    run(&mut ed, ":bdelete! 2").unwrap();
+    assert_eq!(filename(&ed), "tests/fixtures/file_with_contents");
+}
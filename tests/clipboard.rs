@@ -0,0 +1,178 @@
+extern crate king;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::rc::Rc;
+
+use king::clipboard::{Clipboard, ClipboardKind, External, Osc52};
+use king::editor::Editor;
+use king::error::{Error, Result};
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, editor_with, lines, run};
+
+/// Clipboard the test can look into and fill from the outside.
+#[derive(Clone, Default)]
+struct Fake {
+    selections: Rc<RefCell<HashMap<ClipboardKind, String>>>,
+    broken: bool,
+}
+
+impl Fake {
+    fn get(&self, kind: ClipboardKind) -> Option<String> {
+        self.selections.borrow().get(&kind).cloned()
+    }
+
+    fn set(&self, kind: ClipboardKind, text: &str) {
+        self.selections.borrow_mut().insert(kind, text.to_owned());
+    }
+}
+
+impl Clipboard for Fake {
+    fn copy(&mut self, kind: ClipboardKind, text: &str) -> Result<()> {
+        if self.broken {
+            return Err(Error::ClipboardError("broken".to_owned()));
+        }
+
+        self.set(kind, text);
+        Ok(())
+    }
+
+    fn paste(&self, kind: ClipboardKind) -> Result<Option<String>> {
+        Ok(self.get(kind))
+    }
+}
+
+fn editor_with_fake(lines: &[&str]) -> (Editor, Fake) {
+    let mut ed = editor_with(lines);
+    let fake = Fake::default();
+    ed.set_clipboard(Box::new(fake.clone()));
+
+    (ed, fake)
+}
+
+#[test]
+fn yanking_to_the_clipboard() {
+    let (mut ed, fake) = editor_with_fake(&["one two"]);
+
+    input_text(&mut ed, "\"+yy");
+    assert_eq!(fake.get(ClipboardKind::Clipboard).unwrap(), "one two\n");
+
+    input_text(&mut ed, "\"*yw");
+    assert_eq!(fake.get(ClipboardKind::Primary).unwrap(), "one ");
+    assert_eq!(fake.get(ClipboardKind::Clipboard).unwrap(), "one two\n");
+    assert_eq!(ed.register('"').unwrap(), "one ");
+}
+
+#[test]
+fn putting_from_the_clipboard() {
+    let (mut ed, fake) = editor_with_fake(&["one", "two"]);
+
+    fake.set(ClipboardKind::Clipboard, "new\n");
+    input_text(&mut ed, "\"+p");
+    assert_eq!(lines(&ed), vec!["one", "new", "two"]);
+
+    fake.set(ClipboardKind::Primary, "X");
+    input_text(&mut ed, "\"*P");
+    assert_eq!(lines(&ed), vec!["one", "Xnew", "two"]);
+}
+
+#[test]
+fn blocks_come_back_as_blocks() {
+    let (mut ed, _) = editor_with_fake(&["ab", "cd"]);
+
+    input_text(&mut ed, "\x16j\"+y$\"+p");
+
+    assert_eq!(lines(&ed), vec!["aba", "cdc"]);
+}
+
+#[test]
+fn an_empty_clipboard() {
+    let (mut ed, _) = editor_with_fake(&["one"]);
+
+    input_text(&mut ed, "\"+");
+    match ed.handle_key(&Key::from(KeyCode::Char('p'))) {
+        Err(Error::EmptyRegister('+')) => (),
+        _ => panic!("expected an empty register error"),
+    }
+}
+
+#[test]
+fn clipboard_errors_keep_the_register() {
+    let mut ed = editor_with(&["one"]);
+    ed.set_clipboard(Box::new(Fake { broken: true, ..Fake::default() }));
+
+    input_text(&mut ed, "\"+y");
+    match ed.handle_key(&Key::from(KeyCode::Char('y'))) {
+        Err(Error::ClipboardError(_)) => (),
+        _ => panic!("expected a clipboard error"),
+    }
+
+    assert_eq!(ed.register('"').unwrap(), "one\n");
+}
+
+#[test]
+fn osc52_escape_sequences() {
+    let mut ed = editor_with(&["one", "ab"]);
+    ed.set_clipboard(Box::new(Osc52::new()));
+
+    input_text(&mut ed, "\"+yyj\"*yl");
+
+    assert_eq!(ed.take_escape_sequences(), vec!["\x1b]52;c;b25lCg==\x07", "\x1b]52;p;YQ==\x07"]);
+    assert!(ed.take_escape_sequences().is_empty());
+    assert_eq!(ed.register('+').unwrap(), "one\n");
+}
+
+#[test]
+fn setting_the_clipboard() {
+    let mut ed = editor_with(&["ab"]);
+
+    run(&mut ed, ":set clipboard=osc52").unwrap();
+    input_text(&mut ed, "\"+yl");
+    assert_eq!(ed.take_escape_sequences(), vec!["\x1b]52;c;YQ==\x07"]);
+
+    match run(&mut ed, ":set clipboard=nothing") {
+        Err(Error::InvalidArgument(_)) => (),
+        _ => panic!("expected an invalid argument error"),
+    }
+}
+
+#[test]
+fn external_commands() {
+    let path = env::temp_dir().join(format!("king-clipboard-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    let copy = format!("cat > {}", path);
+
+    let mut ed = editor_with(&["one", "two"]);
+    ed.set_clipboard(Box::new(External::new(&["sh", "-c", &copy], &["cat", path])));
+
+    input_text(&mut ed, "\"+yyj\"+p");
+
+    assert_eq!(fs::read_to_string(path).unwrap(), "one\n");
+    assert_eq!(lines(&ed), vec!["one", "two", "one"]);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn custom_clipboard_commands() {
+    let path = env::temp_dir().join(format!("king-custom-clipboard-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut ed = editor_with(&["one", "two"]);
+    run(&mut ed, &format!(":set clipboard=tr a-z A-Z \\| cat > {}|cat {}", path, path)).unwrap();
+    input_text(&mut ed, "\"+yyj\"+p");
+
+    assert_eq!(fs::read_to_string(path).unwrap(), "ONE\n");
+    assert_eq!(lines(&ed), vec!["one", "two", "ONE"]);
+
+    fs::remove_file(path).unwrap();
+
+    match run(&mut ed, ":set clipboard=cat|") {
+        Err(Error::InvalidArgument(_)) => (),
+        _ => panic!("expected an invalid argument error"),
+    }
+}
@@ -0,0 +1,39 @@
+extern crate king;
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use king::clipboard;
+
+mod common;
+use common::{input_text, editor_with};
+
+// the environment is changed here, so this is the only test of its binary
+
+#[test]
+fn only_installed_tools_are_picked() {
+    let dir = env::temp_dir().join(format!("king-clipboard-detection-{}", std::process::id()));
+    let copied = dir.join("copied");
+    fs::create_dir_all(&dir).unwrap();
+
+    env::remove_var("WAYLAND_DISPLAY");
+    env::set_var("DISPLAY", ":0");
+    env::set_var("PATH", &dir);
+
+    let mut ed = editor_with(&["one"]);
+    ed.set_clipboard(clipboard::detect());
+    input_text(&mut ed, "\"+yy");
+    assert_eq!(ed.take_escape_sequences(), vec!["\x1b]52;c;b25lCg==\x07"]);
+
+    let xclip = dir.join("xclip");
+    fs::write(&xclip, format!("#!/bin/sh\n/bin/cat > {}\n", copied.display())).unwrap();
+    fs::set_permissions(&xclip, fs::Permissions::from_mode(0o755)).unwrap();
+
+    ed.set_clipboard(clipboard::detect());
+    input_text(&mut ed, "\"+yy");
+    assert!(ed.take_escape_sequences().is_empty());
+    assert_eq!(fs::read_to_string(&copied).unwrap(), "one\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,10 +1,73 @@
+// each test file uses only some of these
+#![allow(dead_code)]
+
 extern crate king;
 
+use std::fs;
+
 use king::editor::Editor;
-use king::input::Key;
+use king::error::Result;
+use king::headless::Headless;
+use king::input::{self, Key, KeyCode};
+use king::ui;
 
+/// Types `text` as a terminal would send it, so control chars become Ctrl
+/// chords.
 pub fn input_text(ed: &mut Editor, text: &str) {
     for c in text.chars() {
-        ed.handle_key(&Key::Char(c)).unwrap();
+        ed.handle_key(&input::decode_char(c)).unwrap();
     }
 }
+
+/// An editor with `lines` typed into its buffer, back in normal mode with
+/// the cursor at the start.
+pub fn editor_with(lines: &[&str]) -> Editor {
+    let mut ed = Editor::new(10, 40);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, &lines.join("\r"));
+    esc(&mut ed);
+    input_text(&mut ed, "gg0");
+
+    ed
+}
+
+/// The lines shown in the current window.
+pub fn lines(ed: &Editor) -> Vec<String> {
+    let view = ed.window().content_view();
+    (0..view.height()).map(|i| view.line(i).into_owned()).collect()
+}
+
+/// Types a command line, like `:split` or `/pattern`, and runs it.
+pub fn run(ed: &mut Editor, command: &str) -> Result<()> {
+    input_text(ed, command);
+    ed.handle_key(&Key::from(KeyCode::Enter))
+}
+
+/// Edits a copy of `fixture` in `target/`, named with `prefix` so test
+/// files don't share copies, writes it and checks it comes out unchanged.
+pub fn round_trip(prefix: &str, fixture: &str) {
+    let filename = format!("target/{}_{}", prefix, fixture);
+    let original = fs::read(format!("tests/fixtures/{}", fixture)).unwrap();
+    fs::write(&filename, &original).unwrap();
+
+    let mut ed = Editor::new(10, 10);
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    run(&mut ed, ":write").unwrap();
+
+    assert_eq!(fs::read(&filename).unwrap(), original);
+}
+
+pub fn esc(ed: &mut Editor) {
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+}
+
+/// Draws the editor on a screen of its size.
+pub fn render(ed: &Editor) -> Headless {
+    let (height, width) = ed.size();
+    let mut screen = Headless::new(height, width);
+
+    ui::render(ed, &mut screen);
+
+    screen
+}
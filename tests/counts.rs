@@ -0,0 +1,114 @@
+extern crate king;
+
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, editor_with};
+
+#[test]
+fn counts_repeat_movements() {
+    let mut ed = editor_with(&["one two three four", "a", "b", "c", "d"]);
+
+    input_text(&mut ed, "3w");
+    assert_eq!(ed.cursor(), (0, 14));
+
+    input_text(&mut ed, "0");
+    input_text(&mut ed, "3j");
+    assert_eq!(ed.cursor(), (3, 0));
+
+    input_text(&mut ed, "2k");
+    assert_eq!(ed.cursor(), (1, 0));
+}
+
+#[test]
+fn zero_moves_to_line_start_without_a_count() {
+    let mut ed = editor_with(&["0123456789ab"]);
+
+    input_text(&mut ed, "10l");
+    assert_eq!(ed.cursor(), (0, 10));
+
+    input_text(&mut ed, "0");
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn counts_go_to_lines() {
+    let mut ed = editor_with(&["a", "  b", "c", "d"]);
+
+    input_text(&mut ed, "2G");
+    assert_eq!(ed.cursor(), (1, 2));
+
+    input_text(&mut ed, "4gg");
+    assert_eq!(ed.cursor(), (3, 0));
+
+    input_text(&mut ed, "50%");
+    assert_eq!(ed.cursor(), (1, 2));
+}
+
+#[test]
+fn counts_find_later_chars() {
+    let mut ed = editor_with(&["a,b,c,d"]);
+
+    input_text(&mut ed, "2f,");
+    assert_eq!(ed.cursor(), (0, 3));
+
+    input_text(&mut ed, "0");
+    input_text(&mut ed, "3t,");
+    assert_eq!(ed.cursor(), (0, 4));
+}
+
+#[test]
+fn counts_are_shown_while_pending() {
+    let mut ed = editor_with(&["abc"]);
+
+    input_text(&mut ed, "12");
+    assert_eq!(ed.pending_keys(), "12");
+
+    input_text(&mut ed, "f");
+    assert_eq!(ed.pending_keys(), "12f");
+
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(ed.pending_keys(), "");
+}
+
+#[test]
+fn counts_repeat_undo() {
+    let mut ed = editor_with(&["a"]);
+
+    input_text(&mut ed, "ob");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "oc");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    input_text(&mut ed, "2u");
+    assert_eq!(ed.window().content_view().height(), 1);
+
+    input_text(&mut ed, "2\u{12}");
+    assert_eq!(ed.window().content_view().height(), 3);
+}
+
+#[test]
+fn commands_without_a_count_ignore_it() {
+    let mut ed = editor_with(&["abc"]);
+
+    input_text(&mut ed, "3v");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "x");
+
+    assert_eq!(ed.window().content_view().line(0), "abc");
+    assert_eq!(ed.pending_keys(), "");
+}
+
+#[test]
+fn huge_counts_stop_where_the_cursor_can_go_no_further() {
+    let mut ed = editor_with(&["one two", "three"]);
+
+    input_text(&mut ed, "99999999999999999999999j");
+    assert_eq!(ed.cursor(), (1, 0));
+
+    input_text(&mut ed, "999999999l");
+    assert_eq!(ed.cursor(), (1, 4));
+
+    input_text(&mut ed, "gg999999999w");
+    assert_eq!(ed.cursor(), (1, 4));
+}
@@ -1,7 +1,7 @@
 extern crate king;
 
 use king::editor::Editor;
-use king::input::Key;
+use king::input::{Key, KeyCode};
 use king::mode::Mode;
 
 mod common;
@@ -12,7 +12,7 @@ fn editing_a_file_that_exists() {
     let mut ed = Editor::new(10, 10);
 
     input_text(&mut ed, ":edit tests/fixtures/file_with_contents");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
 
     assert_eq!(ed.mode(), Mode::Normal);
     assert_eq!(ed.prompt().text(), "\"tests/fixtures/file_with_contents\"");
@@ -23,10 +23,10 @@ fn cursor_position_reset_when_editing_a_new_file() {
     let mut ed = Editor::new(10, 10);
 
     input_text(&mut ed, ":edit tests/fixtures/file_with_contents");
-    ed.handle_key(&Key::Enter).unwrap();
-    ed.handle_key(&Key::Char('j')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+    ed.handle_key(&Key::from('j')).unwrap();
     input_text(&mut ed, ":edit tests/fixtures/file2");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
 
     assert_eq!(ed.cursor(), (0, 0));
 }
@@ -36,7 +36,7 @@ fn when_content_fits_the_window() {
     let mut ed = Editor::new(10, 10);
 
     input_text(&mut ed, ":edit tests/fixtures/file_with_contents");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
 
     assert_eq!(ed.window().content_view().height(), 3);
     assert_eq!(ed.window().content_view().line(0), "line 1");
@@ -49,7 +49,7 @@ fn when_content_is_larger_than_the_window_height() {
     let mut ed = Editor::new(3, 10);
 
     input_text(&mut ed, ":edit tests/fixtures/file_with_contents");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
 
     assert_eq!(ed.window().content_view().height(), 2);
     assert_eq!(ed.window().content_view().line(0), "line 1");
@@ -61,7 +61,7 @@ fn when_scrolling_the_content() {
     let mut ed = Editor::new(3, 10);
 
     input_text(&mut ed, ":edit tests/fixtures/file_with_contents");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
     input_text(&mut ed, "jjj");
 
     assert_eq!(ed.window().content_view().height(), 2);
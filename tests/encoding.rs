@@ -0,0 +1,140 @@
+extern crate king;
+
+use std::fs;
+
+use king::buffer::FileFormat;
+use king::editor::Editor;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, run, round_trip};
+
+#[test]
+fn latin1_files_are_decoded() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":edit tests/fixtures/latin1_file").unwrap();
+
+    assert_eq!(ed.prompt().text(), "\"tests/fixtures/latin1_file\" [latin1]");
+    assert_eq!(ed.window().content_view().line(0), "café");
+    assert_eq!(ed.window().content_view().line(1), "naïve");
+}
+
+#[test]
+fn utf16_files_are_decoded() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":edit tests/fixtures/utf16le_file").unwrap();
+
+    assert_eq!(ed.prompt().text(), "\"tests/fixtures/utf16le_file\" [utf-16le]");
+    assert_eq!(ed.window().content_view().line(0), "line 😀");
+}
+
+#[test]
+fn invalid_bytes_are_kept_as_placeholders() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":edit tests/fixtures/file_with_invalid_byte").unwrap();
+    assert_eq!(ed.prompt().text(), "\"tests/fixtures/file_with_invalid_byte\" [1 invalid byte]");
+
+    input_text(&mut ed, "llllll");
+    assert_eq!(ed.cursor(), (0, 5));
+}
+
+#[test]
+fn more_invalid_bytes_are_counted() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":edit tests/fixtures/file_with_invalid_bytes").unwrap();
+    assert_eq!(ed.prompt().text(), "\"tests/fixtures/file_with_invalid_bytes\" [2 invalid bytes]");
+}
+
+#[test]
+fn latin1_files_round_trip() {
+    round_trip("encoding_round_trip", "latin1_file");
+}
+
+#[test]
+fn utf16_files_round_trip() {
+    round_trip("encoding_round_trip", "utf16le_file");
+}
+
+#[test]
+fn invalid_bytes_round_trip() {
+    round_trip("encoding_round_trip", "file_with_invalid_byte");
+}
+
+#[test]
+fn placeholder_chars_round_trip() {
+    round_trip("encoding_round_trip", "file_with_placeholder_char");
+    round_trip("encoding_round_trip", "utf16le_file_with_placeholder_char");
+}
+
+fn edit_and_write_large_file(name: &str, bytes: &[u8], lines: usize) {
+    let filename = format!("target/{}", name);
+    fs::write(&filename, bytes).unwrap();
+
+    let mut ed = Editor::new(10, 10);
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    assert_eq!(ed.window().buffer().len(), lines);
+    assert_eq!(ed.window().buffer().file_format(), FileFormat::Dos);
+    assert_eq!(ed.window().buffer().invalid_bytes(), 0);
+
+    run(&mut ed, ":write").unwrap();
+    assert!(fs::read(&filename).unwrap() == bytes, "{} changed when written", filename);
+}
+
+// the lines don't divide the chunks files are decoded in, so chunks end
+// inside multibyte chars, surrogate pairs and CRLFs
+#[test]
+fn large_files_round_trip() {
+    let utf8 = "café\r\n".repeat(80_000);
+    edit_and_write_large_file("large_utf8_file", utf8.as_bytes(), 80_000);
+
+    let mut utf16 = vec![0xFF, 0xFE];
+    for unit in "a😀\r\n".repeat(80_000).encode_utf16() {
+        utf16.push(unit as u8);
+        utf16.push((unit >> 8) as u8);
+    }
+    edit_and_write_large_file("large_utf16le_file", &utf16, 80_000);
+}
+
+#[test]
+fn setting_the_file_encoding_converts_on_save() {
+    let filename = "target/setting_the_file_encoding_converts_on_save";
+    fs::copy("tests/fixtures/latin1_file", filename).unwrap();
+
+    let mut ed = Editor::new(10, 10);
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    run(&mut ed, ":set fileencoding=utf-8").unwrap();
+    run(&mut ed, ":write").unwrap();
+    assert_eq!(fs::read_to_string(filename).unwrap(), "café\nnaïve\n");
+
+    run(&mut ed, ":set fenc").unwrap();
+    assert_eq!(ed.prompt().text(), "fileencoding=utf-8");
+}
+
+#[test]
+fn saving_chars_the_encoding_cannot_represent_fails() {
+    let filename = "target/saving_chars_the_encoding_cannot_represent_fails";
+    fs::copy("tests/fixtures/latin1_file", filename).unwrap();
+
+    let mut ed = Editor::new(10, 10);
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "😀");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, ":write");
+
+    assert!(ed.handle_key(&Key::from(KeyCode::Enter)).is_err());
+    assert_eq!(fs::read(filename).unwrap(), fs::read("tests/fixtures/latin1_file").unwrap());
+}
+
+#[test]
+fn unknown_encodings_are_rejected() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, ":set fileencoding=klingon");
+
+    assert!(ed.handle_key(&Key::from(KeyCode::Enter)).is_err());
+}
@@ -21,7 +21,7 @@ fn starts_with_the_specified_dimentions() {
 fn starts_running() {
     let ed = Editor::new(10, 10);
 
-    assert_eq!(ed.running(), true);
+    assert!(ed.running());
 }
 
 #[test]
@@ -1,7 +1,7 @@
 extern crate king;
 
 use king::editor::Editor;
-use king::input::Key;
+use king::input::{Key, KeyCode};
 use king::mode::Mode;
 
 mod common;
@@ -9,7 +9,7 @@ use common::input_text;
 
 fn editor() -> Editor {
     let mut ed = Editor::new(10, 10);
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     ed
 }
 
@@ -17,7 +17,7 @@ fn editor() -> Editor {
 fn enter_and_exit() {
     let mut ed = editor();
 
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
 
     assert_eq!(ed.mode(), Mode::Normal);
     assert_eq!(ed.cursor(), (0, 0));
@@ -57,15 +57,15 @@ fn deleting_text_with_backspace() {
     input_text(&mut ed, "some 😀a😀");
     assert_eq!(ed.cursor(), (0, 10));
 
-    ed.handle_key(&Key::Backspace).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
     assert_eq!(ed.cursor(), (0, 8));
     assert_eq!(ed.window().content_view().line(0), "some 😀a");
 
-    ed.handle_key(&Key::Backspace).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
     assert_eq!(ed.cursor(), (0, 7));
     assert_eq!(ed.window().content_view().line(0), "some 😀");
 
-    ed.handle_key(&Key::Backspace).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
     assert_eq!(ed.cursor(), (0, 5));
     assert_eq!(ed.window().content_view().line(0), "some ");
 }
@@ -75,13 +75,13 @@ fn deleting_lines_with_backspace() {
     let mut ed = editor();
 
     input_text(&mut ed, "12");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     input_text(&mut ed, "o");
     input_text(&mut ed, "34");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     input_text(&mut ed, "h");
     input_text(&mut ed, "i");
-    ed.handle_key(&Key::Backspace).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
 
     assert_eq!(ed.cursor(), (0, 2));
     assert_eq!(ed.window().content_view().line(0), "1234");
@@ -92,7 +92,7 @@ fn deleting_lines_with_backspace() {
 fn when_buffer_is_empty_i_enters_insert_mode() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
 
     assert_eq!(ed.mode(), Mode::Insert);
     assert_eq!(ed.cursor(), (0, 0));
@@ -102,10 +102,10 @@ fn when_buffer_is_empty_i_enters_insert_mode() {
 fn when_there_is_content_i_does_not_move_the_cursor() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "1234");
-    ed.handle_key(&Key::Esc).unwrap();
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "x");
 
     assert_eq!(ed.window().content_view().line(0), "123x4");
@@ -115,20 +115,20 @@ fn when_there_is_content_i_does_not_move_the_cursor() {
 fn enter_key() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('i')).unwrap();
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
     assert_eq!(ed.cursor(), (1, 0));
     assert_eq!(ed.window().content_view().line(1), "");
 
     input_text(&mut ed, "12");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
     assert_eq!(ed.cursor(), (2, 0));
     assert_eq!(ed.window().content_view().line(2), "");
 
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     input_text(&mut ed, "kl");
-    ed.handle_key(&Key::Char('i')).unwrap();
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
     assert_eq!(ed.cursor(), (2, 0));
     assert_eq!(ed.window().content_view().line(1), "1");
     assert_eq!(ed.window().content_view().line(2), "2");
@@ -138,32 +138,32 @@ fn enter_key() {
 fn inserting_text_when_there_are_widechars() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "1😀ab");
-    ed.handle_key(&Key::Esc).unwrap();
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "x");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     assert_eq!(ed.window().content_view().line(0), "1😀axb");
 
-    ed.handle_key(&Key::Char('h')).unwrap();
-    ed.handle_key(&Key::Char('h')).unwrap();
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('h')).unwrap();
+    ed.handle_key(&Key::from('h')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "x");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     assert_eq!(ed.window().content_view().line(0), "1😀xaxb");
 
-    ed.handle_key(&Key::Char('h')).unwrap();
-    ed.handle_key(&Key::Char('h')).unwrap();
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('h')).unwrap();
+    ed.handle_key(&Key::from('h')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "x");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     assert_eq!(ed.window().content_view().line(0), "1x😀xaxb");
 
-    ed.handle_key(&Key::Char('h')).unwrap();
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('h')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "y");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
     assert_eq!(ed.window().content_view().line(0), "1yx😀xaxb");
 }
 
@@ -171,7 +171,7 @@ fn inserting_text_when_there_are_widechars() {
 fn when_buffer_is_empty_a_enters_insert_mode() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('a')).unwrap();
+    ed.handle_key(&Key::from('a')).unwrap();
 
     assert_eq!(ed.mode(), Mode::Insert);
     assert_eq!(ed.cursor(), (0, 0));
@@ -181,10 +181,10 @@ fn when_buffer_is_empty_a_enters_insert_mode() {
 fn when_there_is_content_a_moves_the_cursor_forward() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('a')).unwrap();
+    ed.handle_key(&Key::from('a')).unwrap();
     input_text(&mut ed, "1234");
-    ed.handle_key(&Key::Esc).unwrap();
-    ed.handle_key(&Key::Char('a')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    ed.handle_key(&Key::from('a')).unwrap();
     input_text(&mut ed, "x");
 
     assert_eq!(ed.window().content_view().line(0), "1234x");
@@ -194,11 +194,11 @@ fn when_there_is_content_a_moves_the_cursor_forward() {
 fn o_opens_insert_mode_on_the_next_line() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "asdf");
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
 
-    ed.handle_key(&Key::Char('o')).unwrap();
+    ed.handle_key(&Key::from('o')).unwrap();
     assert_eq!(ed.mode(), Mode::Insert);
     assert_eq!(ed.cursor(), (1, 0));
 }
@@ -207,12 +207,27 @@ fn o_opens_insert_mode_on_the_next_line() {
 fn shift_o_opens_insert_mode_on_the_previous_line() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char('i')).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
     input_text(&mut ed, "1234");
-    ed.handle_key(&Key::Esc).unwrap();
-    ed.handle_key(&Key::Char('O')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    ed.handle_key(&Key::from('O')).unwrap();
     assert_eq!(ed.mode(), Mode::Insert);
     assert_eq!(ed.cursor(), (0, 0));
     assert_eq!(ed.window().content_view().line(0), "");
     assert_eq!(ed.window().content_view().line(1), "1234");
 }
+
+#[test]
+fn enter_key_after_widechars() {
+    let mut ed = Editor::new(10, 10);
+
+    ed.handle_key(&Key::from('i')).unwrap();
+    input_text(&mut ed, "😀ab");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    ed.handle_key(&Key::from('i')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+
+    assert_eq!(ed.cursor(), (1, 0));
+    assert_eq!(ed.window().content_view().line(0), "😀a");
+    assert_eq!(ed.window().content_view().line(1), "b");
+}
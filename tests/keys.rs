@@ -0,0 +1,110 @@
+extern crate king;
+
+use king::editor::Editor;
+use king::input::{self, Key, KeyCode, Modifiers};
+use king::mode::Mode;
+
+mod common;
+use common::input_text;
+
+fn key(text: &str) -> Key {
+    Key::parse(text).unwrap()
+}
+
+#[test]
+fn parsing_plain_chars() {
+    assert_eq!(key("x"), Key::from('x'));
+    assert_eq!(key("<"), Key::from('<'));
+    assert_eq!(key("<lt>"), Key::from('<'));
+    assert_eq!(key("<Space>"), Key::from(' '));
+}
+
+#[test]
+fn parsing_special_keys() {
+    assert_eq!(key("<CR>"), Key::from(KeyCode::Enter));
+    assert_eq!(key("<esc>"), Key::from(KeyCode::Esc));
+    assert_eq!(key("<PageDown>"), Key::from(KeyCode::PageDown));
+    assert_eq!(key("<F5>"), Key::from(KeyCode::F(5)));
+}
+
+#[test]
+fn parsing_modifiers() {
+    assert_eq!(key("<C-w>"), Key::ctrl('w'));
+    assert_eq!(key("<C-W>"), Key::ctrl('w'));
+    assert_eq!(key("<A-x>"), Key::alt(KeyCode::Char('x')));
+    assert_eq!(key("<M-x>"), Key::alt(KeyCode::Char('x')));
+    assert_eq!(key("<S-a>"), Key::from('A'));
+    assert_eq!(key("<C-A-Up>"), Key::new(KeyCode::Up, Modifiers { ctrl: true, alt: true, shift: false }));
+    assert_eq!(key("<S-Tab>"), Key::new(KeyCode::Tab, Modifiers { shift: true, ..Modifiers::default() }));
+}
+
+#[test]
+fn parsing_sequences() {
+    assert_eq!(input::parse_keys("d<C-w>x<F12>").unwrap(),
+               vec![Key::from('d'), Key::ctrl('w'), Key::from('x'), Key::from(KeyCode::F(12))]);
+    assert_eq!(input::parse_keys("<nope>").unwrap().len(), 6);
+    assert!(Key::parse("ab").is_err());
+    assert!(Key::parse("").is_err());
+}
+
+#[test]
+fn displaying_keys_round_trips() {
+    for text in &["x", "<lt>", "<Space>", "<CR>", "<C-w>", "<A-x>", "<C-A-S-Left>", "<F5>", "<S-Tab>"] {
+        assert_eq!(key(text).to_string(), *text);
+    }
+}
+
+#[test]
+fn decoding_control_chars() {
+    assert_eq!(input::decode_char('\r'), Key::from(KeyCode::Enter));
+    assert_eq!(input::decode_char('\t'), Key::from(KeyCode::Tab));
+    assert_eq!(input::decode_char('\u{7f}'), Key::from(KeyCode::Backspace));
+    assert_eq!(input::decode_char('\u{17}'), Key::ctrl('w'));
+    assert_eq!(input::decode_char('\u{1d}'), Key::ctrl(']'));
+    assert_eq!(input::decode_char('é'), Key::from('é'));
+}
+
+#[test]
+fn decoding_escape_sequences() {
+    assert_eq!(input::decode_escape_sequence(""), Key::from(KeyCode::Esc));
+    assert_eq!(input::decode_escape_sequence("x"), Key::alt(KeyCode::Char('x')));
+    assert_eq!(input::decode_escape_sequence("\u{17}"), key("<C-A-w>"));
+    assert_eq!(input::decode_escape_sequence("[A"), Key::from(KeyCode::Up));
+    assert_eq!(input::decode_escape_sequence("OH"), Key::from(KeyCode::Home));
+    assert_eq!(input::decode_escape_sequence("[3~"), Key::from(KeyCode::Delete));
+    assert_eq!(input::decode_escape_sequence("[6~"), Key::from(KeyCode::PageDown));
+    assert_eq!(input::decode_escape_sequence("OQ"), Key::from(KeyCode::F(2)));
+    assert_eq!(input::decode_escape_sequence("[15~"), Key::from(KeyCode::F(5)));
+    assert_eq!(input::decode_escape_sequence("[24~"), Key::from(KeyCode::F(12)));
+    assert_eq!(input::decode_escape_sequence("[1;5C"), key("<C-Right>"));
+    assert_eq!(input::decode_escape_sequence("[5;3~"), key("<A-PageUp>"));
+    assert_eq!(input::decode_escape_sequence("[Z"), key("<S-Tab>"));
+}
+
+#[test]
+fn arrow_keys_move_the_cursor() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "iab");
+    ed.handle_key(&key("<CR>")).unwrap();
+    input_text(&mut ed, "cd");
+
+    ed.handle_key(&key("<Up>")).unwrap();
+    ed.handle_key(&key("<Left>")).unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+
+    ed.handle_key(&key("<Esc>")).unwrap();
+    ed.handle_key(&key("<Down>")).unwrap();
+    ed.handle_key(&key("<Right>")).unwrap();
+    assert_eq!(ed.cursor(), (1, 1));
+    assert_eq!(ed.mode(), Mode::Normal);
+}
+
+#[test]
+fn tab_inserts_a_tab() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i\t");
+
+    assert_eq!(ed.window().content_view().line(0), "\t");
+}
@@ -0,0 +1,182 @@
+extern crate king;
+
+use king::error::Error;
+use king::headless::Headless;
+use king::input::{Key, KeyCode};
+use king::ui;
+
+mod common;
+use common::{input_text, editor_with, lines, esc};
+
+#[test]
+fn recording_and_playing() {
+    let mut ed = editor_with(&["one", "two", "three"]);
+
+    input_text(&mut ed, "qa0i- ");
+    esc(&mut ed);
+    input_text(&mut ed, "jq");
+
+    assert_eq!(ed.recording(), None);
+    assert_eq!(ed.register('a').unwrap(), "0i- <Esc>j");
+
+    input_text(&mut ed, "@a");
+    assert_eq!(lines(&ed), vec!["- one", "- two", "three"]);
+
+    input_text(&mut ed, "@@");
+    assert_eq!(lines(&ed), vec!["- one", "- two", "- three"]);
+}
+
+#[test]
+fn playing_with_a_count() {
+    let mut ed = editor_with(&["a b c d e f"]);
+
+    input_text(&mut ed, "qxdwq2@x");
+
+    assert_eq!(lines(&ed), vec!["d e f"]);
+}
+
+#[test]
+fn appending_to_a_macro() {
+    let mut ed = editor_with(&["a b c d e f"]);
+
+    input_text(&mut ed, "qzdwqqZwq");
+    assert_eq!(ed.register('z').unwrap(), "dww");
+
+    input_text(&mut ed, "0@z");
+    assert_eq!(lines(&ed), vec!["c d e f"]);
+}
+
+#[test]
+fn playback_stops_at_the_first_error() {
+    let mut ed = editor_with(&["dw\"qpdw", "one two three"]);
+
+    input_text(&mut ed, "\"ay$j");
+    input_text(&mut ed, "@");
+    match ed.handle_key(&Key::from(KeyCode::Char('a'))) {
+        Err(Error::EmptyRegister('q')) => (),
+        _ => panic!("expected an empty register error"),
+    }
+
+    assert_eq!(lines(&ed), vec!["dw\"qpdw", "two three"]);
+    assert_eq!(ed.pending_keys(), "");
+}
+
+#[test]
+fn editing_a_macro_as_text() {
+    let mut ed = editor_with(&["abc", ""]);
+
+    input_text(&mut ed, "qmilt");
+    esc(&mut ed);
+    input_text(&mut ed, "qu");
+    assert_eq!(lines(&ed), vec!["abc", ""]);
+
+    input_text(&mut ed, "j\"mp");
+    assert_eq!(lines(&ed), vec!["abc", "ilt<Esc>"]);
+
+    input_text(&mut ed, "0ldliX");
+    esc(&mut ed);
+    input_text(&mut ed, "0\"my$k0@m");
+    assert_eq!(lines(&ed), vec!["Xtabc", "iXt<Esc>"]);
+}
+
+#[test]
+fn lines_put_back_into_a_register() {
+    let mut ed = editor_with(&["one", "two", "three", "0i<lt><Esc>j0"]);
+
+    input_text(&mut ed, "G\"add");
+    assert_eq!(ed.register('a').unwrap(), "0i<lt><Esc>j0\n");
+
+    // the line break ending the text isn't typed as <CR>
+    input_text(&mut ed, "gg@a");
+    assert_eq!(lines(&ed), vec!["<one", "two", "three"]);
+    assert_eq!(ed.cursor(), (1, 0));
+}
+
+#[test]
+fn repeating_the_last_command_line() {
+    let mut ed = editor_with(&["one", "two", "three"]);
+
+    input_text(&mut ed, ":d\r");
+    input_text(&mut ed, "@:");
+
+    assert_eq!(lines(&ed), vec!["three"]);
+}
+
+#[test]
+fn recording_shows_on_the_prompt_line() {
+    let mut ed = editor_with(&["one"]);
+
+    input_text(&mut ed, "qb");
+    assert_eq!(ed.recording(), Some('b'));
+
+    let (height, width) = ed.size();
+    let mut screen = Headless::new(height, width);
+    ui::render(&ed, &mut screen);
+
+    assert_eq!(screen.row(height - 1), "recording @b");
+}
+
+#[test]
+fn dot_repeats_the_last_change_of_a_macro() {
+    let mut ed = editor_with(&["a b c d e f"]);
+
+    input_text(&mut ed, "qcdwwq0@c.");
+
+    assert_eq!(lines(&ed), vec!["c e f"]);
+}
+
+#[test]
+fn macros_playing_themselves_stop() {
+    let mut ed = editor_with(&["j@a", "1", "2", "3"]);
+
+    input_text(&mut ed, "\"ay$");
+    input_text(&mut ed, "@a");
+
+    assert_eq!(ed.cursor(), (3, 0));
+}
+
+#[test]
+fn a_failed_motion_stops_playback() {
+    let mut ed = editor_with(&["ax", "bx", "cy", "dx"]);
+
+    input_text(&mut ed, "qa0fxdlj0q");
+    input_text(&mut ed, "5@a");
+
+    assert_eq!(lines(&ed), vec!["a", "b", "cy", "dx"]);
+    assert_eq!(ed.cursor(), (2, 0));
+}
+
+#[test]
+fn playback_stops_once_the_keys_change_nothing() {
+    let mut ed = editor_with(&["abc"]);
+
+    input_text(&mut ed, "$qa0q");
+    input_text(&mut ed, "$999999999999@a");
+
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn macros_playing_themselves_forever_give_an_error() {
+    let mut ed = editor_with(&["yy@a"]);
+
+    input_text(&mut ed, "\"ay$");
+    input_text(&mut ed, "@");
+    match ed.handle_key(&Key::from(KeyCode::Char('a'))) {
+        Err(Error::MacroTooDeep) => (),
+        _ => panic!("expected a macro too deep error"),
+    }
+}
+
+#[test]
+fn recording_into_a_read_only_register() {
+    let mut ed = editor_with(&["one"]);
+
+    input_text(&mut ed, "q");
+    match ed.handle_key(&Key::from(KeyCode::Char('%'))) {
+        Err(Error::InvalidRegister('%')) => (),
+        _ => panic!("expected an invalid register error"),
+    }
+
+    assert_eq!(ed.recording(), None);
+}
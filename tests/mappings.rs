@@ -0,0 +1,122 @@
+extern crate king;
+
+use king::command::Command;
+use king::editor::Editor;
+use king::input::{self, Key, KeyCode};
+use king::mode::Mode;
+
+mod common;
+use common::input_text;
+
+fn map(ed: &mut Editor, mode: Mode, keys: &str, command: Command) {
+    ed.add_mapping(mode, &input::parse_keys(keys).unwrap(), command);
+}
+
+#[test]
+fn sequences_wait_for_the_next_key() {
+    let mut ed = Editor::new(10, 20);
+
+    input_text(&mut ed, "Z");
+    assert!(ed.running());
+    assert_eq!(ed.pending_keys(), "Z");
+    assert!(ed.key_timeout().is_some());
+
+    input_text(&mut ed, "Q");
+    assert!(!ed.running());
+    assert_eq!(ed.pending_keys(), "");
+}
+
+#[test]
+fn pending_keys_are_shown_in_notation() {
+    let mut ed = Editor::new(10, 20);
+
+    ed.handle_key(&Key::ctrl('w')).unwrap();
+
+    assert_eq!(ed.pending_keys(), "<C-w>");
+}
+
+#[test]
+fn a_timeout_drops_an_unfinished_sequence() {
+    let mut ed = Editor::new(10, 20);
+
+    input_text(&mut ed, "Z");
+    ed.handle_timeout().unwrap();
+
+    assert_eq!(ed.pending_keys(), "");
+    assert_eq!(ed.key_timeout(), None);
+    assert!(ed.running());
+}
+
+#[test]
+fn a_key_that_ends_no_sequence_is_handled_on_its_own() {
+    let mut ed = Editor::new(10, 20);
+
+    input_text(&mut ed, "Zi");
+
+    assert_eq!(ed.pending_keys(), "");
+    assert_eq!(ed.mode(), Mode::Insert);
+}
+
+#[test]
+fn a_timeout_resolves_ambiguous_prefixes() {
+    let mut ed = Editor::new(10, 20);
+
+    map(&mut ed, Mode::Normal, "g", Command::EnterInsert);
+    map(&mut ed, Mode::Normal, "gx", Command::OpenLineAfter);
+
+    input_text(&mut ed, "g");
+    assert_eq!(ed.mode(), Mode::Normal);
+
+    ed.handle_timeout().unwrap();
+    assert_eq!(ed.mode(), Mode::Insert);
+}
+
+#[test]
+fn the_longer_mapping_wins_when_completed() {
+    let mut ed = Editor::new(10, 20);
+
+    map(&mut ed, Mode::Normal, "g", Command::EnterInsert);
+    map(&mut ed, Mode::Normal, "gx", Command::OpenLineAfter);
+
+    input_text(&mut ed, "gx");
+
+    assert_eq!(ed.mode(), Mode::Insert);
+    assert_eq!(ed.window().content_view().height(), 2);
+}
+
+#[test]
+fn typed_keys_of_an_unfinished_insert_mapping_are_inserted() {
+    let mut ed = Editor::new(10, 20);
+
+    map(&mut ed, Mode::Insert, "jk", Command::LeaveInsert);
+
+    input_text(&mut ed, "ijajk");
+
+    assert_eq!(ed.mode(), Mode::Normal);
+    assert_eq!(ed.window().content_view().line(0), "ja");
+}
+
+#[test]
+fn insert_mappings_time_out_into_text() {
+    let mut ed = Editor::new(10, 20);
+
+    map(&mut ed, Mode::Insert, "jk", Command::LeaveInsert);
+
+    input_text(&mut ed, "ij");
+    assert_eq!(ed.window().content_view().line(0), "");
+
+    ed.handle_timeout().unwrap();
+    assert_eq!(ed.window().content_view().line(0), "j");
+    assert_eq!(ed.mode(), Mode::Insert);
+}
+
+#[test]
+fn the_timeout_can_be_set() {
+    let mut ed = Editor::new(10, 20);
+
+    input_text(&mut ed, ":set timeoutlen=300");
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+    input_text(&mut ed, "Z");
+
+    assert_eq!(ed.key_timeout().map(|timeout| timeout.subsec_millis()), Some(300));
+}
@@ -0,0 +1,125 @@
+extern crate king;
+
+use king::editor::Editor;
+
+mod common;
+use common::{input_text, editor_with};
+
+/// Cursor positions after each of `keys`.
+fn positions(ed: &mut Editor, keys: &str) -> Vec<(usize, usize)> {
+    keys.chars().map(|c| {
+        input_text(ed, &c.to_string());
+        ed.cursor()
+    }).collect()
+}
+
+#[test]
+fn word_motions() {
+    let mut ed = editor_with(&["foo.bar baz", "", "  qux"]);
+
+    assert_eq!(positions(&mut ed, "wwwwww"), vec![(0, 3), (0, 4), (0, 8), (1, 0), (2, 2), (2, 4)]);
+    assert_eq!(positions(&mut ed, "bbbbb"), vec![(2, 2), (1, 0), (0, 8), (0, 4), (0, 3)]);
+}
+
+#[test]
+fn word_end_motions() {
+    let mut ed = editor_with(&["foo.bar baz", "", "  qux"]);
+
+    assert_eq!(positions(&mut ed, "eeee"), vec![(0, 2), (0, 3), (0, 6), (0, 10)]);
+    assert_eq!(positions(&mut ed, "e"), vec![(2, 4)]);
+}
+
+#[test]
+fn big_word_motions() {
+    let mut ed = editor_with(&["foo.bar baz", "a-b"]);
+
+    assert_eq!(positions(&mut ed, "WW"), vec![(0, 8), (1, 0)]);
+    assert_eq!(positions(&mut ed, "E"), vec![(1, 2)]);
+    assert_eq!(positions(&mut ed, "BB"), vec![(1, 0), (0, 8)]);
+}
+
+#[test]
+fn word_motions_count_display_columns() {
+    let mut ed = editor_with(&["日本 語x", "e\u{301}te\u{301} a"]);
+
+    assert_eq!(positions(&mut ed, "wee"), vec![(0, 5), (0, 7), (1, 2)]);
+    assert_eq!(positions(&mut ed, "wb"), vec![(1, 4), (1, 0)]);
+}
+
+#[test]
+fn line_motions() {
+    let mut ed = editor_with(&["  indented line"]);
+
+    assert_eq!(positions(&mut ed, "$^0"), vec![(0, 14), (0, 2), (0, 0)]);
+}
+
+#[test]
+fn document_motions() {
+    let mut ed = editor_with(&["one", "  two", "  three"]);
+
+    assert_eq!(positions(&mut ed, "G"), vec![(2, 2)]);
+
+    input_text(&mut ed, "gg");
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn paragraph_motions() {
+    let mut ed = editor_with(&["a", "b", "", "", "c", "d"]);
+
+    assert_eq!(positions(&mut ed, "}}}"), vec![(2, 0), (5, 0), (5, 0)]);
+    assert_eq!(positions(&mut ed, "{{"), vec![(3, 0), (0, 0)]);
+}
+
+#[test]
+fn matching_brackets() {
+    let mut ed = editor_with(&["x = (a[1] + {", "  b })"]);
+
+    assert_eq!(positions(&mut ed, "%"), vec![(1, 5)]);
+    assert_eq!(positions(&mut ed, "%"), vec![(0, 4)]);
+
+    input_text(&mut ed, "l%");
+    assert_eq!(ed.cursor(), (0, 8));
+}
+
+#[test]
+fn find_char_motions() {
+    let mut ed = editor_with(&["a,b,c,d"]);
+
+    assert_eq!(positions(&mut ed, "f,"), vec![(0, 0), (0, 1)]);
+    assert_eq!(positions(&mut ed, ";;,"), vec![(0, 3), (0, 5), (0, 3)]);
+
+    input_text(&mut ed, "0t,");
+    assert_eq!(ed.cursor(), (0, 0));
+
+    input_text(&mut ed, "lt,");
+    assert_eq!(ed.cursor(), (0, 2));
+    input_text(&mut ed, ";");
+    assert_eq!(ed.cursor(), (0, 4));
+
+    input_text(&mut ed, "$F,");
+    assert_eq!(ed.cursor(), (0, 5));
+    input_text(&mut ed, "T,");
+    assert_eq!(ed.cursor(), (0, 4));
+}
+
+#[test]
+fn find_char_waits_for_its_argument() {
+    let mut ed = editor_with(&["abc"]);
+
+    input_text(&mut ed, "f");
+    assert_eq!(ed.pending_keys(), "f");
+
+    input_text(&mut ed, "c");
+    assert_eq!(ed.pending_keys(), "");
+    assert_eq!(ed.cursor(), (0, 2));
+}
+
+#[test]
+fn find_char_matches_wide_chars() {
+    let mut ed = editor_with(&["日本語"]);
+
+    input_text(&mut ed, "f語");
+
+    assert_eq!(ed.cursor(), (0, 4));
+}
@@ -0,0 +1,177 @@
+extern crate king;
+
+use king::input::{Key, KeyCode};
+use king::mode::Mode;
+
+mod common;
+use common::{input_text, editor_with, lines};
+
+#[test]
+fn operators_wait_for_a_motion() {
+    let mut ed = editor_with(&["foo bar"]);
+
+    input_text(&mut ed, "2d");
+    assert_eq!(ed.mode(), Mode::OperatorPending);
+    assert_eq!(ed.pending_keys(), "2d");
+
+    input_text(&mut ed, "3");
+    assert_eq!(ed.pending_keys(), "2d3");
+
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(ed.mode(), Mode::Normal);
+    assert_eq!(ed.pending_keys(), "");
+    assert_eq!(lines(&ed), vec!["foo bar"]);
+}
+
+#[test]
+fn delete_with_motions() {
+    let mut ed = editor_with(&["foo bar baz", "qux"]);
+
+    input_text(&mut ed, "dw");
+    assert_eq!(lines(&ed), vec!["bar baz", "qux"]);
+    assert_eq!(ed.register('"').unwrap(), "foo ");
+
+    input_text(&mut ed, "de");
+    assert_eq!(lines(&ed), vec![" baz", "qux"]);
+
+    input_text(&mut ed, "wdw");
+    assert_eq!(lines(&ed), vec![" ", "qux"]);
+
+    input_text(&mut ed, "jd$");
+    assert_eq!(lines(&ed), vec![" ", ""]);
+}
+
+#[test]
+fn exclusive_and_inclusive_finds() {
+    let mut ed = editor_with(&["a,b,c,d"]);
+
+    input_text(&mut ed, "dt,");
+    assert_eq!(lines(&ed), vec![",b,c,d"]);
+
+    input_text(&mut ed, "ld2f,");
+    assert_eq!(lines(&ed), vec![",d"]);
+
+    input_text(&mut ed, "$dF,");
+    assert_eq!(lines(&ed), vec!["d"]);
+}
+
+#[test]
+fn counts_multiply() {
+    let mut ed = editor_with(&["a b c d e f g h"]);
+
+    input_text(&mut ed, "2d3w");
+
+    assert_eq!(lines(&ed), vec!["g h"]);
+}
+
+#[test]
+fn doubled_operators_work_on_lines() {
+    let mut ed = editor_with(&["one", "two", "three", "four"]);
+
+    input_text(&mut ed, "jdd");
+    assert_eq!(lines(&ed), vec!["one", "three", "four"]);
+    assert_eq!(ed.register('"').unwrap(), "two\n");
+
+    input_text(&mut ed, "2dd");
+    assert_eq!(lines(&ed), vec!["one"]);
+    assert_eq!(ed.register('"').unwrap(), "three\nfour\n");
+    assert_eq!(ed.cursor(), (0, 0));
+
+    input_text(&mut ed, "dd");
+    assert_eq!(lines(&ed), vec![""]);
+}
+
+#[test]
+fn linewise_motions() {
+    let mut ed = editor_with(&["one", "two", "three", "four"]);
+
+    input_text(&mut ed, "jdj");
+    assert_eq!(lines(&ed), vec!["one", "four"]);
+
+    input_text(&mut ed, "dgg");
+    assert_eq!(lines(&ed), vec![""]);
+}
+
+#[test]
+fn paragraph_motion_from_the_indent_is_linewise() {
+    let mut ed = editor_with(&["a", "b", "", "c"]);
+
+    input_text(&mut ed, "d}");
+
+    assert_eq!(lines(&ed), vec!["", "c"]);
+}
+
+#[test]
+fn change_enters_insert_mode() {
+    let mut ed = editor_with(&["foo bar", "baz"]);
+
+    input_text(&mut ed, "cwx");
+    assert_eq!(ed.mode(), Mode::Insert);
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(lines(&ed), vec!["x bar", "baz"]);
+
+    input_text(&mut ed, "ww");
+    input_text(&mut ed, "cc");
+    input_text(&mut ed, "new");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(lines(&ed), vec!["x bar", "new"]);
+
+    input_text(&mut ed, "u");
+    assert_eq!(lines(&ed), vec!["x bar", "baz"]);
+}
+
+#[test]
+fn change_at_the_end_of_a_line() {
+    let mut ed = editor_with(&["foo bar"]);
+
+    input_text(&mut ed, "wc$baz");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    assert_eq!(lines(&ed), vec!["foo baz"]);
+}
+
+#[test]
+fn yank_keeps_the_text() {
+    let mut ed = editor_with(&["foo bar", "baz"]);
+
+    input_text(&mut ed, "wyb");
+    assert_eq!(ed.register('"').unwrap(), "foo ");
+    assert_eq!(ed.cursor(), (0, 0));
+
+    input_text(&mut ed, "jyk");
+    assert_eq!(ed.register('"').unwrap(), "foo bar\nbaz\n");
+    assert_eq!(lines(&ed), vec!["foo bar", "baz"]);
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn shifting_lines() {
+    let mut ed = editor_with(&["a", "", "b"]);
+
+    input_text(&mut ed, ":set sw=2\r");
+    input_text(&mut ed, ">G");
+    assert_eq!(lines(&ed), vec!["  a", "", "  b"]);
+    assert_eq!(ed.cursor(), (0, 2));
+
+    input_text(&mut ed, ">>");
+    input_text(&mut ed, "3<<");
+    assert_eq!(lines(&ed), vec!["  a", "", "b"]);
+}
+
+#[test]
+fn changing_case() {
+    let mut ed = editor_with(&["Hello World", "abc"]);
+
+    input_text(&mut ed, "gUw");
+    assert_eq!(lines(&ed), vec!["HELLO World", "abc"]);
+
+    input_text(&mut ed, "g~~");
+    assert_eq!(lines(&ed), vec!["hello wORLD", "abc"]);
+
+    input_text(&mut ed, "jguu");
+    input_text(&mut ed, "gUgU");
+    assert_eq!(lines(&ed), vec!["hello wORLD", "ABC"]);
+
+    input_text(&mut ed, "kgu$");
+    assert_eq!(lines(&ed), vec!["hello world", "ABC"]);
+}
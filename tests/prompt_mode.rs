@@ -1,7 +1,7 @@
 extern crate king;
 
 use king::editor::Editor;
-use king::input::Key;
+use king::input::{Key, KeyCode};
 use king::mode::Mode;
 
 mod common;
@@ -21,7 +21,7 @@ fn deleting_text() {
     let mut ed = Editor::new(10, 10);
 
     input_text(&mut ed, ":abc");
-    ed.handle_key(&Key::Backspace).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
 
     assert_eq!(ed.prompt().command_text(), ":ab");
 }
@@ -31,8 +31,8 @@ fn deleting_last_character() {
     let mut ed = Editor::new(10, 10);
 
     input_text(&mut ed, ":a");
-    ed.handle_key(&Key::Backspace).unwrap();
-    ed.handle_key(&Key::Backspace).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
 
     assert_eq!(ed.mode(), Mode::Normal);
     assert_eq!(ed.prompt().command_text(), "");
@@ -43,8 +43,8 @@ fn deleting_last_character() {
 fn leave_prompt_with_escape() {
     let mut ed = Editor::new(10, 10);
 
-    ed.handle_key(&Key::Char(':')).unwrap();
-    ed.handle_key(&Key::Esc).unwrap();
+    ed.handle_key(&Key::from(':')).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
 
     assert_eq!(ed.mode(), Mode::Normal);
     assert_eq!(ed.prompt().command_text(), "");
@@ -56,7 +56,7 @@ fn running_command_to_quit() {
     let mut ed = Editor::new(10, 10);
 
     input_text(&mut ed, ":quit");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
 
-    assert_eq!(ed.running(), false);
+    assert!(!ed.running());
 }
@@ -0,0 +1,139 @@
+extern crate king;
+
+use std::fs;
+
+use king::editor::Editor;
+use king::error::Error;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, run};
+
+fn modified_editor() -> Editor {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "text");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    ed
+}
+
+#[test]
+fn quit_refuses_when_there_are_unsaved_changes() {
+    let mut ed = modified_editor();
+
+    match run(&mut ed, ":quit") {
+        Err(Error::UnsavedChanges) => {},
+        _ => panic!("expected UnsavedChanges"),
+    }
+    assert!(ed.running());
+}
+
+#[test]
+fn quit_with_bang_discards_changes() {
+    let mut ed = modified_editor();
+
+    run(&mut ed, ":quit!").unwrap();
+
+    assert!(!ed.running());
+}
+
+#[test]
+fn undoing_all_changes_allows_quitting() {
+    let mut ed = modified_editor();
+
+    input_text(&mut ed, "u");
+    run(&mut ed, ":quit").unwrap();
+
+    assert!(!ed.running());
+}
+
+#[test]
+fn reloading_refuses_when_there_are_unsaved_changes() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "x");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    match run(&mut ed, ":edit") {
+        Err(Error::UnsavedChanges) => {},
+        _ => panic!("expected UnsavedChanges"),
+    }
+    assert_eq!(ed.window().content_view().line(0), "xline 1 on file 2");
+}
+
+#[test]
+fn editing_another_file_keeps_the_changes() {
+    let mut ed = modified_editor();
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    assert_eq!(ed.window().filename(), Some("tests/fixtures/file2".to_owned()));
+
+    run(&mut ed, ":buffer 1").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "text");
+}
+
+#[test]
+fn quit_refuses_when_a_hidden_buffer_has_unsaved_changes() {
+    let mut ed = modified_editor();
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+
+    match run(&mut ed, ":quit") {
+        Err(Error::UnsavedChangesInBuffer(1)) => {},
+        _ => panic!("expected UnsavedChangesInBuffer"),
+    }
+    assert!(ed.running());
+}
+
+#[test]
+fn edit_with_bang_and_no_file_name_reloads_the_file() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":edit tests/fixtures/file2").unwrap();
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "x");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":edit!").unwrap();
+
+    assert_eq!(ed.window().content_view().line(0), "line 1 on file 2");
+}
+
+#[test]
+fn write_quit_saves_and_quits() {
+    let filename = "target/write_quit_saves_and_quits";
+    let _ = fs::remove_file(filename);
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "saved");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":wq").unwrap();
+
+    assert!(!ed.running());
+    assert_eq!(fs::read_to_string(filename).unwrap(), "saved\n");
+}
+
+#[test]
+fn x_quits_without_writing_when_unmodified() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":x").unwrap();
+
+    assert!(!ed.running());
+}
+
+#[test]
+fn x_writes_when_modified() {
+    let mut ed = modified_editor();
+
+    match run(&mut ed, ":x") {
+        Err(Error::NoFileName) => {},
+        _ => panic!("expected NoFileName"),
+    }
+    assert!(ed.running());
+}
@@ -0,0 +1,254 @@
+extern crate king;
+
+use std::fs;
+
+use king::error::Error;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, editor_with, lines, run};
+
+#[test]
+fn line_numbers() {
+    let mut ed = editor_with(&["1", "2", "3", "4"]);
+
+    run(&mut ed, ":2,3d").unwrap();
+
+    assert_eq!(lines(&ed), vec!["1", "4"]);
+}
+
+#[test]
+fn the_cursor_line_and_the_last_one() {
+    let mut ed = editor_with(&["1", "2", "3", "4"]);
+
+    input_text(&mut ed, "j");
+    run(&mut ed, ":.,$d").unwrap();
+
+    assert_eq!(lines(&ed), vec!["1"]);
+}
+
+#[test]
+fn every_line() {
+    let mut ed = editor_with(&["1", "2"]);
+
+    run(&mut ed, ":%y").unwrap();
+
+    assert_eq!(ed.register('"'), Some("1\n2\n".to_owned()));
+}
+
+#[test]
+fn offsets() {
+    let mut ed = editor_with(&["1", "2", "3", "4", "5", "6"]);
+
+    input_text(&mut ed, "jj");
+    run(&mut ed, ":.+2d").unwrap();
+    assert_eq!(lines(&ed), vec!["1", "2", "3", "4", "6"]);
+
+    input_text(&mut ed, "gg");
+    run(&mut ed, ":+,+2d").unwrap();
+    assert_eq!(lines(&ed), vec!["1", "4", "6"]);
+
+    run(&mut ed, ":$-1d").unwrap();
+    assert_eq!(lines(&ed), vec!["1", "6"]);
+}
+
+#[test]
+fn pattern_addresses() {
+    let mut ed = editor_with(&["one", "two", "three", "four"]);
+
+    run(&mut ed, ":/three/d").unwrap();
+    assert_eq!(lines(&ed), vec!["one", "two", "four"]);
+
+    run(&mut ed, ":?one?+1,/four/-1d").unwrap();
+    assert_eq!(lines(&ed), vec!["one", "four"]);
+}
+
+#[test]
+fn pattern_addresses_become_the_last_search() {
+    let mut ed = editor_with(&["a", "b", "a"]);
+
+    run(&mut ed, ":/a/y").unwrap();
+    input_text(&mut ed, "gg");
+    input_text(&mut ed, "n");
+
+    assert_eq!(ed.cursor(), (2, 0));
+}
+
+#[test]
+fn the_selection_marks() {
+    let mut ed = editor_with(&["1", "2", "3", "4"]);
+
+    input_text(&mut ed, "jVj");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":'>,$d").unwrap();
+    assert_eq!(lines(&ed), vec!["1", "2"]);
+
+    match run(&mut ed, ":'a,'bd") {
+        Err(Error::MarkNotSet) => (),
+        _ => panic!("expected a mark not set error"),
+    }
+}
+
+#[test]
+fn marks_set_with_m() {
+    let mut ed = editor_with(&["1", "2", "3", "4", "5"]);
+
+    input_text(&mut ed, "jmajjmbgg");
+    run(&mut ed, ":'a,'bd").unwrap();
+    assert_eq!(lines(&ed), vec!["1", "5"]);
+
+    match run(&mut ed, ":'c,'bd") {
+        Err(Error::MarkNotSet) => (),
+        _ => panic!("expected a mark not set error"),
+    }
+
+    match ed.handle_key(&Key::from(KeyCode::Char('m'))).and_then(|_| ed.handle_key(&Key::from(KeyCode::Char('A')))) {
+        Err(Error::InvalidMark('A')) => (),
+        _ => panic!("expected an invalid mark error"),
+    }
+}
+
+#[test]
+fn the_second_address_counts_from_the_first_after_a_semicolon() {
+    let mut ed = editor_with(&["a", "x", "a", "b", "a"]);
+
+    input_text(&mut ed, "G");
+    run(&mut ed, ":2;/a/d").unwrap();
+    assert_eq!(lines(&ed), vec!["a", "b", "a"]);
+
+    run(&mut ed, ":1;+1d").unwrap();
+    assert_eq!(lines(&ed), vec!["a"]);
+}
+
+#[test]
+fn backward_ranges_are_swapped() {
+    let mut ed = editor_with(&["1", "2", "3", "4"]);
+
+    run(&mut ed, ":3,2d").unwrap();
+
+    assert_eq!(lines(&ed), vec!["1", "4"]);
+}
+
+#[test]
+fn going_to_a_line() {
+    let mut ed = editor_with(&["1", "  2", "3"]);
+
+    run(&mut ed, ":2").unwrap();
+    assert_eq!(ed.cursor(), (1, 2));
+
+    run(&mut ed, ":$").unwrap();
+    assert_eq!(ed.cursor(), (2, 0));
+
+    run(&mut ed, ":0").unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn invalid_ranges() {
+    let mut ed = editor_with(&["1", "2"]);
+
+    match run(&mut ed, ":3d") {
+        Err(Error::InvalidRange) => (),
+        _ => panic!("expected an invalid range error"),
+    }
+
+    match run(&mut ed, ":-d") {
+        Err(Error::InvalidRange) => (),
+        _ => panic!("expected an invalid range error"),
+    }
+
+    match run(&mut ed, ":1,2quit") {
+        Err(Error::NoRangeAllowed) => (),
+        _ => panic!("expected a no range allowed error"),
+    }
+
+    assert_eq!(lines(&ed), vec!["1", "2"]);
+}
+
+#[test]
+fn substituting_in_a_range() {
+    let mut ed = editor_with(&["x", "x", "x", "x"]);
+
+    run(&mut ed, ":2,3s/x/y/").unwrap();
+
+    assert_eq!(lines(&ed), vec!["x", "y", "y", "x"]);
+}
+
+#[test]
+fn writing_part_of_the_buffer() {
+    let filename = "target/writing_part_of_the_buffer";
+    let _ = fs::remove_file(filename);
+    let mut ed = editor_with(&["1", "2", "3", "4"]);
+
+    run(&mut ed, &format!(":2,3write {}", filename)).unwrap();
+    assert_eq!(fs::read_to_string(filename).unwrap(), "2\n3\n");
+    assert!(ed.window().is_modified());
+
+    run(&mut ed, &format!(":write {}", filename)).unwrap();
+    assert_eq!(fs::read_to_string(filename).unwrap(), "1\n2\n3\n4\n");
+
+    match run(&mut ed, ":2write") {
+        Err(Error::PartialWrite) => (),
+        _ => panic!("expected a partial write error"),
+    }
+}
+
+#[test]
+fn typing_normal_mode_keys_on_each_line() {
+    let mut ed = editor_with(&["a", "b", "c"]);
+
+    run(&mut ed, ":1,2normal i- ").unwrap();
+    assert_eq!(lines(&ed), vec!["- a", "- b", "c"]);
+
+    run(&mut ed, ":normal dd").unwrap();
+    assert_eq!(lines(&ed), vec!["- a", "c"]);
+
+    input_text(&mut ed, "u");
+    assert_eq!(lines(&ed), vec!["- a", "- b", "c"]);
+}
+
+#[test]
+fn sorting_lines() {
+    let mut ed = editor_with(&["b", "C", "a"]);
+
+    run(&mut ed, ":sort").unwrap();
+    assert_eq!(lines(&ed), vec!["C", "a", "b"]);
+
+    run(&mut ed, ":sort i").unwrap();
+    assert_eq!(lines(&ed), vec!["a", "b", "C"]);
+
+    run(&mut ed, ":2,3sort!").unwrap();
+    assert_eq!(lines(&ed), vec!["a", "b", "C"]);
+
+    run(&mut ed, ":%sort!").unwrap();
+    assert_eq!(lines(&ed), vec!["b", "a", "C"]);
+}
+
+#[test]
+fn filtering_lines_through_a_shell_command() {
+    let mut ed = editor_with(&["c", "b", "a", "d"]);
+
+    run(&mut ed, ":1,3!sort").unwrap();
+    assert_eq!(lines(&ed), vec!["a", "b", "c", "d"]);
+
+    run(&mut ed, ":.,+1!tr a-z A-Z").unwrap();
+    assert_eq!(lines(&ed), vec!["A", "B", "c", "d"]);
+
+    input_text(&mut ed, "u");
+    assert_eq!(lines(&ed), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn running_a_shell_command() {
+    let mut ed = editor_with(&["one"]);
+
+    run(&mut ed, ":!echo hello").unwrap();
+    assert_eq!(ed.prompt().text(), "hello");
+
+    match run(&mut ed, ":!echo failed >&2; false") {
+        Err(Error::ShellError(ref message)) if message == "failed" => (),
+        _ => panic!("expected a shell error"),
+    }
+
+    assert_eq!(lines(&ed), vec!["one"]);
+}
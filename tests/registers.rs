@@ -0,0 +1,213 @@
+extern crate king;
+
+use king::error::Error;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, editor_with, lines, run};
+
+#[test]
+fn yanking_into_a_named_register() {
+    let mut ed = editor_with(&["foo bar", "baz"]);
+
+    input_text(&mut ed, "\"ayw");
+    assert_eq!(ed.pending_keys(), "");
+    input_text(&mut ed, "j\"ap");
+
+    assert_eq!(lines(&ed), vec!["foo bar", "bfoo az"]);
+    assert_eq!(ed.register('a').unwrap(), "foo ");
+    assert_eq!(ed.register('"').unwrap(), "foo ");
+    assert_eq!(ed.cursor(), (1, 4));
+}
+
+#[test]
+fn the_register_shows_while_pending() {
+    let mut ed = editor_with(&["foo"]);
+
+    input_text(&mut ed, "\"");
+    assert_eq!(ed.pending_keys(), "\"");
+    input_text(&mut ed, "a2d");
+    assert_eq!(ed.pending_keys(), "\"a2d");
+}
+
+#[test]
+fn uppercase_names_append() {
+    let mut ed = editor_with(&["foo bar", "baz"]);
+
+    input_text(&mut ed, "\"ayww\"Ayw");
+    assert_eq!(ed.register('a').unwrap(), "foo bar");
+
+    input_text(&mut ed, "j\"Ayy");
+    assert_eq!(ed.register('a').unwrap(), "foo bar\nbaz\n");
+
+    input_text(&mut ed, "\"ap");
+    assert_eq!(lines(&ed), vec!["foo bar", "baz", "foo bar", "baz"]);
+}
+
+#[test]
+fn deleted_lines_shift_through_the_numbered_registers() {
+    let mut ed = editor_with(&["one", "two", "three", "four"]);
+
+    input_text(&mut ed, "dddd");
+    assert_eq!(ed.register('1').unwrap(), "two\n");
+    assert_eq!(ed.register('2').unwrap(), "one\n");
+
+    input_text(&mut ed, "dw");
+    assert_eq!(ed.register('-').unwrap(), "three");
+    assert_eq!(ed.register('1').unwrap(), "two\n");
+
+    input_text(&mut ed, "\"2p");
+    assert_eq!(lines(&ed), vec!["", "one", "four"]);
+}
+
+#[test]
+fn yanks_stay_in_register_zero() {
+    let mut ed = editor_with(&["one", "two"]);
+
+    input_text(&mut ed, "yyjdd\"0P");
+
+    assert_eq!(lines(&ed), vec!["one", "one"]);
+    assert_eq!(ed.register('"').unwrap(), "two\n");
+}
+
+#[test]
+fn the_black_hole_register_keeps_nothing() {
+    let mut ed = editor_with(&["one", "two"]);
+
+    input_text(&mut ed, "yy\"_dd");
+
+    assert_eq!(lines(&ed), vec!["two"]);
+    assert_eq!(ed.register('"').unwrap(), "one\n");
+    assert_eq!(ed.register('_'), None);
+}
+
+#[test]
+fn putting_lines() {
+    let mut ed = editor_with(&["one", "  two", "three"]);
+
+    input_text(&mut ed, "jyyP");
+    assert_eq!(lines(&ed), vec!["one", "  two", "  two", "three"]);
+    assert_eq!(ed.cursor(), (1, 2));
+
+    input_text(&mut ed, "G2p");
+    assert_eq!(lines(&ed), vec!["one", "  two", "  two", "three", "  two", "  two"]);
+    assert_eq!(ed.cursor(), (4, 2));
+}
+
+#[test]
+fn putting_chars_with_a_count() {
+    let mut ed = editor_with(&["ab"]);
+
+    input_text(&mut ed, "yl3p");
+    assert_eq!(lines(&ed), vec!["aaaab"]);
+    assert_eq!(ed.cursor(), (0, 3));
+
+    input_text(&mut ed, "$P");
+    assert_eq!(lines(&ed), vec!["aaaaab"]);
+}
+
+#[test]
+fn putting_chars_across_lines() {
+    let mut ed = editor_with(&["foo bar", "baz"]);
+
+    input_text(&mut ed, "wd$vjy0P");
+
+    assert_eq!(lines(&ed), vec![" ", "bazfoo ", "baz"]);
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn putting_a_block() {
+    let mut ed = editor_with(&["abc", "defg", "h"]);
+
+    input_text(&mut ed, "\x16jly");
+    assert_eq!(ed.register('"').unwrap(), "ab\nde");
+
+    input_text(&mut ed, "jp");
+    assert_eq!(lines(&ed), vec!["abc", "dabefg", "hde"]);
+    assert_eq!(ed.cursor(), (1, 1));
+
+    input_text(&mut ed, "G$p");
+    assert_eq!(lines(&ed), vec!["abc", "dabefg", "hdeab", "   de"]);
+}
+
+#[test]
+fn putting_a_block_pads_short_rows() {
+    let mut ed = editor_with(&["a", "bcd", "xyz", "xyz"]);
+
+    input_text(&mut ed, "\x16j$y");
+    input_text(&mut ed, "jjP");
+
+    assert_eq!(lines(&ed), vec!["a", "bcd", "a  xyz", "bcdxyz"]);
+}
+
+#[test]
+fn inserting_a_register() {
+    let mut ed = editor_with(&["foo bar"]);
+
+    input_text(&mut ed, "\"byiw$a \x12");
+    assert_eq!(ed.pending_keys(), "<C-r>");
+    input_text(&mut ed, "b!");
+
+    assert_eq!(lines(&ed), vec!["foo bar foo!"]);
+}
+
+#[test]
+fn inserting_lines() {
+    let mut ed = editor_with(&["one", "two"]);
+
+    input_text(&mut ed, "yyj");
+    input_text(&mut ed, "i\x12\"x");
+
+    assert_eq!(lines(&ed), vec!["one", "one", "xtwo"]);
+}
+
+#[test]
+fn the_last_insert_and_command_line() {
+    let mut ed = editor_with(&["one"]);
+
+    input_text(&mut ed, "otwo\rthrex\x7fe");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":set sw=4").unwrap();
+
+    assert_eq!(ed.register('.').unwrap(), "two\nthree");
+    assert_eq!(ed.register(':').unwrap(), "set sw=4");
+}
+
+#[test]
+fn read_only_registers_cannot_be_written() {
+    let mut ed = editor_with(&["one"]);
+
+    input_text(&mut ed, "\".");
+    assert!(ed.handle_key(&Key::from(KeyCode::Char('y'))).is_ok());
+    match ed.handle_key(&Key::from(KeyCode::Char('y'))) {
+        Err(Error::InvalidRegister('.')) => (),
+        _ => panic!("expected an invalid register error"),
+    }
+
+    assert_eq!(ed.register('"'), None);
+}
+
+#[test]
+fn putting_an_empty_register() {
+    let mut ed = editor_with(&["one"]);
+
+    input_text(&mut ed, "\"z");
+    match ed.handle_key(&Key::from(KeyCode::Char('p'))) {
+        Err(Error::EmptyRegister('z')) => (),
+        _ => panic!("expected an empty register error"),
+    }
+
+    assert_eq!(lines(&ed), vec!["one"]);
+}
+
+#[test]
+fn listing_registers() {
+    let mut ed = editor_with(&["one", "two"]);
+
+    input_text(&mut ed, "\"ayyjyiw");
+    run(&mut ed, ":registers").unwrap();
+
+    assert_eq!(ed.prompt().text(),
+               "Type Name Content\n  c  \"\"   two\n  c  \"0   two\n  l  \"a   one^J\n  c  \".   one^Jtwo\n  c  \":   registers");
+}
@@ -0,0 +1,121 @@
+extern crate king;
+
+use king::backend::{Attributes, Backend};
+use king::editor::Editor;
+use king::headless::Headless;
+use king::input::{self, Key, KeyCode};
+
+mod common;
+use common::{input_text, render};
+
+fn insert(ed: &mut Editor, text: &str) {
+    input_text(ed, "i");
+    input_text(ed, text);
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+}
+
+#[test]
+fn empty_lines_after_the_end_of_the_buffer_show_tildes() {
+    let mut ed = Editor::new(5, 10);
+
+    insert(&mut ed, "abc");
+
+    assert_eq!(render(&ed).rows(), vec!["abc", "~", "~", "~", ""]);
+}
+
+#[test]
+fn the_cursor_is_placed_on_the_screen() {
+    let mut ed = Editor::new(5, 10);
+
+    insert(&mut ed, "abc");
+    let screen = render(&ed);
+
+    assert_eq!(screen.cursor(), (0, 2));
+}
+
+#[test]
+fn wide_chars_take_two_cells() {
+    let mut ed = Editor::new(3, 10);
+
+    insert(&mut ed, "a界b");
+    let screen = render(&ed);
+
+    assert_eq!(screen.row(0), "a界b");
+    assert_eq!(screen.cell(0, 1).grapheme(), "界");
+    assert_eq!(screen.cell(0, 1).width(), 2);
+    assert_eq!(screen.cell(0, 2).grapheme(), "");
+    assert_eq!(screen.cell(0, 3).grapheme(), "b");
+}
+
+#[test]
+fn long_lines_are_clipped_before_a_wide_char_that_doesnt_fit() {
+    let mut ed = Editor::new(3, 10);
+
+    insert(&mut ed, "abcdefghi界");
+    let screen = render(&ed);
+
+    assert_eq!(screen.row(0), "abcdefghi");
+    assert_eq!(screen.cell(0, 9).grapheme(), " ");
+}
+
+#[test]
+fn undecodable_bytes_are_shown_as_escapes() {
+    let mut ed = Editor::new(3, 20);
+
+    input_text(&mut ed, ":edit tests/fixtures/file_with_invalid_byte");
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+
+    assert_eq!(render(&ed).row(0), "café <80>");
+}
+
+#[test]
+fn split_windows_have_status_lines_and_separators() {
+    let mut ed = Editor::new(6, 11);
+
+    input_text(&mut ed, ":vsplit");
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+    let screen = render(&ed);
+
+    assert_eq!(screen.rows(), vec![
+        "     |",
+        "~    |~",
+        "~    |~",
+        "~    |~",
+        " [No | [No",
+        "",
+    ]);
+    assert_eq!(screen.cell(4, 0).attributes(), Attributes { reverse: true, bold: true });
+    assert_eq!(screen.cell(4, 6).attributes(), Attributes { reverse: true, bold: false });
+    assert_eq!(screen.cell(1, 5).attributes(), Attributes::reverse());
+}
+
+#[test]
+fn drawing_over_half_of_a_wide_char_blanks_the_other_half() {
+    let mut screen = Headless::new(1, 4);
+
+    screen.put(0, 0, "界", Attributes::normal());
+    screen.put(0, 1, "x", Attributes::normal());
+
+    assert_eq!(screen.row(0), " x");
+}
+
+#[test]
+fn keys_are_read_from_the_queue() {
+    let mut screen = Headless::new(1, 4);
+
+    screen.push_key(Key::from('a'));
+    screen.push_key(Key::from(KeyCode::Esc));
+
+    assert!(input::read_key(&mut screen, None) == Some(Key::from('a')));
+    assert!(input::read_key(&mut screen, None) == Some(Key::from(KeyCode::Esc)));
+    assert!(input::read_key(&mut screen, None).is_none());
+}
+
+#[test]
+fn pending_keys_are_shown_on_the_prompt_line() {
+    let mut ed = Editor::new(3, 20);
+
+    ed.handle_key(&Key::ctrl('w')).unwrap();
+
+    assert_eq!(render(&ed).row(2), "         <C-w>");
+}
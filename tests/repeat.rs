@@ -0,0 +1,127 @@
+extern crate king;
+
+mod common;
+use common::{input_text, editor_with, lines, esc};
+
+#[test]
+fn repeating_an_operator() {
+    let mut ed = editor_with(&["one two three four"]);
+
+    input_text(&mut ed, "dw.");
+
+    assert_eq!(lines(&ed), vec!["three four"]);
+}
+
+#[test]
+fn a_new_count_replaces_the_old_one() {
+    let mut ed = editor_with(&["a b c d e f g h"]);
+
+    input_text(&mut ed, "2d3w");
+    assert_eq!(lines(&ed), vec!["g h"]);
+
+    input_text(&mut ed, "u1.");
+    assert_eq!(lines(&ed), vec!["b c d e f g h"]);
+
+    input_text(&mut ed, ".");
+    assert_eq!(lines(&ed), vec!["c d e f g h"]);
+}
+
+#[test]
+fn the_count_is_kept() {
+    let mut ed = editor_with(&["1", "2", "3", "4", "5", "6", "7"]);
+
+    input_text(&mut ed, "3dd.");
+
+    assert_eq!(lines(&ed), vec!["7"]);
+}
+
+#[test]
+fn repeating_an_insert_session() {
+    let mut ed = editor_with(&["one", "two"]);
+
+    input_text(&mut ed, "ixy\x7fz");
+    esc(&mut ed);
+    input_text(&mut ed, "j0.");
+
+    assert_eq!(lines(&ed), vec!["xzone", "xztwo"]);
+}
+
+#[test]
+fn repeating_an_insert_session_with_a_count() {
+    let mut ed = editor_with(&["ab"]);
+
+    input_text(&mut ed, "a-");
+    esc(&mut ed);
+    input_text(&mut ed, "$3.");
+
+    assert_eq!(lines(&ed), vec!["a-b---"]);
+}
+
+#[test]
+fn repeating_opened_lines() {
+    let mut ed = editor_with(&["one"]);
+
+    input_text(&mut ed, "onew\rline");
+    esc(&mut ed);
+    input_text(&mut ed, ".");
+    assert_eq!(lines(&ed), vec!["one", "new", "line", "new", "line"]);
+
+    input_text(&mut ed, "gg2.");
+    assert_eq!(lines(&ed), vec!["one", "new", "line", "new", "line", "new", "line", "new", "line"]);
+}
+
+#[test]
+fn repeating_a_change() {
+    let mut ed = editor_with(&["f(a, b)", "g(c)"]);
+
+    input_text(&mut ed, "fac");
+    input_text(&mut ed, "i(x");
+    esc(&mut ed);
+    input_text(&mut ed, "j0f(.");
+
+    assert_eq!(lines(&ed), vec!["f(x)", "g(x)"]);
+}
+
+#[test]
+fn inserting_with_a_count() {
+    let mut ed = editor_with(&["abc"]);
+
+    input_text(&mut ed, "3ix");
+    esc(&mut ed);
+
+    assert_eq!(lines(&ed), vec!["xxxabc"]);
+}
+
+#[test]
+fn motions_yanks_and_undo_are_not_changes() {
+    let mut ed = editor_with(&["one two three four five"]);
+
+    input_text(&mut ed, "dwwywu.");
+
+    assert_eq!(lines(&ed), vec!["two three four five"]);
+
+    input_text(&mut ed, "w.");
+    assert_eq!(lines(&ed), vec!["two four five"]);
+}
+
+#[test]
+fn command_lines_are_not_repeated() {
+    let mut ed = editor_with(&["one two", "three", "four"]);
+
+    input_text(&mut ed, "dw");
+    input_text(&mut ed, ":d\r");
+    input_text(&mut ed, ".");
+
+    assert_eq!(lines(&ed), vec!["", "four"]);
+}
+
+#[test]
+fn repeating_a_put_and_a_visual_delete() {
+    let mut ed = editor_with(&["abcdef"]);
+
+    input_text(&mut ed, "ylp.");
+    assert_eq!(lines(&ed), vec!["aaabcdef"]);
+
+    input_text(&mut ed, "0vld.");
+    assert_eq!(lines(&ed), vec!["cdef"]);
+}
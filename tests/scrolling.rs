@@ -1,7 +1,7 @@
 extern crate king;
 
 use king::editor::Editor;
-use king::input::Key;
+use king::input::{Key, KeyCode};
 
 mod common;
 use common::input_text;
@@ -11,7 +11,7 @@ fn vertical_scroll() {
     let mut ed = Editor::new(3, 3);
 
     input_text(&mut ed, ":edit tests/fixtures/file_with_contents");
-    ed.handle_key(&Key::Enter).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
     assert_eq!(ed.cursor(), (0, 0));
     assert_eq!(ed.window().content_view().line(0), "line 1");
 
@@ -0,0 +1,346 @@
+extern crate king;
+
+use king::backend::Attributes;
+use king::error::Error;
+use king::input::{Key, KeyCode};
+use king::mode::Mode;
+
+mod common;
+use common::{input_text, editor_with, lines, run, render};
+
+#[test]
+fn searching_forward() {
+    let mut ed = editor_with(&["one two", "three two"]);
+
+    run(&mut ed, "/two").unwrap();
+    assert_eq!(ed.cursor(), (0, 4));
+
+    input_text(&mut ed, "n");
+    assert_eq!(ed.cursor(), (1, 6));
+}
+
+#[test]
+fn searching_backward() {
+    let mut ed = editor_with(&["one two", "three two"]);
+
+    input_text(&mut ed, "G$");
+    run(&mut ed, "?two").unwrap();
+    assert_eq!(ed.cursor(), (1, 6));
+
+    input_text(&mut ed, "n");
+    assert_eq!(ed.cursor(), (0, 4));
+
+    input_text(&mut ed, "N");
+    assert_eq!(ed.cursor(), (1, 6));
+}
+
+#[test]
+fn repeating_with_a_count() {
+    let mut ed = editor_with(&["a x x x x"]);
+
+    run(&mut ed, "/x").unwrap();
+    input_text(&mut ed, "2n");
+
+    assert_eq!(ed.cursor(), (0, 6));
+}
+
+#[test]
+fn searching_with_a_count() {
+    let mut ed = editor_with(&["a x x x x"]);
+
+    run(&mut ed, "3/x").unwrap();
+    assert_eq!(ed.cursor(), (0, 6));
+
+    run(&mut ed, "2?x").unwrap();
+    assert_eq!(ed.cursor(), (0, 2));
+}
+
+#[test]
+fn searching_backward_far_from_the_cursor() {
+    let mut text = vec!["a one"];
+    text.extend(vec!["xxxxxxxxxxxxxxx"; 500]);
+    text.extend(vec!["a two", "end"]);
+    let mut ed = editor_with(&text);
+
+    input_text(&mut ed, "G");
+    run(&mut ed, "?a").unwrap();
+    assert_eq!(ed.window().view().line(), 501);
+
+    input_text(&mut ed, "n");
+    assert_eq!(ed.window().view().line(), 0);
+
+    input_text(&mut ed, "n");
+    assert_eq!(ed.window().view().line(), 501);
+}
+
+#[test]
+fn searches_see_the_changes_made_since_the_last_one() {
+    let mut ed = editor_with(&["x", "y"]);
+
+    run(&mut ed, "/y").unwrap();
+    assert_eq!(ed.cursor(), (1, 0));
+
+    input_text(&mut ed, "ggoz");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "ggn");
+    assert_eq!(ed.cursor(), (2, 0));
+}
+
+#[test]
+fn wrapping_around_the_ends() {
+    let mut ed = editor_with(&["one", "two", "one"]);
+
+    input_text(&mut ed, "G");
+    run(&mut ed, "/one").unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+    assert_eq!(ed.prompt().text(), "search hit BOTTOM, continuing at TOP");
+
+    input_text(&mut ed, "N");
+    assert_eq!(ed.cursor(), (2, 0));
+    assert_eq!(ed.prompt().text(), "search hit TOP, continuing at BOTTOM");
+}
+
+#[test]
+fn smart_case() {
+    let mut ed = editor_with(&["Word word WORD"]);
+
+    run(&mut ed, "/word").unwrap();
+    assert_eq!(ed.cursor(), (0, 5));
+    input_text(&mut ed, "n");
+    assert_eq!(ed.cursor(), (0, 10));
+
+    run(&mut ed, "/Word").unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn matches_after_wide_characters() {
+    let mut ed = editor_with(&["漢字 abc"]);
+
+    run(&mut ed, "/b").unwrap();
+
+    assert_eq!(ed.cursor(), (0, 6));
+}
+
+#[test]
+fn an_empty_pattern_repeats_the_last_one() {
+    let mut ed = editor_with(&["a b a b"]);
+
+    run(&mut ed, "/b").unwrap();
+    run(&mut ed, "/").unwrap();
+    assert_eq!(ed.cursor(), (0, 6));
+
+    run(&mut ed, "?").unwrap();
+    assert_eq!(ed.cursor(), (0, 2));
+}
+
+#[test]
+fn patterns_that_are_not_found() {
+    let mut ed = editor_with(&["one"]);
+
+    match ed.handle_key(&Key::from(KeyCode::Char('n'))) {
+        Err(Error::NoPreviousPattern) => (),
+        _ => panic!("expected a no previous pattern error"),
+    }
+
+    match run(&mut ed, "/two") {
+        Err(Error::PatternNotFound(ref pattern)) if pattern == "two" => (),
+        _ => panic!("expected a pattern not found error"),
+    }
+}
+
+#[test]
+fn operators_work_up_to_the_match() {
+    let mut ed = editor_with(&["a b c b c"]);
+
+    run(&mut ed, "/c").unwrap();
+    input_text(&mut ed, "0dn");
+
+    assert_eq!(lines(&ed), vec!["c b c"]);
+}
+
+#[test]
+fn operators_work_up_to_a_search() {
+    let mut ed = editor_with(&["a b c b c x", "d"]);
+
+    run(&mut ed, "d/c").unwrap();
+    assert_eq!(lines(&ed), vec!["c b c x", "d"]);
+
+    run(&mut ed, "$\"ay?b").unwrap();
+    assert_eq!(ed.register('a'), Some("b c ".to_owned()));
+
+    run(&mut ed, "0d2/ ").unwrap();
+    assert_eq!(lines(&ed), vec![" c x", "d"]);
+
+    input_text(&mut ed, "d/x");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "j");
+    assert_eq!(ed.cursor(), (1, 0));
+    assert_eq!(lines(&ed), vec![" c x", "d"]);
+}
+
+#[test]
+fn regular_expressions() {
+    let mut ed = editor_with(&["abc a.c axc", "x12 y345"]);
+
+    run(&mut ed, "/a.c").unwrap();
+    assert_eq!(ed.cursor(), (0, 4));
+
+    run(&mut ed, "/\\d\\{3}").unwrap();
+    assert_eq!(ed.cursor(), (1, 5));
+
+    run(&mut ed, "/^a\\(bc\\|xc\\)").unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+
+    run(&mut ed, "/c$").unwrap();
+    assert_eq!(ed.cursor(), (0, 10));
+}
+
+#[test]
+fn very_magic_and_literal_patterns() {
+    let mut ed = editor_with(&["b aaa a.c (b)"]);
+
+    run(&mut ed, "/\\va{2,}").unwrap();
+    assert_eq!(ed.cursor(), (0, 2));
+
+    run(&mut ed, "/\\V.c").unwrap();
+    assert_eq!(ed.cursor(), (0, 7));
+
+    run(&mut ed, "/\\v\\(b\\)").unwrap();
+    assert_eq!(ed.cursor(), (0, 10));
+
+    run(&mut ed, "/\\<a\\>").unwrap();
+    assert_eq!(ed.cursor(), (0, 6));
+}
+
+#[test]
+fn overriding_smart_case() {
+    let mut ed = editor_with(&["word Word"]);
+
+    run(&mut ed, "/\\CWORD\\c").unwrap();
+    assert_eq!(ed.cursor(), (0, 5));
+
+    run(&mut ed, "/word\\C").unwrap();
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn patterns_spanning_lines() {
+    let mut ed = editor_with(&["one", "two", "", "three"]);
+
+    run(&mut ed, "/e\\nt").unwrap();
+    assert_eq!(ed.cursor(), (0, 2));
+
+    run(&mut ed, "/^\\n").unwrap();
+    assert_eq!(ed.cursor(), (2, 0));
+
+    run(&mut ed, "?e\\_s*t").unwrap();
+    assert_eq!(ed.cursor(), (0, 2));
+}
+
+#[test]
+fn regex_matches_after_wide_characters() {
+    let mut ed = editor_with(&["漢字 x1 x2"]);
+
+    run(&mut ed, "/x\\d").unwrap();
+    input_text(&mut ed, "n");
+
+    assert_eq!(ed.cursor(), (0, 8));
+}
+
+#[test]
+fn invalid_patterns() {
+    let mut ed = editor_with(&["one"]);
+
+    match run(&mut ed, "/\\(one") {
+        Err(Error::InvalidPattern(ref pattern)) if pattern == "\\(one" => (),
+        _ => panic!("expected an invalid pattern error"),
+    }
+}
+
+#[test]
+fn the_cursor_follows_the_pattern_being_typed() {
+    let mut ed = editor_with(&["one", "two", "three"]);
+
+    input_text(&mut ed, "/t");
+    assert_eq!(ed.window().cursor(), (1, 0));
+
+    input_text(&mut ed, "h");
+    assert_eq!(ed.window().cursor(), (2, 0));
+
+    input_text(&mut ed, "x");
+    assert_eq!(ed.window().cursor(), (0, 0));
+
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+    assert_eq!(ed.cursor(), (2, 0));
+}
+
+#[test]
+fn escape_goes_back_to_where_the_search_started() {
+    let numbers = (1..21).map(|n| n.to_string()).collect::<Vec<_>>();
+    let numbers = numbers.iter().map(|n| &n[..]).collect::<Vec<_>>();
+    let mut ed = editor_with(&numbers);
+
+    input_text(&mut ed, "jl/15");
+    assert_eq!(ed.window().top_line(), 6);
+    assert_eq!(ed.window().cursor(), (8, 0));
+
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(ed.window().top_line(), 0);
+    assert_eq!(ed.cursor(), (1, 0));
+}
+
+#[test]
+fn deleting_the_whole_search_goes_back() {
+    let mut ed = editor_with(&["one", "two"]);
+
+    input_text(&mut ed, "/tw");
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
+    assert_eq!(ed.window().cursor(), (1, 0));
+
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
+    assert_eq!(ed.mode(), Mode::Normal);
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn the_match_being_typed_is_highlighted() {
+    let mut ed = editor_with(&["abcb"]);
+
+    input_text(&mut ed, "/b");
+    let screen = render(&ed);
+
+    assert_eq!(screen.cell(0, 1).attributes(), Attributes { reverse: true, bold: true });
+    assert_eq!(screen.cell(0, 3).attributes(), Attributes::normal());
+}
+
+#[test]
+fn matches_stay_highlighted_until_nohlsearch() {
+    let mut ed = editor_with(&["a漢b", "cb"]);
+
+    run(&mut ed, "/b").unwrap();
+    let screen = render(&ed);
+    assert_eq!(screen.cell(0, 0).attributes(), Attributes::normal());
+    assert_eq!(screen.cell(0, 3).attributes(), Attributes::reverse());
+    assert_eq!(screen.cell(1, 1).attributes(), Attributes::reverse());
+
+    run(&mut ed, ":nohlsearch").unwrap();
+    assert_eq!(render(&ed).cell(0, 3).attributes(), Attributes::normal());
+
+    input_text(&mut ed, "n");
+    assert_eq!(render(&ed).cell(0, 3).attributes(), Attributes::reverse());
+}
+
+#[test]
+fn highlighted_line_breaks() {
+    let mut ed = editor_with(&["abc", "d"]);
+
+    run(&mut ed, "/c\\n").unwrap();
+    let screen = render(&ed);
+
+    assert_eq!(screen.cell(0, 2).attributes(), Attributes::reverse());
+    assert_eq!(screen.cell(0, 3).attributes(), Attributes::reverse());
+    assert_eq!(screen.cell(1, 0).attributes(), Attributes::normal());
+}
@@ -0,0 +1,220 @@
+extern crate king;
+
+use king::backend::Attributes;
+use king::error::Error;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, editor_with, lines, run, render};
+
+#[test]
+fn substituting_in_the_cursor_line() {
+    let mut ed = editor_with(&["a a a", "a"]);
+
+    run(&mut ed, ":s/a/b/").unwrap();
+    assert_eq!(lines(&ed), vec!["b a a", "a"]);
+
+    run(&mut ed, ":s/a/c/g").unwrap();
+    assert_eq!(lines(&ed), vec!["b c c", "a"]);
+}
+
+#[test]
+fn substituting_in_every_line() {
+    let mut ed = editor_with(&["one two", "two two", "three"]);
+
+    run(&mut ed, ":%s/two/2/g").unwrap();
+
+    assert_eq!(lines(&ed), vec!["one 2", "2 2", "three"]);
+    assert_eq!(ed.prompt().text(), "3 substitutions on 2 lines");
+    assert_eq!(ed.cursor(), (1, 0));
+}
+
+#[test]
+fn substituting_in_the_selected_lines() {
+    let mut ed = editor_with(&["x", "x", "x", "x"]);
+
+    input_text(&mut ed, "jVj:");
+    run(&mut ed, "s/x/y").unwrap();
+
+    assert_eq!(lines(&ed), vec!["x", "y", "y", "x"]);
+}
+
+#[test]
+fn other_delimiters_and_escaped_ones() {
+    let mut ed = editor_with(&["a/b"]);
+
+    run(&mut ed, ":s#/#\\##").unwrap();
+    assert_eq!(lines(&ed), vec!["a#b"]);
+
+    run(&mut ed, ":s/#/\\//").unwrap();
+    assert_eq!(lines(&ed), vec!["a/b"]);
+}
+
+#[test]
+fn ignoring_case() {
+    let mut ed = editor_with(&["Word word"]);
+
+    run(&mut ed, ":s/WORD/x/gi").unwrap();
+    assert_eq!(lines(&ed), vec!["x x"]);
+
+    let mut ed = editor_with(&["Word word"]);
+
+    run(&mut ed, ":s/word/x/gI").unwrap();
+    assert_eq!(lines(&ed), vec!["Word x"]);
+}
+
+#[test]
+fn counting_matches() {
+    let mut ed = editor_with(&["a b a", "a"]);
+
+    run(&mut ed, ":%s/a//gn").unwrap();
+
+    assert_eq!(lines(&ed), vec!["a b a", "a"]);
+    assert_eq!(ed.prompt().text(), "3 matches on 2 lines");
+}
+
+#[test]
+fn a_single_substitution() {
+    let mut ed = editor_with(&["a"]);
+
+    run(&mut ed, ":s/a/b/").unwrap();
+
+    assert_eq!(ed.prompt().text(), "1 substitution on 1 line");
+}
+
+#[test]
+fn capture_groups() {
+    let mut ed = editor_with(&["john smith"]);
+
+    run(&mut ed, ":s/\\(\\w\\+\\) \\(\\w\\+\\)/\\2, \\1 (&)/").unwrap();
+
+    assert_eq!(lines(&ed), vec!["smith, john (john smith)"]);
+}
+
+#[test]
+fn changing_case() {
+    let mut ed = editor_with(&["john smith", "JOHN SMITH"]);
+
+    run(&mut ed, ":s/\\v(\\w+) (\\w+)/\\u\\1 \\U\\2\\E!/").unwrap();
+    assert_eq!(lines(&ed)[0], "John SMITH!");
+
+    input_text(&mut ed, "j");
+    run(&mut ed, ":s/\\v(\\w+) (\\w+)/\\L\\1\\E \\l\\2/").unwrap();
+    assert_eq!(lines(&ed)[1], "john sMITH");
+}
+
+#[test]
+fn splitting_and_joining_lines() {
+    let mut ed = editor_with(&["a,b", "c"]);
+
+    run(&mut ed, ":s/,/\\r/").unwrap();
+    assert_eq!(lines(&ed), vec!["a", "b", "c"]);
+
+    run(&mut ed, ":%s/\\n//").unwrap();
+    assert_eq!(lines(&ed), vec!["abc"]);
+}
+
+#[test]
+fn undoing_all_the_substitutions_at_once() {
+    let mut ed = editor_with(&["a", "a", "a"]);
+
+    run(&mut ed, ":%s/a/b/").unwrap();
+    assert_eq!(lines(&ed), vec!["b", "b", "b"]);
+
+    input_text(&mut ed, "u");
+    assert_eq!(lines(&ed), vec!["a", "a", "a"]);
+}
+
+#[test]
+fn confirming_each_match() {
+    let mut ed = editor_with(&["a a a a"]);
+
+    run(&mut ed, ":s/a/b/gc").unwrap();
+    assert_eq!(ed.prompt().text(), "replace with b (y/n/a/q/l)?");
+    assert_eq!(ed.cursor(), (0, 0));
+
+    input_text(&mut ed, "yn");
+    assert_eq!(ed.cursor(), (0, 4));
+    assert_eq!(lines(&ed), vec!["a a a a"]);
+
+    input_text(&mut ed, "a");
+    assert_eq!(lines(&ed), vec!["b a b b"]);
+    assert_eq!(ed.prompt().text(), "3 substitutions on 1 line");
+}
+
+#[test]
+fn stopping_while_confirming() {
+    let mut ed = editor_with(&["a a a"]);
+
+    run(&mut ed, ":s/a/b/gc").unwrap();
+    input_text(&mut ed, "nl");
+    assert_eq!(lines(&ed), vec!["a b a"]);
+
+    run(&mut ed, ":s/a/b/gc").unwrap();
+    input_text(&mut ed, "yq");
+    assert_eq!(lines(&ed), vec!["b b a"]);
+
+    run(&mut ed, ":s/a/b/gc").unwrap();
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(lines(&ed), vec!["b b a"]);
+
+    input_text(&mut ed, "u");
+    assert_eq!(lines(&ed), vec!["a b a"]);
+}
+
+#[test]
+fn the_match_being_confirmed_is_highlighted() {
+    let mut ed = editor_with(&["a a"]);
+
+    run(&mut ed, ":s/a/b/gc").unwrap();
+    input_text(&mut ed, "n");
+    let screen = render(&ed);
+
+    assert_eq!(screen.cell(0, 0).attributes(), Attributes::reverse());
+    assert_eq!(screen.cell(0, 2).attributes(), Attributes { reverse: true, bold: true });
+}
+
+#[test]
+fn repeating_the_last_substitution() {
+    let mut ed = editor_with(&["a a", "a a"]);
+
+    run(&mut ed, ":s/a/b/g").unwrap();
+    input_text(&mut ed, "j");
+    run(&mut ed, ":s").unwrap();
+
+    assert_eq!(lines(&ed), vec!["b b", "b a"]);
+}
+
+#[test]
+fn an_empty_pattern_uses_the_last_search() {
+    let mut ed = editor_with(&["one two"]);
+
+    run(&mut ed, "/two").unwrap();
+    run(&mut ed, ":s//2/").unwrap();
+    assert_eq!(lines(&ed), vec!["one 2"]);
+
+    run(&mut ed, ":s/one/1/").unwrap();
+    input_text(&mut ed, "u0");
+    input_text(&mut ed, "n");
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn substitutions_that_cannot_be_done() {
+    let mut ed = editor_with(&["one"]);
+
+    match run(&mut ed, ":s") {
+        Err(Error::NoPreviousSubstitution) => (),
+        _ => panic!("expected a no previous substitution error"),
+    }
+
+    match run(&mut ed, ":s/two/2/") {
+        Err(Error::PatternNotFound(ref pattern)) if pattern == "two" => (),
+        _ => panic!("expected a pattern not found error"),
+    }
+
+    match run(&mut ed, ":s/one/1/x") {
+        Err(Error::InvalidArgument(_)) => (),
+        _ => panic!("expected an invalid argument error"),
+    }
+}
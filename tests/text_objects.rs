@@ -0,0 +1,114 @@
+extern crate king;
+
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, editor_with, lines};
+
+/// Runs `keys` on a fresh editor with the cursor moved by `motion` first,
+/// returning the lines and what was deleted or yanked.
+fn after(text: &[&str], motion: &str, keys: &str) -> (Vec<String>, String) {
+    let mut ed = editor_with(text);
+
+    input_text(&mut ed, motion);
+    input_text(&mut ed, keys);
+
+    (lines(&ed), ed.register('"').unwrap_or_default())
+}
+
+#[test]
+fn words() {
+    assert_eq!(after(&["foo bar baz"], "w", "diw"), (vec!["foo  baz".to_owned()], "bar".to_owned()));
+    assert_eq!(after(&["foo bar baz"], "w", "daw"), (vec!["foo baz".to_owned()], "bar ".to_owned()));
+    assert_eq!(after(&["foo bar"], "w", "daw"), (vec!["foo".to_owned()], " bar".to_owned()));
+    assert_eq!(after(&["foo bar baz"], "", "d3iw"), (vec![" baz".to_owned()], "foo bar".to_owned()));
+    assert_eq!(after(&["a.b c"], "", "yiW"), (vec!["a.b c".to_owned()], "a.b".to_owned()));
+    assert_eq!(after(&["a.b c"], "", "yiw"), (vec!["a.b c".to_owned()], "a".to_owned()));
+}
+
+#[test]
+fn words_are_grapheme_aware() {
+    assert_eq!(after(&["e\u{301}te\u{301} 日本"], "", "diw"), (vec![" 日本".to_owned()], "e\u{301}te\u{301}".to_owned()));
+    assert_eq!(after(&["ab 日本 cd"], "w", "daw"), (vec!["ab cd".to_owned()], "日本 ".to_owned()));
+}
+
+#[test]
+fn sentences() {
+    let text = ["One. Two three! Four?"];
+
+    assert_eq!(after(&text, "W", "dis").0, vec!["One.  Four?"]);
+    assert_eq!(after(&text, "W", "das").0, vec!["One. Four?"]);
+    assert_eq!(after(&text, "$", "das").0, vec!["One. Two three!"]);
+}
+
+#[test]
+fn sentences_span_lines() {
+    let text = ["One. Two", "three. Four", "", "Five."];
+
+    assert_eq!(after(&text, "W", "yis").1, "Two\nthree.");
+}
+
+#[test]
+fn paragraphs() {
+    let text = ["a", "b", "", "", "c"];
+
+    assert_eq!(after(&text, "", "dip").0, vec!["", "", "c"]);
+    assert_eq!(after(&text, "", "dap").0, vec!["c"]);
+    assert_eq!(after(&text, "G", "dap").0, vec!["a", "b"]);
+    assert_eq!(after(&text, "", "yap").1, "a\nb\n\n\n");
+}
+
+#[test]
+fn quotes() {
+    let text = ["say \"hi there\" now"];
+
+    assert_eq!(after(&text, "fh", "di\"").0, vec!["say \"\" now"]);
+    assert_eq!(after(&text, "fh", "da\"").0, vec!["say now"]);
+    assert_eq!(after(&text, "", "di\"").0, vec!["say \"\" now"]);
+    assert_eq!(after(&text, "f\"", "yi\"").1, "hi there");
+    assert_eq!(after(&["x = 'a\\'b'"], "fa", "di'").0, vec!["x = ''"]);
+    assert_eq!(after(&["`cmd`"], "", "ci`ls").0, vec!["`ls`"]);
+}
+
+#[test]
+fn brackets() {
+    let text = ["f(a, (b), c)"];
+
+    assert_eq!(after(&text, "fb", "di(").0, vec!["f(a, (), c)"]);
+    assert_eq!(after(&text, "fb", "d2i(").0, vec!["f()"]);
+    assert_eq!(after(&text, "fb", "da)").0, vec!["f(a, , c)"]);
+    assert_eq!(after(&text, "fa", "dab").0, vec!["f"]);
+    assert_eq!(after(&["[1, [2]]"], "$", "di]").0, vec!["[]"]);
+    assert_eq!(after(&["a<b>"], "fb", "di<").0, vec!["a<>"]);
+    assert_eq!(after(&["x"], "", "di(").0, vec!["x"]);
+}
+
+#[test]
+fn blocks_over_lines() {
+    let text = ["if x {", "    foo", "    bar", "}"];
+
+    assert_eq!(after(&text, "j", "diB"), (vec!["if x {".to_owned(), "}".to_owned()], "    foo\n    bar\n".to_owned()));
+    assert_eq!(after(&text, "j", "da{").0, vec!["if x "]);
+}
+
+#[test]
+fn tags() {
+    let text = ["<div><b>bold</b> text</div>"];
+
+    assert_eq!(after(&text, "fo", "dit").0, vec!["<div><b></b> text</div>"]);
+    assert_eq!(after(&text, "fo", "dat").0, vec!["<div> text</div>"]);
+    assert_eq!(after(&text, "fo", "d2it").0, vec!["<div></div>"]);
+    assert_eq!(after(&text, "fx", "yit").1, "<b>bold</b> text");
+    assert_eq!(after(&["<p>", "  <br/>a", "</p>"], "j", "dit").0, vec!["<p></p>"]);
+}
+
+#[test]
+fn objects_work_with_any_operator() {
+    assert_eq!(after(&["foo bar"], "w", "gUiw").0, vec!["foo BAR"]);
+    assert_eq!(after(&["a", "b", "", "c"], "", ">ip").0, vec!["        a", "        b", "", "c"]);
+
+    let mut ed = editor_with(&["(one two)"]);
+    input_text(&mut ed, "wci(x");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(lines(&ed), vec!["(x)"]);
+}
@@ -0,0 +1,137 @@
+extern crate king;
+
+use king::editor::Editor;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, run};
+
+#[test]
+fn undo_reverts_a_whole_insert_session() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "abc");
+    ed.handle_key(&Key::from(KeyCode::Backspace)).unwrap();
+    ed.handle_key(&Key::from(KeyCode::Enter)).unwrap();
+    input_text(&mut ed, "d");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    input_text(&mut ed, "u");
+
+    assert_eq!(ed.window().content_view().height(), 1);
+    assert_eq!(ed.window().content_view().line(0), "");
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn undo_steps_back_one_insert_session_at_a_time() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "12");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "o");
+    input_text(&mut ed, "34");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    input_text(&mut ed, "u");
+    assert_eq!(ed.window().content_view().height(), 1);
+    assert_eq!(ed.window().content_view().line(0), "12");
+    assert_eq!(ed.cursor(), (0, 1));
+
+    input_text(&mut ed, "u");
+    assert_eq!(ed.window().content_view().line(0), "");
+
+    input_text(&mut ed, "u");
+    assert_eq!(ed.prompt().text(), "Already at oldest change");
+}
+
+#[test]
+fn ctrl_r_redoes() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "12");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "O");
+    input_text(&mut ed, "34");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    input_text(&mut ed, "uu\u{12}");
+    assert_eq!(ed.window().content_view().height(), 1);
+    assert_eq!(ed.window().content_view().line(0), "12");
+
+    input_text(&mut ed, "\u{12}");
+    assert_eq!(ed.window().content_view().line(0), "34");
+    assert_eq!(ed.window().content_view().line(1), "12");
+
+    input_text(&mut ed, "\u{12}");
+    assert_eq!(ed.prompt().text(), "Already at newest change");
+}
+
+#[test]
+fn undo_and_redo_prompt_commands() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "😀a");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    run(&mut ed, ":undo").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "");
+
+    run(&mut ed, ":redo").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "😀a");
+}
+
+#[test]
+fn earlier_and_later_walk_across_branches() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "one");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "u");
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "two");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    run(&mut ed, ":earlier").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "one");
+
+    run(&mut ed, ":earlier 1").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "");
+
+    run(&mut ed, ":later 2").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "two");
+
+    run(&mut ed, ":earlier 5").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "");
+}
+
+#[test]
+fn later_with_a_huge_count_goes_to_the_newest_change() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "one");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    input_text(&mut ed, "a");
+    input_text(&mut ed, "two");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":earlier").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "one");
+
+    run(&mut ed, ":later 18446744073709551615").unwrap();
+    assert_eq!(ed.window().content_view().line(0), "onetwo");
+}
+
+#[test]
+fn earlier_with_invalid_count() {
+    let mut ed = Editor::new(10, 10);
+
+    input_text(&mut ed, ":earlier x");
+
+    assert!(ed.handle_key(&Key::from(KeyCode::Enter)).is_err());
+}
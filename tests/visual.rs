@@ -0,0 +1,206 @@
+extern crate king;
+
+use king::backend::Attributes;
+use king::editor::Editor;
+use king::headless::Headless;
+use king::input::{Key, KeyCode};
+use king::mode::Mode;
+use king::ui;
+
+mod common;
+use common::{input_text, editor_with, lines};
+
+/// The cells of screen row `y` drawn in reverse video, as text.
+fn reversed(ed: &Editor, y: usize) -> String {
+    let (height, width) = ed.size();
+    let mut screen = Headless::new(height, width);
+    ui::render(ed, &mut screen);
+
+    (0..width).map(|x| screen.cell(y, x))
+        .filter(|cell| cell.attributes() == Attributes::reverse())
+        .map(|cell| cell.grapheme().to_owned())
+        .collect()
+}
+
+#[test]
+fn visual_modes_start_and_end() {
+    let mut ed = editor_with(&["abc"]);
+
+    input_text(&mut ed, "v");
+    assert_eq!(ed.mode(), Mode::Visual);
+
+    input_text(&mut ed, "V");
+    assert_eq!(ed.mode(), Mode::VisualLine);
+
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    assert_eq!(ed.mode(), Mode::VisualBlock);
+
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    assert_eq!(ed.mode(), Mode::Normal);
+
+    input_text(&mut ed, "v");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(ed.mode(), Mode::Normal);
+}
+
+#[test]
+fn selections_are_drawn_in_reverse_video() {
+    let mut ed = editor_with(&["foo bar baz", "qux"]);
+
+    input_text(&mut ed, "wve");
+    assert_eq!(reversed(&ed, 0), "bar");
+
+    input_text(&mut ed, "V");
+    assert_eq!(reversed(&ed, 0), "foo bar baz");
+    assert_eq!(reversed(&ed, 1), "");
+
+    input_text(&mut ed, "v");
+    assert_eq!(reversed(&ed, 0), "bar");
+
+    input_text(&mut ed, "v");
+    assert_eq!(reversed(&ed, 0), "");
+}
+
+#[test]
+fn charwise_selection_spans_lines() {
+    let mut ed = editor_with(&["foo bar", "baz qux"]);
+
+    input_text(&mut ed, "wvjd");
+
+    assert_eq!(lines(&ed), vec!["foo ux"]);
+    assert_eq!(ed.register('"').unwrap(), "bar\nbaz q");
+    assert_eq!(ed.mode(), Mode::Normal);
+}
+
+#[test]
+fn swapping_the_ends() {
+    let mut ed = editor_with(&["one two three"]);
+
+    input_text(&mut ed, "wvlo");
+    assert_eq!(ed.cursor(), (0, 4));
+
+    input_text(&mut ed, "hy");
+    assert_eq!(ed.register('"').unwrap(), " tw");
+}
+
+#[test]
+fn linewise_operators() {
+    let mut ed = editor_with(&["a", "b", "c", "d"]);
+
+    input_text(&mut ed, "jVjd");
+    assert_eq!(lines(&ed), vec!["a", "d"]);
+    assert_eq!(ed.register('"').unwrap(), "b\nc\n");
+
+    input_text(&mut ed, "Vk>");
+    assert_eq!(lines(&ed), vec!["        a", "        d"]);
+
+    input_text(&mut ed, "vU");
+    assert_eq!(lines(&ed), vec!["        A", "        d"]);
+}
+
+#[test]
+fn change_selection() {
+    let mut ed = editor_with(&["foo bar"]);
+
+    input_text(&mut ed, "veclet");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    assert_eq!(lines(&ed), vec!["let bar"]);
+}
+
+#[test]
+fn text_objects_extend_the_selection() {
+    let mut ed = editor_with(&["f(a, b)", "", "x"]);
+
+    input_text(&mut ed, "fbvi(");
+    assert_eq!(reversed(&ed, 0), "a, b");
+
+    input_text(&mut ed, "ipy");
+    assert_eq!(ed.register('"').unwrap(), "f(a, b)\n");
+}
+
+#[test]
+fn blockwise_selection() {
+    let mut ed = editor_with(&["abcd", "efgh", "ijkl"]);
+
+    input_text(&mut ed, "l");
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    input_text(&mut ed, "jl");
+    assert_eq!(reversed(&ed, 0), "bc");
+    assert_eq!(reversed(&ed, 1), "fg");
+    assert_eq!(reversed(&ed, 2), "");
+
+    input_text(&mut ed, "d");
+    assert_eq!(lines(&ed), vec!["ad", "eh", "ijkl"]);
+    assert_eq!(ed.register('"').unwrap(), "bc\nfg");
+    assert_eq!(ed.cursor(), (0, 1));
+}
+
+#[test]
+fn blockwise_selection_follows_display_columns() {
+    let mut ed = editor_with(&["a日本b", "abcdef"]);
+
+    input_text(&mut ed, "l");
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    input_text(&mut ed, "j");
+    assert_eq!(reversed(&ed, 0), "日");
+    assert_eq!(reversed(&ed, 1), "bc");
+
+    input_text(&mut ed, "y");
+    assert_eq!(ed.register('"').unwrap(), "日\nbc");
+
+    input_text(&mut ed, "jlll");
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    input_text(&mut ed, "kU");
+    assert_eq!(lines(&ed), vec!["a日本b", "abcDEf"]);
+}
+
+#[test]
+fn blockwise_change_and_case() {
+    let mut ed = editor_with(&["abc", "def"]);
+
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    input_text(&mut ed, "j~");
+    assert_eq!(lines(&ed), vec!["Abc", "Def"]);
+
+    input_text(&mut ed, "l");
+    ed.handle_key(&Key::ctrl('v')).unwrap();
+    input_text(&mut ed, "jcX");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    assert_eq!(lines(&ed), vec!["AXc", "Df"]);
+}
+
+#[test]
+fn colon_works_on_the_selected_lines() {
+    let mut ed = editor_with(&["a", "b", "c", "d"]);
+
+    input_text(&mut ed, "jVj:");
+    assert_eq!(ed.mode(), Mode::Prompt);
+    assert_eq!(ed.prompt().command_text(), ":'<,'>");
+
+    input_text(&mut ed, "d\r");
+    assert_eq!(lines(&ed), vec!["a", "d"]);
+
+    input_text(&mut ed, ":'<,'>y\r");
+    assert_eq!(ed.register('"').unwrap(), "d\n");
+}
+
+#[test]
+fn ranges_are_refused_by_other_commands() {
+    let mut ed = editor_with(&["a"]);
+
+    input_text(&mut ed, "V");
+    input_text(&mut ed, ":");
+    input_text(&mut ed, "ls");
+
+    assert!(ed.handle_key(&Key::from(KeyCode::Enter)).is_err());
+}
+
+#[test]
+fn counts_move_the_cursor_in_visual_mode() {
+    let mut ed = editor_with(&["a b c d"]);
+
+    input_text(&mut ed, "v2wd");
+
+    assert_eq!(lines(&ed), vec![" d"]);
+}
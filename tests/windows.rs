@@ -0,0 +1,174 @@
+extern crate king;
+
+use king::editor::Editor;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, run};
+
+fn window_command(ed: &mut Editor, c: char) {
+    ed.handle_key(&Key::ctrl('w')).unwrap();
+    ed.handle_key(&Key::from(c)).unwrap();
+}
+
+fn areas(ed: &Editor) -> Vec<((usize, usize), (usize, usize))> {
+    ed.windows().iter().map(|window| (window.position(), window.size())).collect()
+}
+
+#[test]
+fn split_stacks_windows_with_status_lines() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+
+    assert_eq!(areas(&ed), vec![((0, 0), (4, 20)), ((5, 0), (3, 20))]);
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn vsplit_puts_windows_side_by_side() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":vsplit").unwrap();
+
+    assert_eq!(areas(&ed), vec![((0, 0), (8, 10)), ((0, 11), (8, 9))]);
+}
+
+#[test]
+fn split_with_a_filename_opens_it_in_the_new_window() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split tests/fixtures/file2").unwrap();
+
+    let filenames = ed.windows().iter().map(|window| window.filename()).collect::<Vec<_>>();
+    assert_eq!(filenames, vec![Some("tests/fixtures/file2".to_owned()), None]);
+}
+
+#[test]
+fn moving_between_windows() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    run(&mut ed, ":vsplit").unwrap();
+
+    window_command(&mut ed, 'j');
+    assert_eq!(ed.cursor(), (5, 0));
+
+    window_command(&mut ed, 'k');
+    window_command(&mut ed, 'l');
+    assert_eq!(ed.cursor(), (0, 11));
+
+    window_command(&mut ed, 'h');
+    assert_eq!(ed.cursor(), (0, 0));
+}
+
+#[test]
+fn windows_on_the_same_buffer_share_changes() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    input_text(&mut ed, "iabc");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+
+    let lines = ed.windows().iter().map(|window| window.content_view().line(0).into_owned()).collect::<Vec<_>>();
+    assert_eq!(lines, vec!["abc", "abc"]);
+}
+
+#[test]
+fn closing_a_window_gives_its_space_back() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    window_command(&mut ed, 'c');
+
+    assert_eq!(areas(&ed), vec![((0, 0), (9, 20))]);
+}
+
+#[test]
+fn the_last_window_cant_be_closed() {
+    let mut ed = Editor::new(10, 20);
+
+    let result = run(&mut ed, ":close");
+
+    assert!(result.is_err());
+    assert_eq!(ed.windows().len(), 1);
+}
+
+#[test]
+fn only_closes_the_other_windows() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    run(&mut ed, ":vsplit").unwrap();
+    window_command(&mut ed, 'j');
+    run(&mut ed, ":only").unwrap();
+
+    assert_eq!(areas(&ed), vec![((0, 0), (9, 20))]);
+}
+
+#[test]
+fn quit_closes_the_window_when_there_are_several() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    run(&mut ed, ":quit").unwrap();
+
+    assert!(ed.running());
+    assert_eq!(ed.windows().len(), 1);
+
+    run(&mut ed, ":quit").unwrap();
+
+    assert!(!ed.running());
+}
+
+#[test]
+fn resizing_windows() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    run(&mut ed, ":resize 2").unwrap();
+    assert_eq!(areas(&ed), vec![((0, 0), (2, 20)), ((3, 0), (5, 20))]);
+
+    run(&mut ed, ":resize +1").unwrap();
+    assert_eq!(areas(&ed), vec![((0, 0), (3, 20)), ((4, 0), (4, 20))]);
+
+    window_command(&mut ed, '-');
+    window_command(&mut ed, '-');
+    assert_eq!(areas(&ed), vec![((0, 0), (1, 20)), ((2, 0), (6, 20))]);
+
+    window_command(&mut ed, '=');
+    assert_eq!(areas(&ed), vec![((0, 0), (4, 20)), ((5, 0), (3, 20))]);
+}
+
+#[test]
+fn resizing_windows_vertically() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":vsplit").unwrap();
+    run(&mut ed, ":vertical resize 5").unwrap();
+
+    assert_eq!(areas(&ed), vec![((0, 0), (8, 5)), ((0, 6), (8, 14))]);
+}
+
+#[test]
+fn resizing_windows_as_large_as_possible() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    run(&mut ed, ":resize").unwrap();
+    assert_eq!(areas(&ed), vec![((0, 0), (6, 20)), ((7, 0), (1, 20))]);
+
+    run(&mut ed, ":vsplit").unwrap();
+    run(&mut ed, ":vertical resize").unwrap();
+    assert_eq!(areas(&ed), vec![((0, 0), (6, 18)), ((0, 19), (6, 1)), ((7, 0), (1, 20))]);
+}
+
+#[test]
+fn windows_cant_get_smaller_than_one_line() {
+    let mut ed = Editor::new(10, 20);
+
+    run(&mut ed, ":split").unwrap();
+    run(&mut ed, ":resize 0").unwrap();
+
+    assert_eq!(areas(&ed), vec![((0, 0), (1, 20)), ((2, 0), (6, 20))]);
+}
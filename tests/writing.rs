@@ -0,0 +1,179 @@
+extern crate king;
+
+use std::fs;
+use std::os::unix::fs::{symlink, PermissionsExt};
+
+use king::editor::Editor;
+use king::input::{Key, KeyCode};
+
+mod common;
+use common::{input_text, run, round_trip, esc};
+
+fn edit_and_write(filename: &str) {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    input_text(&mut ed, "i");
+    input_text(&mut ed, "new ");
+    ed.handle_key(&Key::from(KeyCode::Esc)).unwrap();
+    run(&mut ed, ":write").unwrap();
+
+    assert_eq!(ed.prompt().text(), "File written");
+}
+
+#[test]
+fn writing_replaces_the_contents() {
+    let filename = "target/writing_replaces_the_contents";
+    fs::write(filename, "line\n").unwrap();
+
+    edit_and_write(filename);
+
+    assert_eq!(fs::read_to_string(filename).unwrap(), "new line\n");
+}
+
+#[test]
+fn writing_creates_missing_files() {
+    let filename = "target/writing_creates_missing_files";
+    let _ = fs::remove_file(filename);
+
+    edit_and_write(filename);
+
+    assert_eq!(fs::read_to_string(filename).unwrap(), "new \n");
+}
+
+#[test]
+fn writing_ends_lines_typed_into_an_empty_file() {
+    let filename = "target/writing_ends_lines_typed_into_an_empty_file";
+    fs::write(filename, "").unwrap();
+
+    edit_and_write(filename);
+
+    assert_eq!(fs::read_to_string(filename).unwrap(), "new \n");
+}
+
+#[test]
+fn writing_preserves_the_file_mode() {
+    let filename = "target/writing_preserves_the_file_mode";
+    fs::write(filename, "line\n").unwrap();
+    fs::set_permissions(filename, fs::Permissions::from_mode(0o751)).unwrap();
+
+    edit_and_write(filename);
+
+    let mode = fs::metadata(filename).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o751);
+}
+
+#[test]
+fn writing_through_a_symlink_keeps_the_link() {
+    let target = "target/writing_through_a_symlink_target";
+    let link = "target/writing_through_a_symlink_link";
+    fs::write(target, "line\n").unwrap();
+    let _ = fs::remove_file(link);
+    symlink("writing_through_a_symlink_target", link).unwrap();
+
+    edit_and_write(link);
+
+    assert!(fs::symlink_metadata(link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(target).unwrap(), "new line\n");
+}
+
+#[test]
+fn writing_leaves_no_temporary_files_behind() {
+    let dir = "target/writing_leaves_no_temporary_files_behind";
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+
+    edit_and_write(&format!("{}/file", dir));
+
+    let entries = fs::read_dir(dir).unwrap().count();
+    assert_eq!(entries, 1);
+}
+
+#[test]
+fn failed_writes_leave_no_temporary_files_behind() {
+    let dir = "target/failed_writes_leave_no_temporary_files_behind";
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(format!("{}/directory", dir)).unwrap();
+
+    let mut ed = Editor::new(10, 10);
+    assert!(run(&mut ed, &format!(":write {}/directory", dir)).is_err());
+
+    let entries = fs::read_dir(dir).unwrap().count();
+    assert_eq!(entries, 1);
+}
+
+#[test]
+fn writing_keeps_dos_line_endings() {
+    round_trip("round_trip", "dos_file");
+}
+
+#[test]
+fn writing_keeps_a_missing_final_newline() {
+    round_trip("round_trip", "file_without_eol");
+}
+
+#[test]
+fn writing_keeps_the_bom() {
+    round_trip("round_trip", "file_with_bom");
+}
+
+#[test]
+fn dos_line_endings_are_not_part_of_the_text() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":edit tests/fixtures/dos_file").unwrap();
+
+    assert_eq!(ed.prompt().text(), "\"tests/fixtures/dos_file\" [dos]");
+    assert_eq!(ed.window().content_view().line(0), "line 1");
+}
+
+#[test]
+fn the_bom_is_not_part_of_the_text() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":edit tests/fixtures/file_with_bom").unwrap();
+
+    assert_eq!(ed.window().content_view().line(0), "line 1");
+}
+
+#[test]
+fn setting_the_file_format_converts_line_endings() {
+    let filename = "target/setting_the_file_format_converts_line_endings";
+    fs::copy("tests/fixtures/dos_file", filename).unwrap();
+
+    let mut ed = Editor::new(10, 10);
+    run(&mut ed, &format!(":edit {}", filename)).unwrap();
+    run(&mut ed, ":set fileformat=unix").unwrap();
+    run(&mut ed, ":write").unwrap();
+    assert_eq!(fs::read_to_string(filename).unwrap(), "line 1\nline 2\n");
+
+    run(&mut ed, ":set ff=dos").unwrap();
+    run(&mut ed, ":write").unwrap();
+    assert_eq!(fs::read_to_string(filename).unwrap(), "line 1\r\nline 2\r\n");
+
+    run(&mut ed, ":set ff").unwrap();
+    assert_eq!(ed.prompt().text(), "fileformat=dos");
+}
+
+#[test]
+fn setting_the_file_format_marks_the_buffer_modified() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":set fileformat=dos").unwrap();
+    input_text(&mut ed, ":quit");
+
+    assert!(ed.handle_key(&Key::from(KeyCode::Enter)).is_err());
+}
+
+#[test]
+fn undoing_an_edit_keeps_a_file_format_change_unsaved() {
+    let mut ed = Editor::new(10, 10);
+
+    run(&mut ed, ":set fileformat=dos").unwrap();
+    input_text(&mut ed, "ia");
+    esc(&mut ed);
+    input_text(&mut ed, "u");
+    input_text(&mut ed, ":quit");
+
+    assert!(ed.handle_key(&Key::from(KeyCode::Enter)).is_err());
+}